@@ -0,0 +1,164 @@
+//! Timestamped copies of the settings files, so a corrupted or wiped config can be restored
+//! instead of set up again.
+//!
+//! A copy is taken when loading would rewrite a file, like after an update adds settings or
+//! when it no longer parses, and before the first change to the settings in a while.
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use directories::ProjectDirs;
+
+/// Copies kept per profile, the oldest are removed.
+const KEEP: usize = 20;
+/// Changes this soon after the last copy don't get their own, moving a slider saves every step.
+const MIN_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Newest copy per file name prefix, so throttled changes don't list the folder every time.
+static NEWEST: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+fn newest_known(prefix: &str) -> Option<u64> {
+    let newest = NEWEST.lock().unwrap_or_else(|e| e.into_inner());
+    newest.iter().find(|(p, _)| p == prefix).map(|(_, t)| *t)
+}
+
+fn remember_newest(prefix: &str, created: u64) {
+    let mut newest = NEWEST.lock().unwrap_or_else(|e| e.into_inner());
+    newest.retain(|(p, _)| p != prefix);
+    newest.push((prefix.to_owned(), created));
+}
+
+fn folder() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "SlimeVR Wrangler").map(|pd| pd.config_dir().join("backups"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// File names start like the settings file they are a copy of.
+fn prefix(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("config-{profile}-"),
+        None => "config-".into(),
+    }
+}
+
+/// A copy of the settings file of one profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    /// Seconds since the unix epoch.
+    pub created: u64,
+    pub path: PathBuf,
+}
+impl Display for Backup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let age = now().saturating_sub(self.created);
+        match age {
+            0..=59 => write!(f, "Just now"),
+            60..=3599 => write!(f, "{} min ago", age / 60),
+            3600..=86399 => write!(f, "{} h ago", age / 3600),
+            _ => write!(f, "{} days ago", age / 86400),
+        }
+    }
+}
+
+/// Copies of the settings file of this profile, newest first.
+pub fn list(profile: Option<&str>) -> Vec<Backup> {
+    folder().map_or(vec![], |dir| list_in(&dir, profile))
+}
+
+fn list_in(dir: &Path, profile: Option<&str>) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let prefix = prefix(profile);
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            // The timestamp is all digits, which also keeps "config-" from matching profiles.
+            let created = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".json")?
+                .parse()
+                .ok()?;
+            Some(Backup {
+                created,
+                path: entry.path(),
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.created.cmp(&a.created));
+    backups
+}
+
+/// Copies the settings file as it is now, and removes the oldest copies past [`KEEP`]. With
+/// `throttle`, nothing is copied if the last copy is recent.
+pub(crate) fn take(profile: Option<&str>, throttle: bool) -> Result<(), String> {
+    let Some(file) = crate::settings::file_name(profile).filter(|f| f.exists()) else {
+        return Ok(());
+    };
+    let dir = folder().ok_or("no config folder")?;
+    let prefix = prefix(profile);
+    let created = now();
+    let recent = |newest: u64| created.saturating_sub(newest) < MIN_INTERVAL.as_secs();
+    if throttle && newest_known(&prefix).map_or(false, recent) {
+        return Ok(());
+    }
+    let existing = list(profile);
+    if let Some(newest) = existing.first() {
+        remember_newest(&prefix, newest.created);
+        if throttle && recent(newest.created) {
+            return Ok(());
+        }
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{prefix}{created}.json"));
+    fs::copy(file, path).map_err(|e| e.to_string())?;
+    remember_newest(&prefix, created);
+    for old in existing.iter().skip(KEEP - 1) {
+        fs::remove_file(&old.path).ok();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::list_in;
+
+    #[test]
+    fn list() {
+        let dir = std::env::temp_dir().join(format!("wrangler-backups-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "config-100.json",
+            "config-300.json",
+            "config-200.json",
+            "config-player2-400.json",
+            "config-player2-x.json",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        let created = |profile| {
+            list_in(&dir, profile)
+                .into_iter()
+                .map(|b| b.created)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(created(None), [300, 200, 100]);
+        assert_eq!(created(Some("player2")), [400]);
+        assert_eq!(created(Some("player3")), [] as [u64; 0]);
+        fs::remove_dir_all(dir).ok();
+    }
+}
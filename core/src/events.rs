@@ -0,0 +1,127 @@
+//! Changes from every part of the app, for frontends and integrations that react to them
+//! instead of polling the [`Snapshot`](crate::joycon::Snapshot).
+//!
+//! The bus is created with the [`Wrapper`](crate::joycon::Wrapper) and reached from any thread
+//! through [`Remote::events`](crate::joycon::Remote::events). Subscribe with
+//! [`Bus::subscribe`], each receiver gets every event published after it subscribed.
+//! Rotations come on their own stream, [`Bus::subscribe_rotations`].
+
+use std::{sync::Arc, time::Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::joycon::{ServerStatus, Snapshot, Status};
+
+/// Events a slow receiver can fall behind by, before it misses the oldest ones and gets
+/// [`broadcast::error::RecvError::Lagged`].
+const CAPACITY: usize = 256;
+/// About a second of rotations from a dozen controllers.
+const ROTATION_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Event {
+    /// A tracker showed up, when its controller connects or a profile switch claims it.
+    DeviceAdded(Status),
+    /// The status, battery, name, tracker id or pause of a tracker changed. Rotation and
+    /// report rate change all the time and are left to the snapshot.
+    DeviceChanged(Status),
+    DeviceRemoved {
+        serial_number: String,
+    },
+    ServerChanged(ServerStatus),
+    /// Sending to the server failed or works again.
+    SocketError(Option<String>),
+    Paused(bool),
+    /// The settings were changed and saved, or another profile was loaded.
+    SettingsChanged,
+    /// Wrangler should quit, SlimeVR Server has been gone for as long as set in the settings.
+    /// Sending is paused already, quitting is up to the frontend, the core never ends the
+    /// process it runs in.
+    Quit,
+}
+
+/// A tracker's rotation after an IMU report, at the rate the controller sends them. Kept out
+/// of [`Event`], there are far too many for the receivers that only want changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    pub serial_number: Arc<str>,
+    /// Roll, pitch and yaw in degrees, like [`Status::rotation`].
+    pub rotation: (f64, f64, f64),
+    /// When the report was handled.
+    pub at: Instant,
+}
+
+#[derive(Clone)]
+pub struct Bus {
+    events: broadcast::Sender<Event>,
+    rotations: broadcast::Sender<Rotation>,
+}
+impl Default for Bus {
+    fn default() -> Self {
+        Self {
+            events: broadcast::channel(CAPACITY).0,
+            rotations: broadcast::channel(ROTATION_CAPACITY).0,
+        }
+    }
+}
+impl Bus {
+    pub fn publish(&self, event: Event) {
+        // Nobody listening is fine.
+        self.events.send(event).ok();
+    }
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+    pub fn publish_rotation(&self, rotation: Rotation) {
+        self.rotations.send(rotation).ok();
+    }
+    pub fn subscribe_rotations(&self) -> broadcast::Receiver<Rotation> {
+        self.rotations.subscribe()
+    }
+    /// Publishes what changed between two snapshots of the communication thread.
+    pub fn publish_changes(&self, old: &Snapshot, new: &Snapshot) {
+        if old.server != new.server {
+            self.publish(Event::ServerChanged(new.server));
+        }
+        if old.socket_error != new.socket_error {
+            self.publish(Event::SocketError(new.socket_error.clone()));
+        }
+        if old.paused != new.paused {
+            self.publish(Event::Paused(new.paused));
+        }
+        for status in &new.trackers {
+            match old
+                .trackers
+                .iter()
+                .find(|s| s.serial_number == status.serial_number)
+            {
+                None => self.publish(Event::DeviceAdded(status.clone())),
+                Some(before) if !same_state(before, status) => {
+                    self.publish(Event::DeviceChanged(status.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for status in &old.trackers {
+            if !new
+                .trackers
+                .iter()
+                .any(|s| s.serial_number == status.serial_number)
+            {
+                self.publish(Event::DeviceRemoved {
+                    serial_number: status.serial_number.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn same_state(a: &Status, b: &Status) -> bool {
+    a.status == b.status
+        && a.battery == b.battery
+        && a.name == b.name
+        && a.tracker_id == b.tracker_id
+        && a.paused == b.paused
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use super::imu::JoyconAxisData;
+
+/// A controller axis, possibly reversed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    #[serde(rename = "x")]
+    X,
+    #[serde(rename = "y")]
+    Y,
+    #[serde(rename = "z")]
+    Z,
+    #[serde(rename = "-x")]
+    NegX,
+    #[serde(rename = "-y")]
+    NegY,
+    #[serde(rename = "-z")]
+    NegZ,
+}
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X | Axis::NegX => 0,
+            Axis::Y | Axis::NegY => 1,
+            Axis::Z | Axis::NegZ => 2,
+        }
+    }
+    fn sign(self) -> f64 {
+        match self {
+            Axis::X | Axis::Y | Axis::Z => 1.0,
+            Axis::NegX | Axis::NegY | Axis::NegZ => -1.0,
+        }
+    }
+}
+
+/// Which controller axis becomes roll (x), pitch (y) and yaw (z) of the tracker.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Axes {
+    pub x: Axis,
+    pub y: Axis,
+    pub z: Axis,
+}
+impl Default for Axes {
+    fn default() -> Self {
+        Self {
+            x: Axis::X,
+            y: Axis::Y,
+            z: Axis::Z,
+        }
+    }
+}
+impl Axes {
+    /// Every controller axis has to be used exactly once.
+    pub fn is_valid(&self) -> bool {
+        let (x, y, z) = (self.x.index(), self.y.index(), self.z.index());
+        x != y && y != z && x != z
+    }
+
+    /// -1 if the mapping mirrors, which turns a right handed frame into a left handed one.
+    fn handedness(&self) -> f64 {
+        let even = matches!(
+            (self.x.index(), self.y.index(), self.z.index()),
+            (0, 1, 2) | (1, 2, 0) | (2, 0, 1)
+        );
+        let parity = if even { 1.0 } else { -1.0 };
+        parity * self.x.sign() * self.y.sign() * self.z.sign()
+    }
+
+    /// Remaps a sample, optionally mirrored along x to flip the handedness. Angular velocity
+    /// turns the other way in a mirror, so the gyro gets an extra sign flip then.
+    pub fn apply(&self, flip_handedness: bool, frame: JoyconAxisData) -> JoyconAxisData {
+        let accel = [frame.accel_x, frame.accel_y, frame.accel_z];
+        let gyro = [frame.gyro_x, frame.gyro_y, frame.gyro_z];
+        let axes = [self.x, self.y, self.z];
+        let mut accel = axes.map(|a| a.sign() * accel[a.index()]);
+        let mut gyro = axes.map(|a| a.sign() * gyro[a.index()]);
+        let mut handedness = self.handedness();
+        if flip_handedness {
+            accel[0] = -accel[0];
+            gyro[0] = -gyro[0];
+            handedness = -handedness;
+        }
+        if handedness < 0.0 {
+            gyro = gyro.map(|g| -g);
+        }
+        JoyconAxisData {
+            accel_x: accel[0],
+            accel_y: accel[1],
+            accel_z: accel[2],
+            gyro_x: gyro[0],
+            gyro_y: gyro[1],
+            gyro_z: gyro[2],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: JoyconAxisData = JoyconAxisData {
+        accel_x: 1.0,
+        accel_y: 2.0,
+        accel_z: 3.0,
+        gyro_x: 4.0,
+        gyro_y: 5.0,
+        gyro_z: 6.0,
+    };
+
+    fn axes(x: Axis, y: Axis, z: Axis) -> Axes {
+        Axes { x, y, z }
+    }
+    fn values(frame: JoyconAxisData) -> [f64; 6] {
+        [
+            frame.accel_x,
+            frame.accel_y,
+            frame.accel_z,
+            frame.gyro_x,
+            frame.gyro_y,
+            frame.gyro_z,
+        ]
+    }
+    #[test]
+    fn default_keeps_frame() {
+        assert_eq!(Axes::default().apply(false, FRAME), FRAME);
+    }
+    #[test]
+    fn rotate() {
+        let out = axes(Axis::Y, Axis::NegX, Axis::Z).apply(false, FRAME);
+        assert_eq!(values(out), [2.0, -1.0, 3.0, 5.0, -4.0, 6.0]);
+    }
+    #[test]
+    fn mirror_turns_gyro_around() {
+        let out = axes(Axis::NegX, Axis::Y, Axis::Z).apply(false, FRAME);
+        assert_eq!(values(out), [-1.0, 2.0, 3.0, 4.0, -5.0, -6.0]);
+        let out = Axes::default().apply(true, FRAME);
+        assert_eq!(values(out), [-1.0, 2.0, 3.0, 4.0, -5.0, -6.0]);
+        let out = axes(Axis::NegX, Axis::Y, Axis::Z).apply(true, FRAME);
+        assert_eq!(values(out), values(FRAME));
+    }
+    #[test]
+    fn valid_only_with_each_axis_once() {
+        assert!(axes(Axis::Z, Axis::NegX, Axis::Y).is_valid());
+        assert!(!axes(Axis::X, Axis::NegX, Axis::Z).is_valid());
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::HashSet,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use windows::{
+    core::GUID,
+    Devices::Bluetooth::{
+        Advertisement::{
+            BluetoothLEAdvertisementReceivedEventArgs, BluetoothLEAdvertisementWatcher,
+            BluetoothLEScanningMode,
+        },
+        BluetoothConnectionStatus, BluetoothLEDevice,
+        GenericAttributeProfile::{
+            GattCharacteristic, GattClientCharacteristicConfigurationDescriptorValue,
+            GattCommunicationStatus, GattValueChangedEventArgs,
+        },
+    },
+    Foundation::TypedEventHandler,
+    Storage::Streams::{DataReader, DataWriter, IBuffer},
+};
+
+use super::{
+    communication::{ChannelData, DeviceSender},
+    imu::JoyconAxisData,
+    serial::Sensor,
+    ChannelInfo, JoyconDesign, JoyconDesignType,
+};
+
+/// What a notification carried.
+pub enum Report {
+    /// One sample, resampled to the rate the fusion expects like serial boards.
+    Imu(JoyconAxisData),
+    /// A button press or a battery level.
+    Info(ChannelInfo),
+}
+
+/// A Bluetooth LE motion device type: how to recognize its advertisement and read its reports.
+/// Add new devices to `PROTOCOLS`.
+pub trait BleProtocol: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Whether an advertisement is from this device, by its local name or its manufacturer
+    /// data as Bluetooth SIG company identifier and data.
+    fn matches(&self, local_name: &str, manufacturer_data: &[(u16, Vec<u8>)]) -> bool;
+    fn service(&self) -> GUID;
+    /// Characteristic sending input reports as notifications.
+    fn characteristic(&self) -> GUID;
+    /// Characteristic and commands written to it after subscribing, like a faster report rate.
+    fn setup(&self) -> Option<(GUID, &'static [&'static [u8]])> {
+        None
+    }
+    fn design(&self) -> JoyconDesign;
+    fn parse(&self, report: &[u8]) -> Option<Report>;
+}
+
+/// WitMotion WT901BLE IMU pucks. Each notification is a 0x55 0x61 header and little endian
+/// accelerometer, gyro and angle, three axes each. The angles are the puck's own fusion and
+/// aren't used.
+struct WitMotion;
+
+/// Sample from a WitMotion notification, with accelerometer in G and gyro in radians/s.
+fn witmotion_sample(report: &[u8]) -> Option<JoyconAxisData> {
+    if report.len() < 20 || report[..2] != [0x55, 0x61] {
+        return None;
+    }
+    let value = |i: usize| f64::from(i16::from_le_bytes([report[2 + i * 2], report[3 + i * 2]]));
+    // Full scale is ±16 G and ±2000 degrees/s.
+    let accel = |i| value(i) / 32768.0 * 16.0;
+    let gyro = |i| (value(i) / 32768.0 * 2000.0).to_radians();
+    Some(JoyconAxisData {
+        accel_x: accel(0),
+        accel_y: accel(1),
+        accel_z: accel(2),
+        gyro_x: gyro(3),
+        gyro_y: gyro(4),
+        gyro_z: gyro(5),
+    })
+}
+
+impl BleProtocol for WitMotion {
+    fn name(&self) -> &'static str {
+        "WitMotion"
+    }
+    fn matches(&self, local_name: &str, _: &[(u16, Vec<u8>)]) -> bool {
+        local_name.starts_with("WT901BLE")
+    }
+    fn service(&self) -> GUID {
+        GUID::from_u128(0x0000ffe5_0000_1000_8000_00805f9a34fb)
+    }
+    fn characteristic(&self) -> GUID {
+        GUID::from_u128(0x0000ffe4_0000_1000_8000_00805f9a34fb)
+    }
+    /// Unlocks the configuration and sets the output rate to 100 Hz, from 10 Hz.
+    fn setup(&self) -> Option<(GUID, &'static [&'static [u8]])> {
+        Some((
+            GUID::from_u128(0x0000ffe9_0000_1000_8000_00805f9a34fb),
+            &[
+                &[0xFF, 0xAA, 0x69, 0x88, 0xB5],
+                &[0xFF, 0xAA, 0x03, 0x09, 0x00],
+            ],
+        ))
+    }
+    fn design(&self) -> JoyconDesign {
+        JoyconDesign {
+            color: "#1e90ff".into(),
+            design_type: JoyconDesignType::Pro,
+        }
+    }
+    fn parse(&self, report: &[u8]) -> Option<Report> {
+        witmotion_sample(report).map(Report::Imu)
+    }
+}
+
+const PROTOCOLS: &[&dyn BleProtocol] = &[&WitMotion];
+
+fn read_buffer(buffer: &IBuffer) -> windows::core::Result<Vec<u8>> {
+    let reader = DataReader::FromBuffer(buffer)?;
+    let mut data = vec![0; reader.UnconsumedBufferLength()? as usize];
+    reader.ReadBytes(&mut data)?;
+    Ok(data)
+}
+
+fn find_protocol(
+    args: &BluetoothLEAdvertisementReceivedEventArgs,
+) -> Option<&'static dyn BleProtocol> {
+    let advertisement = args.Advertisement().ok()?;
+    let local_name = advertisement
+        .LocalName()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let manufacturer_data: Vec<(u16, Vec<u8>)> = advertisement
+        .ManufacturerData()
+        .ok()?
+        .into_iter()
+        .filter_map(|entry| {
+            Some((
+                entry.CompanyId().ok()?,
+                read_buffer(&entry.Data().ok()?).ok()?,
+            ))
+        })
+        .collect();
+    PROTOCOLS
+        .iter()
+        .find(|p| p.matches(&local_name, &manufacturer_data))
+        .copied()
+}
+
+fn characteristic(
+    device: &BluetoothLEDevice,
+    service: GUID,
+    characteristic: GUID,
+) -> windows::core::Result<GattCharacteristic> {
+    let services = device
+        .GetGattServicesForUuidAsync(service)?
+        .get()?
+        .Services()?;
+    services
+        .GetAt(0)?
+        .GetCharacteristicsForUuidAsync(characteristic)?
+        .get()?
+        .Characteristics()?
+        .GetAt(0)
+}
+
+fn write(characteristic: &GattCharacteristic, data: &[u8]) -> Result<(), String> {
+    let status = DataWriter::new()
+        .and_then(|writer| {
+            writer.WriteBytes(data)?;
+            characteristic
+                .WriteValueAsync(&writer.DetachBuffer()?)?
+                .get()
+        })
+        .map_err(|e| e.to_string())?;
+    if status != GattCommunicationStatus::Success {
+        return Err(format!("{status:?}"));
+    }
+    Ok(())
+}
+
+fn connect(
+    address: u64,
+    protocol: &'static dyn BleProtocol,
+    tx: DeviceSender,
+) -> windows::core::Result<()> {
+    let serial_number: Arc<str> = format!("{address:012x}").into();
+    let device = BluetoothLEDevice::FromBluetoothAddressAsync(address)?.get()?;
+    let characteristic = characteristic(&device, protocol.service(), protocol.characteristic())?;
+
+    let (disconnect_tx, disconnect_rx) = mpsc::channel();
+    device.ConnectionStatusChanged(&TypedEventHandler::new(
+        move |device: &Option<BluetoothLEDevice>, _| {
+            if let Some(device) = device {
+                if device.ConnectionStatus()? == BluetoothConnectionStatus::Disconnected {
+                    disconnect_tx.send(()).ok();
+                }
+            }
+            Ok(())
+        },
+    ))?;
+
+    {
+        let tx = tx.clone();
+        let serial_number = serial_number.clone();
+        let sensor = Mutex::new(Sensor::new(serial_number.clone()));
+        characteristic.ValueChanged(&TypedEventHandler::new(
+            move |_, args: &Option<GattValueChangedEventArgs>| {
+                if let Some(args) = args {
+                    let report = read_buffer(&args.CharacteristicValue()?)?;
+                    match protocol.parse(&report) {
+                        Some(Report::Imu(sample)) => sensor.lock().unwrap().push(&tx, sample),
+                        Some(Report::Info(info)) => {
+                            tx.send(ChannelData::new(serial_number.clone(), info)).ok();
+                        }
+                        None => {}
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+    }
+    let status = characteristic
+        .WriteClientCharacteristicConfigurationDescriptorAsync(
+            GattClientCharacteristicConfigurationDescriptorValue::Notify,
+        )?
+        .get()?;
+    if status != GattCommunicationStatus::Success {
+        println!(
+            "[INFO] BLE {} {serial_number}: could not subscribe to reports.",
+            protocol.name()
+        );
+        return Ok(());
+    }
+    if let Some((uuid, commands)) = protocol.setup() {
+        let written = characteristic(&device, protocol.service(), uuid)
+            .map_err(|e| e.to_string())
+            .and_then(|setup| commands.iter().try_for_each(|c| write(&setup, c)));
+        // Still usable at the rate it was left at.
+        if let Err(e) = written {
+            println!(
+                "[INFO] BLE {} {serial_number}: setup failed: {e}",
+                protocol.name()
+            );
+        }
+    }
+
+    tx.send(ChannelData::new(
+        serial_number.clone(),
+        ChannelInfo::Connected(protocol.design()),
+    ))
+    .ok();
+    // Keep the device and characteristic alive until Windows reports a disconnect.
+    disconnect_rx.recv().ok();
+    tx.send(ChannelData::new(serial_number, ChannelInfo::Disconnected))
+        .ok();
+    drop((characteristic, device));
+    Ok(())
+}
+
+/// Scans for Bluetooth LE motion devices and reads them alongside the hidapi integration.
+pub fn spawn_thread(tx: DeviceSender) {
+    let Ok(watcher) = BluetoothLEAdvertisementWatcher::new() else {
+        println!("[INFO] Bluetooth LE is not available, skipping BLE devices.");
+        return;
+    };
+    let connected = Arc::new(Mutex::new(HashSet::new()));
+    let handler = TypedEventHandler::new(
+        move |_, args: &Option<BluetoothLEAdvertisementReceivedEventArgs>| {
+            let Some(args) = args else {
+                return Ok(());
+            };
+            let Some(protocol) = find_protocol(args) else {
+                return Ok(());
+            };
+            let address = args.BluetoothAddress()?;
+            if !connected.lock().unwrap().insert(address) {
+                return Ok(());
+            }
+            let tx = tx.clone();
+            let connected = connected.clone();
+            thread::spawn(move || {
+                if let Err(e) = connect(address, protocol, tx) {
+                    println!("[INFO] BLE {}: {e}", protocol.name());
+                }
+                connected.lock().unwrap().remove(&address);
+            });
+            Ok(())
+        },
+    );
+    let started = watcher
+        .SetScanningMode(BluetoothLEScanningMode::Active)
+        .and_then(|_| watcher.Received(&handler))
+        .and_then(|_| watcher.Start());
+    if let Err(e) = started {
+        println!("\x1b[0;31m[ERROR]\x1b[0m Could not scan for BLE devices: {e}");
+        return;
+    }
+    // The watcher stops scanning when dropped.
+    loop {
+        thread::sleep(Duration::from_secs(60));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::witmotion_sample;
+
+    #[test]
+    fn witmotion() {
+        let mut report = vec![0x55, 0x61];
+        for value in [2048i16, -2048, 0, 16384, 0, -16384, 100, 200, 300] {
+            report.extend(value.to_le_bytes());
+        }
+        let sample = witmotion_sample(&report).unwrap();
+        assert_eq!(
+            (sample.accel_x, sample.accel_y, sample.accel_z),
+            (1.0, -1.0, 0.0)
+        );
+        assert!((sample.gyro_x - 1000f64.to_radians()).abs() < 1e-9);
+        assert!((sample.gyro_z + 1000f64.to_radians()).abs() < 1e-9);
+    }
+    #[test]
+    fn witmotion_other_packets() {
+        assert!(witmotion_sample(&[0x55, 0x71, 0, 0]).is_none());
+        assert!(witmotion_sample(&[0x55, 0x61, 0, 0]).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+    X,
+    Y,
+    L,
+    R,
+    ZL,
+    ZR,
+    SL,
+    SR,
+    Minus,
+    Plus,
+    LStick,
+    RStick,
+    Home,
+    Capture,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonAction {
+    YawReset,
+    /// Pauses or resumes sending this controller's tracker.
+    Pause,
+}
+
+/// Buttons that trigger an action when all of them are held together for `hold_ms`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ButtonMapping {
+    pub buttons: Vec<Button>,
+    pub action: ButtonAction,
+    #[serde(default)]
+    pub hold_ms: u32,
+}
+impl ButtonMapping {
+    fn new(buttons: &[Button], action: ButtonAction, hold_ms: u32) -> Self {
+        Self {
+            buttons: buttons.to_vec(),
+            action,
+            hold_ms,
+        }
+    }
+
+    /// Up or B for yaw reset, holding SL and SR, or L and R on a Pro Controller, to pause.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new(&[Button::Up], ButtonAction::YawReset, 0),
+            Self::new(&[Button::B], ButtonAction::YawReset, 0),
+            Self::new(&[Button::SL, Button::SR], ButtonAction::Pause, 1500),
+            Self::new(&[Button::L, Button::R], ButtonAction::Pause, 1500),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Held {
+    since: Option<Instant>,
+    fired: bool,
+}
+
+/// Which mappings of one controller are held, so each fires once per press.
+#[derive(Default)]
+pub struct Chords(Vec<Held>);
+impl Chords {
+    /// Actions triggered by the buttons held now. A held chord takes its buttons from
+    /// mappings with fewer buttons, so pressing it doesn't also trigger those.
+    pub fn update(
+        &mut self,
+        mappings: &[ButtonMapping],
+        held: impl Fn(Button) -> bool,
+    ) -> Vec<ButtonAction> {
+        if self.0.len() != mappings.len() {
+            self.0 = vec![Held::default(); mappings.len()];
+        }
+        let now = Instant::now();
+        let mut order: Vec<usize> = (0..mappings.len()).collect();
+        order.sort_by_key(|&i| Reverse(mappings[i].buttons.len()));
+        let mut claimed = vec![];
+        let mut actions = vec![];
+        for i in order {
+            let mapping = &mappings[i];
+            let state = &mut self.0[i];
+            let down = !mapping.buttons.is_empty()
+                && mapping
+                    .buttons
+                    .iter()
+                    .all(|b| held(*b) && !claimed.contains(b));
+            if !down {
+                *state = Held::default();
+                continue;
+            }
+            claimed.extend(&mapping.buttons);
+            let since = *state.since.get_or_insert(now);
+            let hold = Duration::from_millis(mapping.hold_ms.into());
+            if !state.fired && now.duration_since(since) >= hold {
+                state.fired = true;
+                actions.push(mapping.action);
+            }
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_per_press() {
+        let mappings = [ButtonMapping::new(&[Button::Up], ButtonAction::YawReset, 0)];
+        let mut chords = Chords::default();
+        assert_eq!(
+            chords.update(&mappings, |b| b == Button::Up),
+            [ButtonAction::YawReset]
+        );
+        assert!(chords.update(&mappings, |b| b == Button::Up).is_empty());
+        assert!(chords.update(&mappings, |_| false).is_empty());
+        assert_eq!(
+            chords.update(&mappings, |b| b == Button::Up),
+            [ButtonAction::YawReset]
+        );
+    }
+    #[test]
+    fn chord_takes_its_buttons() {
+        let mappings = [
+            ButtonMapping::new(&[Button::L], ButtonAction::YawReset, 0),
+            ButtonMapping::new(&[Button::L, Button::R], ButtonAction::Pause, 0),
+        ];
+        let mut chords = Chords::default();
+        let held = |b: Button| b == Button::L || b == Button::R;
+        assert_eq!(chords.update(&mappings, held), [ButtonAction::Pause]);
+        assert_eq!(
+            chords.update(&mappings, |b| b == Button::L),
+            [ButtonAction::YawReset]
+        );
+    }
+    #[test]
+    fn waits_for_hold() {
+        let mappings = ButtonMapping::defaults();
+        let mut chords = Chords::default();
+        let held = |b: Button| b == Button::SL || b == Button::SR;
+        assert!(chords.update(&mappings, held).is_empty());
+    }
+    #[test]
+    fn empty_mapping_never_fires() {
+        let mappings = [ButtonMapping::new(&[], ButtonAction::YawReset, 0)];
+        assert!(Chords::default().update(&mappings, |_| true).is_empty());
+    }
+}
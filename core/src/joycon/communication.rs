@@ -0,0 +1,1588 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    io,
+    net::{self, IpAddr, SocketAddr},
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+use nalgebra::{Unit, UnitQuaternion, Vector3};
+use protocol::deku::{
+    bitvec::{BitVec, Msb0},
+    DekuContainerRead, DekuContainerWrite, DekuWrite,
+};
+use protocol::PacketType;
+use serde::Serialize;
+use tokio::{
+    net::UdpSocket,
+    sync::{mpsc::UnboundedReceiver, watch, Notify},
+    time::{interval, MissedTickBehavior},
+};
+
+use super::{
+    imu::{Imu, JoyconAxisData},
+    is_simulated,
+    pipeline::{scale_about_axis, SampleSettings, Twist},
+    raw_log::RawLog,
+    rerun_viewer::Viewer,
+    script::Script,
+    stick::Sticks,
+    JoyconDesign, RotationStage, SampleStage,
+};
+use crate::events::{Bus, Event, Rotation};
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+pub enum Battery {
+    Empty,
+    Critical,
+    Low,
+    Medium,
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Status {
+    pub rotation: (f64, f64, f64),
+    /// Last rotation sent to the server as w, x, y, z, or the fused one before anything was
+    /// sent. For comparing with what the server shows.
+    pub quaternion: [f64; 4],
+    pub design: JoyconDesign,
+    pub serial_number: String,
+    /// The alias set by the user, or the serial number.
+    pub name: String,
+    /// Sensor id the tracker is sent to the server with.
+    pub tracker_id: u8,
+    pub battery: Battery,
+    pub status: DeviceStatus,
+    pub sticks: Sticks,
+    /// IMU reports received in the last second, about 66 for a healthy connection.
+    pub report_rate: usize,
+    /// Seconds since the controller connected, `None` while disconnected.
+    pub uptime: Option<u64>,
+    /// Seconds since the IMU last stalled, `None` if it hasn't since connecting.
+    pub since_stall: Option<u64>,
+    /// Paused on the controller itself, by holding SL and SR.
+    pub paused: bool,
+    /// Average gyro reading over about the last second in degrees/s, after removing the gyro
+    /// bias set for it. How fast the tracker would drift, while the controller lies still.
+    pub gyro_average: (f64, f64, f64),
+    /// Last gyro reading as the controller sent it, in degrees/s, before any pipeline stage.
+    pub raw_gyro: (f64, f64, f64),
+    /// Last accelerometer reading as the controller sent it, in G.
+    pub raw_accel: (f64, f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum DeviceStatus {
+    Healthy,
+    LaggyIMU,
+    NoIMU,
+    /// Connected, but didn't acknowledge the commands that start the IMU.
+    SetupFailed,
+    /// Only speaks the simple HID report mode, so only its buttons work.
+    ButtonsOnly,
+    /// Found, but couldn't be opened. Opening is retried.
+    AccessDenied,
+    Disconnected,
+}
+impl DeviceStatus {
+    /// What the user can do about a status that isn't healthy.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            DeviceStatus::SetupFailed => {
+                Some("Remove the controller in your bluetooth settings and pair it again.")
+            }
+            DeviceStatus::ButtonsOnly => Some(
+                "This controller doesn't send motion data, many third-party controllers don't. \
+                Its buttons still work for yaw reset.",
+            ),
+            DeviceStatus::AccessDenied => Some(
+                "Another app may be using this controller. Close BetterJoy, DS4Windows or Steam, \
+                Wrangler keeps trying to open it.",
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DeviceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DeviceStatus::Healthy => "Healthy",
+            DeviceStatus::LaggyIMU => "Laggy IMU",
+            DeviceStatus::NoIMU => "No IMU",
+            DeviceStatus::SetupFailed => "Setup failed",
+            DeviceStatus::ButtonsOnly => "No IMU, buttons only",
+            DeviceStatus::AccessDenied => "Access denied",
+            DeviceStatus::Disconnected => "Disconnected",
+        })
+    }
+}
+
+/// Moves from the previously sent rotation to the newest one over one report interval, so
+/// rotations can be sent at a steady rate higher than the controller's. Adds one report
+/// interval of latency.
+#[derive(Debug, Clone, Copy)]
+struct Interpolation {
+    from: UnitQuaternion<f64>,
+    to: UnitQuaternion<f64>,
+    since: Instant,
+    period: Duration,
+}
+impl Interpolation {
+    fn new(rotation: UnitQuaternion<f64>) -> Self {
+        Self {
+            from: rotation,
+            to: rotation,
+            since: Instant::now(),
+            period: Duration::from_millis(15),
+        }
+    }
+    fn push(&mut self, rotation: UnitQuaternion<f64>) {
+        let now = Instant::now();
+        // Starting from where the output is now instead of the last target avoids a jump when
+        // a report arrives early.
+        self.from = self.at(now);
+        self.to = rotation;
+        self.period = now
+            .duration_since(self.since)
+            .clamp(Duration::from_millis(5), Duration::from_millis(50));
+        self.since = now;
+    }
+    fn at(&self, now: Instant) -> UnitQuaternion<f64> {
+        let t = now.duration_since(self.since).as_secs_f64() / self.period.as_secs_f64();
+        self.from
+            .try_slerp(&self.to, t.min(1.0), 1.0e-9)
+            .unwrap_or(self.to)
+    }
+}
+
+/// A report is expected every 15ms, after this many are missed the last rotation is repeated.
+const KEEP_ALIVE_AFTER: Duration = Duration::from_millis(100);
+/// Longer dropouts are real disconnects, the server should see those.
+const KEEP_ALIVE_FOR: Duration = Duration::from_secs(1);
+
+/// Sensor statuses of the SlimeVR protocol, as the firmware sends them.
+const SENSOR_OFFLINE: u8 = 0;
+const SENSOR_OK: u8 = 1;
+const SENSOR_ERROR: u8 = 2;
+
+/// Limits how fast the sent rotation can turn, so a single corrupted report can't snap a
+/// tracker around. The output catches up with the fusion over the next reports.
+fn clamp_velocity(
+    previous: Option<(UnitQuaternion<f64>, Instant)>,
+    rotation: UnitQuaternion<f64>,
+    max_degrees_per_second: f64,
+) -> UnitQuaternion<f64> {
+    let Some((previous, since)) = previous else {
+        return rotation;
+    };
+    if max_degrees_per_second <= 0.0 {
+        return rotation;
+    }
+    // Queued reports arrive in bursts, don't count them as faster movement.
+    let dt = since.elapsed().max(Duration::from_millis(15)).as_secs_f64();
+    let max_angle = (max_degrees_per_second * dt).to_radians();
+    let angle = previous.angle_to(&rotation);
+    if angle <= max_angle {
+        return rotation;
+    }
+    previous
+        .try_slerp(&rotation, max_angle / angle, 1.0e-9)
+        .unwrap_or(previous)
+}
+
+struct Device {
+    imu: Imu,
+    design: JoyconDesign,
+    send_id: u8,
+    battery: Battery,
+    status: DeviceStatus,
+    imu_times: Vec<Instant>,
+    script: Option<Script>,
+    sticks: Sticks,
+    interpolation: Option<Interpolation>,
+    /// Last rotation out of the smoothing stage, which clamps the velocity.
+    output: Option<(UnitQuaternion<f64>, Instant)>,
+    /// Last rotation sent, including the mounting rotation.
+    sent_rotation: Option<UnitQuaternion<f64>>,
+    connected_at: Option<Instant>,
+    /// Last time the IMU was laggy or gone after having been healthy.
+    last_stall: Option<Instant>,
+    raw_log: Option<RawLog>,
+    /// Not sent while paused from the controller.
+    paused: bool,
+    /// In radians/s, per controller axis.
+    gyro_average: [f64; 3],
+    /// Last sensor status the server was told about.
+    sent_sensor_status: u8,
+    /// Turn about the scale axis so far.
+    scale_twist: Option<Twist>,
+    /// Last sample as received, for the raw readout.
+    raw: Option<JoyconAxisData>,
+}
+
+impl Device {
+    /// Tells the server a tracker stopped, so it takes it out of the skeleton instead of
+    /// keeping the last rotation as a frozen limb.
+    fn sensor_status(&self) -> u8 {
+        match self.status {
+            DeviceStatus::Healthy | DeviceStatus::LaggyIMU => SENSOR_OK,
+            // A controller that just connected hasn't sent anything yet.
+            DeviceStatus::NoIMU if self.last_stall.is_none() => SENSOR_OK,
+            DeviceStatus::NoIMU | DeviceStatus::SetupFailed | DeviceStatus::AccessDenied => {
+                SENSOR_ERROR
+            }
+            DeviceStatus::Disconnected | DeviceStatus::ButtonsOnly => SENSOR_OFFLINE,
+        }
+    }
+    /// Announces the sensor with its current status, also to report a change of status.
+    pub fn handshake(&self, socket: &UdpSocket, address: &SocketAddr) {
+        let sensor_info = PacketType::SensorInfo {
+            packet_id: 0,
+            sensor_id: self.send_id,
+            sensor_status: self.sensor_status(),
+            sensor_type: 0,
+        };
+        socket
+            .try_send_to(&sensor_info.to_bytes().unwrap(), *address)
+            .ok();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelData {
+    /// Shared so that sending a report doesn't allocate.
+    pub serial_number: Arc<str>,
+    pub info: ChannelInfo,
+}
+impl ChannelData {
+    pub fn new(serial_number: impl Into<Arc<str>>, info: ChannelInfo) -> Self {
+        Self {
+            serial_number: serial_number.into(),
+            info,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ChannelInfo {
+    Connected(JoyconDesign),
+    ImuData([JoyconAxisData; 3]),
+    Battery(Battery),
+    Sticks(Sticks),
+    SetupFailed,
+    ButtonsOnly,
+    OpenFailed,
+    Paused(bool),
+    Reset,
+    Disconnected,
+}
+
+type ImuQueue = (Arc<str>, mpsc::Receiver<[JoyconAxisData; 3]>);
+
+/// Sending side given to the device threads. Every device gets its own bounded queue for IMU
+/// data, so a device that stalls and then bursts can't hold up the packets of the others.
+/// Other events are rare and always delivered.
+#[derive(Clone)]
+pub struct DeviceSender {
+    events: mpsc::Sender<ChannelData>,
+    new_queues: mpsc::Sender<ImuQueue>,
+    queues: Arc<Mutex<HashMap<Arc<str>, mpsc::SyncSender<[JoyconAxisData; 3]>>>>,
+    queue_size: usize,
+    wake: Arc<Notify>,
+}
+impl DeviceSender {
+    /// Never blocks. IMU data is dropped if the device's queue is full.
+    pub fn send(&self, data: ChannelData) -> Result<(), mpsc::SendError<ChannelData>> {
+        let result = self.inner_send(data);
+        self.wake.notify_one();
+        result
+    }
+    fn inner_send(&self, data: ChannelData) -> Result<(), mpsc::SendError<ChannelData>> {
+        let imu = match data.info {
+            ChannelInfo::ImuData(imu) => imu,
+            ChannelInfo::Disconnected => {
+                self.queues.lock().unwrap().remove(&data.serial_number);
+                return self.events.send(data);
+            }
+            _ => return self.events.send(data),
+        };
+        let mut queues = self.queues.lock().unwrap();
+        let queue = queues.entry(data.serial_number.clone()).or_insert_with(|| {
+            let (tx, rx) = mpsc::sync_channel(self.queue_size);
+            self.new_queues.send((data.serial_number.clone(), rx)).ok();
+            tx
+        });
+        match queue.try_send(imu) {
+            Ok(()) | Err(mpsc::TrySendError::Full(_)) => Ok(()),
+            Err(mpsc::TrySendError::Disconnected(_)) => Err(mpsc::SendError(data)),
+        }
+    }
+}
+
+/// `queue_size` is the number of IMU reports queued per device before new ones are dropped.
+/// A bigger queue rides out longer Bluetooth hiccups, at the cost of sending stale rotations
+/// for longer afterwards.
+pub fn device_channel(queue_size: usize) -> (DeviceSender, DeviceReceiver) {
+    let (events, events_rx) = mpsc::channel();
+    let (new_queues, new_queues_rx) = mpsc::channel();
+    let wake = Arc::new(Notify::new());
+    (
+        DeviceSender {
+            events,
+            new_queues,
+            queues: Arc::default(),
+            queue_size: queue_size.max(1),
+            wake: wake.clone(),
+        },
+        DeviceReceiver {
+            events: events_rx,
+            new_queues: new_queues_rx,
+            queues: HashMap::new(),
+            wake,
+        },
+    )
+}
+
+pub struct DeviceReceiver {
+    events: mpsc::Receiver<ChannelData>,
+    new_queues: mpsc::Receiver<ImuQueue>,
+    queues: HashMap<Arc<str>, mpsc::Receiver<[JoyconAxisData; 3]>>,
+    wake: Arc<Notify>,
+}
+impl DeviceReceiver {
+    /// Notified after every send.
+    fn wake(&self) -> Arc<Notify> {
+        self.wake.clone()
+    }
+    /// Everything received since the last call. Each device's IMU queue is drained on its own,
+    /// so a full queue of one device never delays another.
+    fn try_collect(&mut self, messages: &mut Vec<ChannelData>) {
+        for (serial_number, queue) in self.new_queues.try_iter() {
+            self.queues.insert(serial_number, queue);
+        }
+        messages.extend(self.events.try_iter());
+        self.queues.retain(|serial_number, queue| loop {
+            match queue.try_recv() {
+                Ok(imu) => messages.push(ChannelData::new(
+                    serial_number.clone(),
+                    ChannelInfo::ImuData(imu),
+                )),
+                Err(mpsc::TryRecvError::Empty) => break true,
+                Err(mpsc::TryRecvError::Disconnected) => break false,
+            }
+        });
+    }
+}
+
+/// Commands sent from the frontends to the communication thread.
+#[derive(Debug, Clone, Copy)]
+pub enum Control {
+    YawReset,
+    MountingReset,
+    /// Opens the network socket again, after an error.
+    Retry,
+    Pause(bool),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Xyz {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+fn calc_acceleration(
+    rotation: UnitQuaternion<f64>,
+    axisdata: &JoyconAxisData,
+    rad_rotation: f64,
+) -> Xyz {
+    let a = rotation.coords;
+    let (x, y, z, w) = (a.x, a.y, a.z, a.w);
+    let gravity = [
+        2.0 * ((-x) * (-z) - w * y),
+        -2.0 * (w * (-x) + y * (-z)),
+        w * w - x * x - y * y + z * z,
+    ];
+    let vector = Xyz {
+        x: axisdata.accel_x - gravity[0],
+        y: axisdata.accel_y - gravity[1],
+        z: axisdata.accel_z - gravity[2],
+    };
+
+    let rad_rotation = -rad_rotation;
+    Xyz {
+        x: vector.x * rad_rotation.cos() - vector.y * rad_rotation.sin(),
+        y: vector.x * rad_rotation.sin() + vector.y * rad_rotation.cos(),
+        z: vector.z,
+    }
+}
+
+/// Packet formats the server understands, going by its handshake response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ServerProtocol {
+    /// Sensor ids in packets, so every tracker can be sent.
+    #[default]
+    Current,
+    /// Servers from before protocol versions only take a single sensor, without an id.
+    Legacy,
+}
+impl ServerProtocol {
+    fn from_handshake(packet: &[u8]) -> Self {
+        match protocol::handshake_version(packet) {
+            Some(_) => Self::Current,
+            None => Self::Legacy,
+        }
+    }
+    /// Whether to send data of this sensor at all. Legacy servers only get the first tracker.
+    fn accepts(self, sensor_id: u8, first_id: u8) -> bool {
+        self == Self::Current || sensor_id == first_id
+    }
+    fn sensor_id(self, sensor_id: u8) -> Option<u8> {
+        match self {
+            Self::Current => Some(sensor_id),
+            Self::Legacy => None,
+        }
+    }
+    fn rotation(self, sensor_id: u8, rotation: UnitQuaternion<f64>) -> PacketType {
+        match self {
+            Self::Current => PacketType::RotationData {
+                packet_id: 0,
+                sensor_id,
+                data_type: 1,
+                quat: (*rotation).into(),
+                calibration_info: 0,
+            },
+            Self::Legacy => PacketType::Rotation {
+                packet_id: 0,
+                quat: (*rotation).into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ServerStatus {
+    #[default]
+    Disconnected,
+    Unknown,
+    Connected,
+    /// Was connected, but the server stopped pinging.
+    TimedOut,
+}
+impl Display for ServerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ServerStatus::Disconnected => "Disconnected",
+            ServerStatus::Unknown => "Unknown",
+            ServerStatus::Connected => "Connected",
+            ServerStatus::TimedOut => "Timed out, reconnecting",
+        })
+    }
+}
+
+/// Latest state of the communication thread, readable from any thread.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub server: ServerStatus,
+    pub protocol: ServerProtocol,
+    pub paused: bool,
+    pub trackers: Vec<Status>,
+    /// Tracker data packets sent to the server per second.
+    pub packet_rate: u32,
+    /// Serial numbers of all connected controllers, also those this instance doesn't use.
+    pub connected: Vec<String>,
+    /// Why nothing can be sent to the server, like no network socket or an unreachable network.
+    pub socket_error: Option<String>,
+}
+
+fn load_script(settings: &settings::Handler, serial_number: &str) -> Option<Script> {
+    let path = settings.load().joycon_script_get(serial_number)?;
+    match Script::load(&path) {
+        Ok(script) => {
+            println!("[INFO] Using script {} for {serial_number}", path.display());
+            Some(script)
+        }
+        Err(e) => {
+            println!(
+                "\x1b[0;31m[ERROR]\x1b[0m Could not load script {}: {e}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+/// Weight of each sample in the average gyro reading, about a second's worth at 200 Hz.
+const GYRO_AVERAGE_WEIGHT: f64 = 0.005;
+
+/// Tracker id that stays the same across restarts, so the server remembers the assignment.
+/// Probes for the next free id if it's `taken`.
+fn derived_id(serial_number: &str, taken: impl Fn(u8) -> bool) -> u8 {
+    // FNV-1a, the std hashers aren't guaranteed to be stable between Rust versions.
+    let hash = serial_number.bytes().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x01000193)
+    });
+    // Ids 1 to 254, like the ones saved for keep_ids.
+    let first = (hash % 254) as u8 + 1;
+    let mut id = first;
+    while taken(id) {
+        id = id % 254 + 1;
+        // Every id is taken, sharing one is all that's left.
+        if id == first {
+            break;
+        }
+    }
+    id
+}
+
+/// How often yaw drift learned while running is written to the settings file.
+const DRIFT_SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Yaw drift learned this session, in radians/s, until it's saved so the next session starts
+/// with it. Kept in memory in between, writing the settings file after every rest would put
+/// file I/O in the sensor path.
+#[derive(Clone)]
+pub(crate) struct LearnedDrift {
+    unsaved: Arc<Mutex<HashMap<String, f64>>>,
+    settings: settings::Handler,
+}
+impl LearnedDrift {
+    pub(crate) fn new(settings: settings::Handler) -> Self {
+        Self {
+            unsaved: Arc::default(),
+            settings,
+        }
+    }
+    fn insert(&self, serial_number: &str, drift: f64) {
+        self.unsaved
+            .lock()
+            .unwrap()
+            .insert(serial_number.to_owned(), drift);
+    }
+    /// Learned this session or in earlier ones, in radians/s.
+    fn get(&self, serial_number: &str) -> f64 {
+        match self.unsaved.lock().unwrap().get(serial_number) {
+            Some(drift) => *drift,
+            None => self
+                .settings
+                .load()
+                .joycon_yaw_drift_get(serial_number)
+                .to_radians(),
+        }
+    }
+    /// Writes what was learned since the last save. Small changes aren't worth writing the
+    /// settings file for.
+    pub(crate) fn save(&self) {
+        let unsaved = std::mem::take(&mut *self.unsaved.lock().unwrap());
+        let changed: Vec<(String, f64)> = {
+            let settings = self.settings.load();
+            unsaved
+                .into_iter()
+                .map(|(serial_number, drift)| (serial_number, drift.to_degrees()))
+                .filter(|(serial_number, drift)| {
+                    (drift - settings.joycon_yaw_drift_get(serial_number)).abs() >= 0.01
+                })
+                .collect()
+        };
+        if !changed.is_empty() {
+            self.settings.change(|ws| {
+                for (serial_number, drift) in changed {
+                    ws.joycon_yaw_drift_set(serial_number, drift);
+                }
+            });
+        }
+    }
+}
+
+/// Opens or closes the raw IMU log as the setting changes, and writes the samples to it.
+fn update_raw_log(
+    raw_log: &mut Option<RawLog>,
+    serial_number: &str,
+    enabled: bool,
+    frames: &[JoyconAxisData],
+) {
+    if !enabled {
+        *raw_log = None;
+        return;
+    }
+    if raw_log.is_none() {
+        match RawLog::create(serial_number) {
+            Ok((log, path)) => {
+                println!(
+                    "[INFO] Logging raw IMU of {serial_number} to {}",
+                    path.display()
+                );
+                *raw_log = Some(log);
+            }
+            Err(e) => {
+                println!("\x1b[0;31m[ERROR]\x1b[0m Could not create raw IMU log: {e}");
+                return;
+            }
+        }
+    }
+    if let Some(log) = raw_log {
+        if let Err(e) = log.write(frames) {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not write raw IMU log: {e}");
+            *raw_log = None;
+        }
+    }
+}
+
+/// Sends an unfused sample, for servers that do their own fusion. Mounting rotation is left
+/// to the server as well.
+fn send_raw(
+    socket: &UdpSocket,
+    buffer: &mut PacketBuffer,
+    address: SocketAddr,
+    sensor_id: Option<u8>,
+    frame: &JoyconAxisData,
+) {
+    let gyro_packet = PacketType::Gyro {
+        packet_id: 0,
+        vector: (
+            frame.gyro_x as f32,
+            frame.gyro_y as f32,
+            frame.gyro_z as f32,
+        ),
+        sensor_id,
+    };
+    send_packet(socket, buffer, address, &gyro_packet);
+    let acceleration_packet = PacketType::Acceleration {
+        packet_id: 0,
+        vector: (
+            frame.accel_x as f32,
+            frame.accel_y as f32,
+            frame.accel_z as f32,
+        ),
+        sensor_id,
+    };
+    send_packet(socket, buffer, address, &acceleration_packet);
+}
+
+/// Buffer that is reused, as this runs for every IMU report of every tracker. Also counts the
+/// packets for the packet rate.
+#[derive(Default)]
+struct PacketBuffer {
+    buf: BitVec<u8, Msb0>,
+    sent: u32,
+}
+
+/// Binds to the configured local port, or any free one if that's taken, on the picked network
+/// interface. Falls back to any interface if the picked one is gone.
+fn bind_socket(settings: &settings::WranglerSettings) -> io::Result<UdpSocket> {
+    let bind = |ip: IpAddr| {
+        net::UdpSocket::bind(
+            &[
+                SocketAddr::new(ip, settings.local_port),
+                SocketAddr::new(ip, 0),
+            ][..],
+        )
+    };
+    let any = IpAddr::from([0, 0, 0, 0]);
+    let socket = match settings.bind_address {
+        Some(ip) => bind(ip).or_else(|e| {
+            println!(
+                "\x1b[0;31m[ERROR]\x1b[0m Could not use the network interface with address {ip}, using any: {e}"
+            );
+            bind(any)
+        }),
+        None => bind(any),
+    }?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket)
+}
+
+fn send_packet(
+    socket: &UdpSocket,
+    buffer: &mut PacketBuffer,
+    address: SocketAddr,
+    packet: &PacketType,
+) {
+    buffer.buf.clear();
+    if packet.write(&mut buffer.buf, ()).is_ok()
+        && socket
+            .try_send_to(buffer.buf.as_raw_slice(), address)
+            .is_ok()
+    {
+        buffer.sent += 1;
+    }
+}
+
+pub struct Communication {
+    receive: DeviceReceiver,
+    control: UnboundedReceiver<Control>,
+    snapshot: watch::Sender<Arc<Snapshot>>,
+    events: Bus,
+    settings: settings::Handler,
+    learned_drift: LearnedDrift,
+
+    devices: HashMap<Arc<str>, Device>,
+    /// Every connected controller, so one can be picked up when a profile switch claims it.
+    present: BTreeMap<Arc<str>, JoyconDesign>,
+
+    use_keep_ids: bool,
+    socket: UdpSocket,
+    packet: PacketBuffer,
+    /// Tracker data packets sent per second, measured over the last second.
+    packet_rate: u32,
+    packet_rate_since: Instant,
+    address: SocketAddr,
+    connected: ServerStatus,
+    protocol: ServerProtocol,
+    last_handshake: Instant,
+    last_heartbeat: Instant,
+    last_ping: Instant,
+    last_reset: Instant,
+    paused: bool,
+    /// Rotations per second sent for each device, or 0 to send one for every report.
+    output_rate: u16,
+    /// Only with the `rerun` feature, when a viewer could be reached at startup.
+    viewer: Option<Viewer>,
+    socket_error: Option<String>,
+}
+impl Communication {
+    /// Runs the connection to the SlimeVR Server on its own single threaded runtime.
+    #[tokio::main(flavor = "current_thread")]
+    pub async fn start(
+        receive: DeviceReceiver,
+        mut control: UnboundedReceiver<Control>,
+        snapshot: watch::Sender<Arc<Snapshot>>,
+        events: Bus,
+        settings: settings::Handler,
+        learned_drift: LearnedDrift,
+    ) {
+        let mut paused = false;
+        let socket = 'bind: loop {
+            match bind_socket(&settings.load()) {
+                Ok(socket) => break socket,
+                Err(e) => {
+                    let error = format!("Could not open a network socket: {e}");
+                    println!("\x1b[0;31m[ERROR]\x1b[0m {error}, retrying.");
+                    let failed = Snapshot {
+                        socket_error: Some(error),
+                        ..Snapshot::default()
+                    };
+                    events.publish_changes(&snapshot.borrow(), &failed);
+                    snapshot.send_replace(Arc::new(failed));
+                    let retry_at = tokio::time::Instant::now() + Duration::from_secs(5);
+                    // Retry binds again right away, there are no trackers yet for the rest.
+                    loop {
+                        tokio::select! {
+                            () = tokio::time::sleep_until(retry_at) => continue 'bind,
+                            control = control.recv() => match control {
+                                Some(Control::Retry) => continue 'bind,
+                                Some(Control::Pause(pause)) => paused = pause,
+                                Some(_) => {}
+                                None => return,
+                            },
+                        }
+                    }
+                }
+            }
+        };
+        let address = { settings.load().get_socket_address() };
+        let use_keep_ids = { settings.load().keep_ids };
+        let output_rate = { settings.load().output_rate };
+
+        Self {
+            receive,
+            control,
+            snapshot,
+            events,
+            settings,
+            learned_drift,
+            devices: HashMap::new(),
+            present: BTreeMap::new(),
+            use_keep_ids,
+            socket,
+            packet: PacketBuffer::default(),
+            packet_rate: 0,
+            packet_rate_since: Instant::now(),
+            address,
+            connected: ServerStatus::Disconnected,
+            protocol: ServerProtocol::Current,
+            last_handshake: Instant::now().checked_sub(Duration::from_secs(60)).unwrap(),
+            last_heartbeat: Instant::now(),
+            last_ping: Instant::now(),
+            last_reset: Instant::now(),
+            paused,
+            output_rate,
+            viewer: Viewer::connect(),
+            socket_error: None,
+        }
+        .main_loop()
+        .await;
+    }
+
+    /// Keeps the error of the last failed send to the server for the status, until a send
+    /// works again. A full send buffer is normal under load.
+    fn check_sent(&mut self, result: io::Result<usize>) {
+        match result {
+            Ok(_) => self.socket_error = None,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => {
+                let error = format!("Could not send to {}: {e}", self.address);
+                if self.socket_error.as_ref() != Some(&error) {
+                    println!("\x1b[0;31m[ERROR]\x1b[0m {error}");
+                }
+                self.socket_error = Some(error);
+            }
+        }
+    }
+
+    fn retry_socket(&mut self) {
+        match bind_socket(&self.settings.load()) {
+            Ok(socket) => {
+                println!("[INFO] Opened the network socket again.");
+                self.socket = socket;
+                self.socket_error = None;
+                self.connected = ServerStatus::Disconnected;
+                self.last_handshake = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+            }
+            Err(e) => {
+                self.socket_error = Some(format!("Could not open a network socket: {e}"));
+            }
+        }
+    }
+
+    fn send_handshake(&mut self) {
+        let handshake = PacketType::Handshake {
+            packet_id: 0,
+            board: 0,
+            imu: 0,
+            mcu_type: 0,
+            imu_info: (0, 0, 0),
+            build: 9,
+            firmware: "slimevr-wrangler".to_string().into(),
+            mac_address: self.settings.load().emulated_mac,
+        };
+        let result = self
+            .socket
+            .try_send_to(&handshake.to_bytes().unwrap(), self.address);
+        self.check_sent(result);
+    }
+
+    fn send_heartbeat(&mut self) {
+        let heartbeat = PacketType::Heartbeat { packet_id: 0 };
+        let result = self
+            .socket
+            .try_send_to(&heartbeat.to_bytes().unwrap(), self.address);
+        self.check_sent(result);
+    }
+
+    fn send_reset(&self) {
+        self.send_user_action(3);
+    }
+
+    fn send_user_action(&self, typ: u8) {
+        let handshake = PacketType::UserAction { packet_id: 0, typ };
+        self.socket
+            .try_send_to(&handshake.to_bytes().unwrap(), self.address)
+            .ok();
+    }
+
+    fn parse_message(&mut self, msg: ChannelData) {
+        let sn = msg.serial_number;
+        match &msg.info {
+            ChannelInfo::Connected(design) => {
+                self.present.insert(sn.clone(), design.clone());
+            }
+            ChannelInfo::Disconnected => {
+                self.present.remove(&sn);
+            }
+            _ => {}
+        }
+        if !self.settings.load().claims(&sn) {
+            return;
+        }
+        if !self.devices.contains_key(&sn) && !matches!(msg.info, ChannelInfo::Connected(_)) {
+            // Connected before the profile switched to one that uses it.
+            if let Some(design) = self.present.get(&sn).cloned() {
+                self.parse_message(ChannelData::new(sn.clone(), ChannelInfo::Connected(design)));
+            }
+        }
+        match msg.info {
+            ChannelInfo::Connected(design) => {
+                let script = load_script(&self.settings, &sn);
+                if self.devices.contains_key(&sn) {
+                    let device = self.devices.get_mut(&sn).unwrap();
+                    device.imu = Imu::new(self.learned_drift.get(&sn));
+                    device.imu_times = vec![];
+                    device.script = script;
+                    device.interpolation = None;
+                    device.output = None;
+                    device.sent_rotation = None;
+                    device.connected_at = Some(Instant::now());
+                    device.last_stall = None;
+                    device.paused = false;
+                    device.gyro_average = [0.0; 3];
+                    device.scale_twist = None;
+                    device.raw = None;
+                    return;
+                }
+
+                if !is_simulated(&sn)
+                    && self.settings.load().joycon_design_get(&sn) != Some(design.design_type)
+                {
+                    let design_type = design.design_type;
+                    self.settings
+                        .change(|ws| ws.joycon_design_set(sn.to_string(), design_type));
+                }
+
+                let send_id = match self.settings.load().joycon_tracker_id_get(&sn) {
+                    Some(id) => id,
+                    None if self.use_keep_ids => self.settings.joycon_keep_id(sn.to_string()),
+                    None => self.derived_id(&sn),
+                };
+                let device = Device {
+                    imu: Imu::new(self.learned_drift.get(&sn)),
+                    design,
+                    send_id,
+                    battery: Battery::Full,
+                    status: DeviceStatus::NoIMU,
+                    imu_times: vec![],
+                    script,
+                    sticks: Sticks::default(),
+                    interpolation: None,
+                    output: None,
+                    sent_rotation: None,
+                    connected_at: Some(Instant::now()),
+                    last_stall: None,
+                    raw_log: None,
+                    paused: false,
+                    gyro_average: [0.0; 3],
+                    sent_sensor_status: SENSOR_OK,
+                    scale_twist: None,
+                    raw: None,
+                };
+
+                device.handshake(&self.socket, &self.address);
+                self.devices.insert(sn, device);
+            }
+            ChannelInfo::ImuData(imu_data) => {
+                let first_id = self.first_id();
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    let accepted = self.protocol.accepts(device.send_id, first_id);
+                    let sensor_id = self.protocol.sensor_id(device.send_id);
+                    let data = self.settings.load().joycon_data_get(&sn);
+                    let paused = self.paused || device.paused;
+                    let raw_imu = !paused && data == settings::TrackerData::RawImu;
+                    let settings = self.settings.load();
+                    let pipeline = settings.joycon_pipeline_get(&sn);
+                    let (axes, flip_handedness) = settings.joycon_axes_get(&sn);
+                    let sample_settings = SampleSettings {
+                        gyro_scale: settings.joycon_scale_get(&sn),
+                        gyro_bias: settings.joycon_gyro_bias_get(&sn).map(f64::to_radians),
+                        axes,
+                        flip_handedness,
+                    };
+                    // The bias is subtracted after the gyro scale, if there is one.
+                    let bias_scale = if pipeline.samples.contains(&SampleStage::Scale) {
+                        sample_settings.gyro_scale
+                    } else {
+                        1.0
+                    };
+                    let compensate_drift = settings.yaw_drift_compensation;
+                    update_raw_log(
+                        &mut device.raw_log,
+                        &sn,
+                        settings.joycon_log_raw_get(&sn),
+                        &imu_data,
+                    );
+                    let mut last_frame = None;
+                    for frame in imu_data {
+                        // Measured the same whatever the pipeline, for setting the gyro bias.
+                        let calibrated = frame
+                            .with_gyro_scale(bias_scale)
+                            .without_gyro_bias(sample_settings.gyro_bias);
+                        let gyro = [calibrated.gyro_x, calibrated.gyro_y, calibrated.gyro_z];
+                        for (average, g) in device.gyro_average.iter_mut().zip(gyro) {
+                            *average += (g - *average) * GYRO_AVERAGE_WEIGHT;
+                        }
+                        let frame = pipeline.samples(frame, &sample_settings, &mut device.script);
+                        if let Some(frame) = frame {
+                            // Still fused locally for the rotation shown in the status.
+                            device.imu.update(frame, compensate_drift);
+                            last_frame = Some(frame);
+                            if raw_imu && accepted {
+                                send_raw(
+                                    &self.socket,
+                                    &mut self.packet,
+                                    self.address,
+                                    sensor_id,
+                                    &frame,
+                                );
+                            }
+                        }
+                    }
+                    device.imu_times.push(Instant::now());
+                    device.raw = Some(imu_data[2]);
+                    if let Some(drift) = device.imu.take_learned() {
+                        self.learned_drift.insert(&sn, drift);
+                    }
+                    if let Some(viewer) = &self.viewer {
+                        viewer.log(&sn, &imu_data, device.imu.rotation);
+                    }
+                    if last_frame.is_some() {
+                        self.events.publish_rotation(Rotation {
+                            serial_number: sn.clone(),
+                            rotation: device.imu.euler_angles_deg(),
+                            at: Instant::now(),
+                        });
+                    }
+                    let Some(last_frame) = last_frame else {
+                        return;
+                    };
+                    if paused || raw_imu {
+                        return;
+                    }
+
+                    let mut rotated_quat = device.imu.rotation;
+                    let mut rad_rotation = 0.0;
+                    for stage in &pipeline.rotation {
+                        match stage {
+                            RotationStage::Scale => {
+                                if let Some(axis) =
+                                    Unit::try_new(Vector3::from(settings.scale_axis), 1.0e-9)
+                                {
+                                    rotated_quat = scale_about_axis(
+                                        rotated_quat,
+                                        &axis,
+                                        settings.joycon_yaw_scale_get(&sn),
+                                        settings.joycon_tilt_scale_get(&sn),
+                                        &mut device.scale_twist,
+                                    );
+                                }
+                            }
+                            RotationStage::Smoothing => {
+                                rotated_quat = clamp_velocity(
+                                    device.output,
+                                    rotated_quat,
+                                    settings.max_angular_velocity,
+                                );
+                                device.output = Some((rotated_quat, Instant::now()));
+                            }
+                            RotationStage::Mounting => {
+                                let joycon_rotation = settings.joycon_rotation_get(&sn);
+                                if joycon_rotation > 0 {
+                                    rad_rotation = (joycon_rotation as f64).to_radians();
+                                    rotated_quat *= UnitQuaternion::from_axis_angle(
+                                        &Vector3::z_axis(),
+                                        rad_rotation,
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    device.sent_rotation = Some(rotated_quat);
+                    if !accepted {
+                        return;
+                    }
+                    if self.output_rate == 0 {
+                        let rotation_packet = self.protocol.rotation(device.send_id, rotated_quat);
+                        send_packet(
+                            &self.socket,
+                            &mut self.packet,
+                            self.address,
+                            &rotation_packet,
+                        );
+                    } else {
+                        match &mut device.interpolation {
+                            Some(interpolation) => interpolation.push(rotated_quat),
+                            None => device.interpolation = Some(Interpolation::new(rotated_quat)),
+                        }
+                    }
+
+                    if data == settings::TrackerData::Rotation {
+                        return;
+                    }
+                    let acc = calc_acceleration(device.imu.rotation, &last_frame, rad_rotation);
+                    let acceleration_packet = PacketType::Acceleration {
+                        packet_id: 0,
+                        vector: (acc.x as f32, acc.y as f32, acc.z as f32),
+                        sensor_id,
+                    };
+                    send_packet(
+                        &self.socket,
+                        &mut self.packet,
+                        self.address,
+                        &acceleration_packet,
+                    );
+                }
+            }
+            ChannelInfo::Battery(battery) => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.battery = battery;
+                }
+            }
+            ChannelInfo::Sticks(sticks) => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.sticks = sticks;
+                }
+            }
+            ChannelInfo::SetupFailed => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.status = DeviceStatus::SetupFailed;
+                }
+            }
+            ChannelInfo::ButtonsOnly => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.status = DeviceStatus::ButtonsOnly;
+                }
+            }
+            ChannelInfo::OpenFailed => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.status = DeviceStatus::AccessDenied;
+                }
+            }
+            ChannelInfo::Paused(paused) => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    println!(
+                        "[INFO] {sn}: {} from the controller.",
+                        if paused { "Paused" } else { "Resumed" }
+                    );
+                    device.paused = paused;
+                }
+            }
+            ChannelInfo::Reset => {
+                if self.settings.load().send_reset && self.last_reset.elapsed().as_secs() >= 2 {
+                    self.last_reset = Instant::now();
+                    self.send_reset();
+                }
+            }
+            ChannelInfo::Disconnected => {
+                if let Some(device) = self.devices.get_mut(&sn) {
+                    device.imu_times = vec![];
+                    device.status = DeviceStatus::Disconnected;
+                    device.connected_at = None;
+                }
+            }
+        }
+    }
+
+    /// Tracker id that stays the same across restarts, see [`derived_id`]. Saved the first time,
+    /// ids of connected devices and ones other controllers have saved or set by the user are
+    /// taken then.
+    fn derived_id(&self, serial_number: &str) -> u8 {
+        let settings = self.settings.load();
+        if let Some(id) = settings
+            .joycon
+            .get(serial_number)
+            .and_then(|j| j.derived_id)
+        {
+            return id;
+        }
+        let taken: Vec<u8> = settings
+            .joycon
+            .iter()
+            .filter(|(serial, _)| serial.as_str() != serial_number)
+            .filter_map(|(_, joycon)| joycon.tracker_id.or(joycon.derived_id))
+            .collect();
+        let id = derived_id(serial_number, |id| {
+            taken.contains(&id) || self.devices.values().any(|d| d.send_id == id)
+        });
+        if !is_simulated(serial_number) {
+            self.settings.change(|ws| {
+                ws.joycon
+                    .entry(serial_number.to_owned())
+                    .or_default()
+                    .derived_id = Some(id);
+            });
+        }
+        id
+    }
+
+    fn parse_control(&mut self, control: Control) {
+        match control {
+            Control::YawReset => {
+                self.last_reset = Instant::now();
+                self.send_reset();
+            }
+            Control::MountingReset => self.send_user_action(4),
+            Control::Retry => self.retry_socket(),
+            Control::Pause(paused) => {
+                self.paused = paused;
+            }
+        }
+    }
+
+    fn update_statuses(&mut self) {
+        let now = Instant::now();
+        let discard_before = now.checked_sub(Duration::from_secs(1)).unwrap();
+        for device in self.devices.values_mut() {
+            let previous = device.status;
+            device.imu_times.retain(|t| t > &discard_before);
+            // The controllers turn their IMU off while paused, that's no stall.
+            if self.paused {
+                continue;
+            }
+            match device.imu_times.len() {
+                x if x >= 55 => {
+                    device.status = DeviceStatus::Healthy;
+                }
+                x if x > 0 => {
+                    device.status = DeviceStatus::LaggyIMU;
+                }
+                _ => {
+                    if !matches!(
+                        device.status,
+                        DeviceStatus::Disconnected
+                            | DeviceStatus::SetupFailed
+                            | DeviceStatus::ButtonsOnly
+                            | DeviceStatus::AccessDenied
+                    ) {
+                        device.status = DeviceStatus::NoIMU;
+                    }
+                }
+            }
+            // Kept at now while the stall lasts, so the time counts from its end.
+            let stalled = matches!(device.status, DeviceStatus::LaggyIMU | DeviceStatus::NoIMU);
+            if stalled && (previous == DeviceStatus::Healthy || device.last_stall.is_some()) {
+                device.last_stall = Some(now);
+            }
+            let sensor_status = device.sensor_status();
+            if sensor_status != device.sent_sensor_status {
+                device.sent_sensor_status = sensor_status;
+                device.handshake(&self.socket, &self.address);
+            }
+        }
+    }
+
+    fn parse_packet(&mut self, packet: &[u8]) {
+        if matches!(
+            self.connected,
+            ServerStatus::Disconnected | ServerStatus::TimedOut
+        ) {
+            self.connected = ServerStatus::Unknown;
+        }
+        match PacketType::from_bytes((packet, 0)) {
+            Ok((_, PacketType::Ping { id: _ })) => {
+                self.last_ping = Instant::now();
+                self.socket.try_send_to(packet, self.address).ok();
+            }
+            Ok((_, PacketType::HandshakeResponse)) => {
+                let protocol = ServerProtocol::from_handshake(packet);
+                if protocol != self.protocol {
+                    if protocol == ServerProtocol::Legacy {
+                        println!("[INFO] SlimeVR Server is too old for multiple trackers, only the first one is sent. Update the server to use all of them.");
+                    }
+                    self.protocol = protocol;
+                }
+                self.last_ping = Instant::now();
+                self.connected = ServerStatus::Connected;
+            }
+            _ => {}
+        }
+    }
+
+    /// The tracker that legacy servers get, the one with the lowest id.
+    fn first_id(&self) -> u8 {
+        self.devices.values().map(|d| d.send_id).min().unwrap_or(0)
+    }
+
+    fn check_connection(&mut self) {
+        let address = self.settings.load().get_socket_address();
+        if address != self.address {
+            println!("[INFO] SlimeVR Server address changed to {address}, reconnecting.");
+            self.address = address;
+            self.connected = ServerStatus::Disconnected;
+            self.last_handshake = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+        }
+        let (heartbeat_interval, timeout) = {
+            let settings = self.settings.load();
+            (
+                Duration::from_millis(settings.heartbeat_interval_ms.into()),
+                Duration::from_millis(settings.server_timeout_ms.into()),
+            )
+        };
+        if self.last_ping.elapsed() >= timeout {
+            match self.connected {
+                ServerStatus::Connected => {
+                    println!(
+                        "[INFO] No ping from SlimeVR Server for {}ms, reconnecting.",
+                        timeout.as_millis()
+                    );
+                    self.connected = ServerStatus::TimedOut;
+                    self.last_handshake =
+                        Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+                }
+                ServerStatus::Unknown => self.connected = ServerStatus::Disconnected,
+                ServerStatus::Disconnected | ServerStatus::TimedOut => {}
+            }
+        }
+        if self.connected == ServerStatus::Connected {
+            if self.last_heartbeat.elapsed() >= heartbeat_interval {
+                self.last_heartbeat = Instant::now();
+                self.send_heartbeat();
+            }
+        } else if self.last_handshake.elapsed() >= heartbeat_interval {
+            self.last_handshake = Instant::now();
+            self.send_handshake();
+            for device in self.devices.values().sorted_by_key(|d| d.send_id) {
+                device.handshake(&self.socket, &self.address);
+            }
+        }
+    }
+
+    /// Repeats the last rotation of devices that missed a few reports, so the server doesn't
+    /// time the tracker out over a short Bluetooth hiccup. The status shows it as a laggy IMU.
+    fn send_keep_alive(&mut self) {
+        // The interpolated output already keeps sending.
+        if self.paused || self.output_rate > 0 {
+            return;
+        }
+        let first_id = self.first_id();
+        for device in self.devices.values() {
+            let (Some(last), Some(rotation)) = (device.imu_times.last(), device.sent_rotation)
+            else {
+                continue;
+            };
+            if device.paused {
+                continue;
+            }
+            if !self.protocol.accepts(device.send_id, first_id) {
+                continue;
+            }
+            let age = last.elapsed();
+            if age < KEEP_ALIVE_AFTER || age > KEEP_ALIVE_FOR {
+                continue;
+            }
+            let rotation_packet = self.protocol.rotation(device.send_id, rotation);
+            send_packet(
+                &self.socket,
+                &mut self.packet,
+                self.address,
+                &rotation_packet,
+            );
+        }
+    }
+
+    fn send_interpolated(&mut self) {
+        let now = Instant::now();
+        let first_id = self.first_id();
+        for device in self.devices.values() {
+            let Some(interpolation) = &device.interpolation else {
+                continue;
+            };
+            if device.paused {
+                continue;
+            }
+            if !self.protocol.accepts(device.send_id, first_id) {
+                continue;
+            }
+            // Stop once the controller is gone, like when sending on every report.
+            if now.duration_since(interpolation.since) > Duration::from_secs(1) {
+                continue;
+            }
+            let rotation_packet = self
+                .protocol
+                .rotation(device.send_id, interpolation.at(now));
+            send_packet(
+                &self.socket,
+                &mut self.packet,
+                self.address,
+                &rotation_packet,
+            );
+        }
+    }
+
+    /// Forgets controllers that the settings no longer claim, after a profile switch, and tells
+    /// the server they are gone.
+    fn drop_unclaimed(&mut self) {
+        let settings = self.settings.load();
+        let (socket, address) = (&self.socket, &self.address);
+        self.devices.retain(|sn, device| {
+            let claimed = settings.claims(sn);
+            if !claimed {
+                device.status = DeviceStatus::Disconnected;
+                device.handshake(socket, address);
+            }
+            claimed
+        });
+    }
+
+    fn send_statuses(&mut self) {
+        self.update_statuses();
+        let elapsed = self.packet_rate_since.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.packet_rate = (f64::from(self.packet.sent) / elapsed.as_secs_f64()).round() as u32;
+            self.packet.sent = 0;
+            self.packet_rate_since = Instant::now();
+        }
+
+        let settings = self.settings.load();
+        let mut statuses = Vec::new();
+        for (serial_number, device) in &self.devices {
+            statuses.push(Status {
+                rotation: device.imu.euler_angles_deg(),
+                quaternion: {
+                    let q = device.sent_rotation.unwrap_or(device.imu.rotation);
+                    [q.w, q.i, q.j, q.k]
+                },
+                design: device.design.clone(),
+                serial_number: serial_number.to_string(),
+                name: settings.joycon_name(serial_number),
+                tracker_id: device.send_id,
+                battery: device.battery,
+                status: device.status,
+                sticks: device.sticks,
+                report_rate: device.imu_times.len(),
+                uptime: device.connected_at.map(|t| t.elapsed().as_secs()),
+                since_stall: device.last_stall.map(|t| t.elapsed().as_secs()),
+                paused: device.paused,
+                gyro_average: device.gyro_average.map(f64::to_degrees).into(),
+                raw_gyro: device.raw.map_or((0.0, 0.0, 0.0), |r| {
+                    (
+                        r.gyro_x.to_degrees(),
+                        r.gyro_y.to_degrees(),
+                        r.gyro_z.to_degrees(),
+                    )
+                }),
+                raw_accel: device
+                    .raw
+                    .map_or((0.0, 0.0, 0.0), |r| (r.accel_x, r.accel_y, r.accel_z)),
+            });
+        }
+        let snapshot = Snapshot {
+            server: self.connected,
+            protocol: self.protocol,
+            paused: self.paused,
+            trackers: statuses,
+            packet_rate: self.packet_rate,
+            connected: self.present.keys().map(|sn| sn.to_string()).collect(),
+            socket_error: self.socket_error.clone(),
+        };
+        // Only wake up listeners when something actually changed.
+        let events = &self.events;
+        self.snapshot.send_if_modified(|current| {
+            if **current == snapshot {
+                return false;
+            }
+            events.publish_changes(current, &snapshot);
+            *current = Arc::new(snapshot);
+            true
+        });
+    }
+
+    /// Waits for whatever happens first: a packet from the server, a frontend command, device
+    /// data or the connection timer. Returns once every frontend handle has been dropped.
+    async fn main_loop(&mut self) {
+        let mut buf = [0; 512];
+        // IMU data only updates the statuses on this timer, building them for every report
+        // would be wasted work.
+        let mut timer = interval(Duration::from_millis(50));
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let wake = self.receive.wake();
+        let mut messages = Vec::new();
+        let output_rate = self.output_rate;
+        let mut output = interval(Duration::from_secs_f64(1.0 / f64::from(output_rate.max(1))));
+        output.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut settings_changes = self.settings.changes();
+        let mut drift_save = interval(DRIFT_SAVE_INTERVAL);
+        drift_save.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let server_before = self.connected;
+            let mut device_event = false;
+            tokio::select! {
+                received = self.socket.recv(&mut buf) => {
+                    if let Ok(len) = received {
+                        self.parse_packet(&buf[..len]);
+                    }
+                }
+                control = self.control.recv() => match control {
+                    Some(control) => self.parse_control(control),
+                    None => {
+                        self.learned_drift.save();
+                        return;
+                    }
+                },
+                () = wake.notified() => {
+                    self.receive.try_collect(&mut messages);
+                    for msg in messages.drain(..) {
+                        // Stick movement shows up with the next tick like rotation does.
+                        device_event |= !matches!(
+                            msg.info,
+                            ChannelInfo::ImuData(_) | ChannelInfo::Sticks(_)
+                        );
+                        self.parse_message(msg);
+                    }
+                }
+                _ = timer.tick() => {
+                    self.drop_unclaimed();
+                    self.check_connection();
+                    self.send_keep_alive();
+                    self.send_statuses();
+                    continue;
+                }
+                Ok(()) = settings_changes.changed() => {
+                    self.events.publish(Event::SettingsChanged);
+                    continue;
+                }
+                _ = drift_save.tick() => {
+                    self.learned_drift.save();
+                    continue;
+                }
+                _ = output.tick(), if output_rate > 0 => {
+                    if !self.paused {
+                        self.send_interpolated();
+                    }
+                    continue;
+                }
+            }
+            if device_event || self.connected != server_before {
+                self.send_statuses();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaw(degrees: f64) -> UnitQuaternion<f64> {
+        UnitQuaternion::from_axis_angle(&Vector3::z_axis(), degrees.to_radians())
+    }
+    #[test]
+    fn clamp_velocity_passes_slow_turns() {
+        let previous = Some((yaw(0.0), Instant::now()));
+        assert_eq!(clamp_velocity(None, yaw(90.0), 100.0), yaw(90.0));
+        assert_eq!(clamp_velocity(previous, yaw(90.0), 0.0), yaw(90.0));
+        assert_eq!(clamp_velocity(previous, yaw(1.0), 360.0), yaw(1.0));
+    }
+    #[test]
+    fn clamp_velocity_limits_fast_turns() {
+        let previous = Some((yaw(0.0), Instant::now()));
+        // At least 15ms have passed, 1.5 degrees at 100 degrees/s.
+        let angle = clamp_velocity(previous, yaw(90.0), 100.0)
+            .angle_to(&yaw(0.0))
+            .to_degrees();
+        assert!((1.5 - 1.0e-9..10.0).contains(&angle));
+    }
+    #[test]
+    fn derived_id_stays_the_same() {
+        let id = derived_id("98:B6:E9:12:34:56", |_| false);
+        assert!((1..=254).contains(&id));
+        assert_eq!(derived_id("98:B6:E9:12:34:56", |_| false), id);
+    }
+    #[test]
+    fn derived_id_skips_taken() {
+        let first = derived_id("98:B6:E9:12:34:56", |_| false);
+        let next = derived_id("98:B6:E9:12:34:56", |id| id == first);
+        assert_eq!(next, first % 254 + 1);
+        // Past 254 it goes on at 1.
+        assert_eq!(derived_id("98:B6:E9:12:34:56", |id| id >= first), 1);
+    }
+    #[test]
+    fn derived_id_shares_when_all_taken() {
+        let first = derived_id("98:B6:E9:12:34:56", |_| false);
+        assert_eq!(derived_id("98:B6:E9:12:34:56", |_| true), first);
+    }
+}
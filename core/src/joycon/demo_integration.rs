@@ -0,0 +1,86 @@
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    communication::{ChannelData, ChannelInfo, DeviceSender},
+    imu::JoyconAxisData,
+    Battery, JoyconDesign, JoyconDesignType,
+};
+
+/// Serial numbers of the simulated trackers start with this.
+pub(crate) const SERIAL_PREFIX: &str = "demo_";
+
+const COLORS: [&str; 6] = [
+    "#aacc20", "#aa20cc", "#20aacc", "#20ccaa", "#ccaa20", "#cc20aa",
+];
+const DESIGNS: [JoyconDesignType; 3] = [
+    JoyconDesignType::Left,
+    JoyconDesignType::Right,
+    JoyconDesignType::Pro,
+];
+const BATTERIES: [Battery; 4] = [
+    Battery::Full,
+    Battery::Medium,
+    Battery::Low,
+    Battery::Critical,
+];
+
+/// Scripted motion: a walk-like swing that is a bit different for every tracker, plus a slow
+/// yaw drift so the rotation visibly changes.
+fn motion(index: usize, seconds: f64) -> JoyconAxisData {
+    let phase = index as f64 * 0.7;
+    JoyconAxisData {
+        accel_x: 0.0,
+        accel_y: -1.0,
+        accel_z: 0.0,
+        gyro_x: 1.5 * (seconds * 2.0 + phase).sin(),
+        gyro_y: 0.0,
+        gyro_z: 0.02 * (index + 1) as f64,
+    }
+}
+
+fn spawn_demo(tx: DeviceSender, index: usize) {
+    let sn: Arc<str> = format!("{SERIAL_PREFIX}{index}").into();
+    tx.send(ChannelData::new(
+        sn.clone(),
+        ChannelInfo::Connected(JoyconDesign {
+            color: COLORS[index % COLORS.len()].into(),
+            design_type: DESIGNS[index % DESIGNS.len()],
+        }),
+    ))
+    .unwrap();
+
+    let start = Instant::now();
+    let mut battery = None;
+    loop {
+        // Same rate as a real Joy-Con: a report of 3 samples every 15ms.
+        let seconds = start.elapsed().as_secs_f64();
+        let samples = [0.0, 0.005, 0.01].map(|offset| motion(index, seconds + offset));
+        if tx
+            .send(ChannelData::new(sn.clone(), ChannelInfo::ImuData(samples)))
+            .is_err()
+        {
+            return;
+        }
+
+        let level = BATTERIES[(seconds / 60.0) as usize % BATTERIES.len()];
+        if battery != Some(level) {
+            battery = Some(level);
+            tx.send(ChannelData::new(sn.clone(), ChannelInfo::Battery(level)))
+                .ok();
+        }
+
+        thread::sleep(Duration::from_millis(15));
+    }
+}
+
+/// Simulated trackers that go through the same pipeline as real controllers.
+pub fn demo_controllers(tx: DeviceSender, count: usize) {
+    for index in 0..count {
+        let tx = tx.clone();
+        thread::spawn(move || spawn_demo(tx, index));
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JoyconDesignType {
+    Left,
+    Right,
+    Pro,
+}
+
+/// Color and shape of a controller, as reported when it connects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct JoyconDesign {
+    pub color: String,
+    pub design_type: JoyconDesignType,
+}
@@ -0,0 +1,134 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+use vqf_cxx::{VQFBuilder, VQF};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JoyconAxisData {
+    pub accel_x: f64,
+    pub accel_y: f64,
+    pub accel_z: f64,
+    pub gyro_x: f64,
+    pub gyro_y: f64,
+    pub gyro_z: f64,
+}
+impl JoyconAxisData {
+    pub fn with_gyro_scale(self, scale: f64) -> Self {
+        Self {
+            gyro_x: self.gyro_x * scale,
+            gyro_y: self.gyro_y * scale,
+            gyro_z: self.gyro_z * scale,
+            ..self
+        }
+    }
+    /// Subtracts a gyro bias in radians/s, per controller axis.
+    pub fn without_gyro_bias(self, bias: [f64; 3]) -> Self {
+        Self {
+            gyro_x: self.gyro_x - bias[0],
+            gyro_y: self.gyro_y - bias[1],
+            gyro_z: self.gyro_z - bias[2],
+            ..self
+        }
+    }
+}
+
+/// Seconds between samples.
+const SAMPLE_TIME: f64 = 0.005;
+/// Below this angular velocity in radians/s, with only gravity on the accelerometer, the
+/// controller counts as lying still.
+const REST_GYRO: f64 = 0.05;
+const REST_ACCEL: f64 = 0.05;
+/// Shorter rest periods are too noisy to learn the drift from.
+const REST_MIN_SAMPLES: u32 = 400;
+/// Yaw turning faster than this at rest (radians/s, about 1 degree/s) is movement, not drift.
+const MAX_DRIFT: f64 = 0.0175;
+/// How much each rest period moves the learned drift towards what it measured.
+const LEARN_RATE: f64 = 0.2;
+
+/// Yaw of the uncorrected rotation when the current rest period started, and since.
+struct Rest {
+    start_yaw: f64,
+    last_yaw: f64,
+    samples: u32,
+}
+
+pub struct Imu {
+    vqf: VQF,
+    pub rotation: UnitQuaternion<f64>,
+    /// Yaw the fused rotation drifts by, in radians/s, learned while the controller rests.
+    yaw_drift: f64,
+    /// Yaw removed from the rotation so far.
+    yaw_correction: f64,
+    rest: Option<Rest>,
+    /// Set when a rest period improved the learned drift, until taken.
+    learned: Option<f64>,
+}
+impl Imu {
+    /// Starts from the yaw drift learned in earlier sessions, in radians/s.
+    pub fn new(yaw_drift: f64) -> Self {
+        Self {
+            vqf: VQFBuilder::new(SAMPLE_TIME).build(),
+            rotation: UnitQuaternion::new_unchecked(Quaternion::new(
+                1.0f64, 0.0f64, 0.0f64, 0.0f64,
+            )),
+            yaw_drift,
+            yaw_correction: 0.0,
+            rest: None,
+            learned: None,
+        }
+    }
+    /// `compensate_drift` removes the learned yaw drift from the rotation. The drift is learned
+    /// either way.
+    pub fn update(&mut self, frame: JoyconAxisData, compensate_drift: bool) {
+        let gyro = Vector3::new(frame.gyro_x, frame.gyro_y, frame.gyro_z);
+        let acc = Vector3::new(frame.accel_x, frame.accel_y, frame.accel_z);
+        self.vqf.update_6dof(&gyro.data.0[0], &acc.data.0[0]);
+        let fused = UnitQuaternion::new_unchecked(self.vqf.get_quat_6d().into());
+
+        let resting = gyro.norm() < REST_GYRO && (acc.norm() - 1.0).abs() < REST_ACCEL;
+        self.learn_drift(resting, fused.euler_angles().2);
+
+        self.rotation = if compensate_drift {
+            self.yaw_correction -= self.yaw_drift * SAMPLE_TIME;
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.yaw_correction) * fused
+        } else {
+            fused
+        };
+    }
+    /// Any yaw change while lying still is drift. Each long enough rest period nudges the
+    /// learned drift towards the one it measured.
+    fn learn_drift(&mut self, resting: bool, yaw: f64) {
+        if resting {
+            let rest = self.rest.get_or_insert(Rest {
+                start_yaw: yaw,
+                last_yaw: yaw,
+                samples: 0,
+            });
+            rest.last_yaw = yaw;
+            rest.samples += 1;
+            return;
+        }
+        let Some(rest) = self.rest.take() else {
+            return;
+        };
+        if rest.samples < REST_MIN_SAMPLES {
+            return;
+        }
+        let turned = (rest.last_yaw - rest.start_yaw + PI).rem_euclid(2.0 * PI) - PI;
+        let drift = turned / (f64::from(rest.samples) * SAMPLE_TIME);
+        if drift.abs() > MAX_DRIFT {
+            return;
+        }
+        self.yaw_drift += (drift - self.yaw_drift) * LEARN_RATE;
+        self.learned = Some(self.yaw_drift);
+    }
+    /// The learned yaw drift in radians/s, if it changed since last taken.
+    pub fn take_learned(&mut self) -> Option<f64> {
+        self.learned.take()
+    }
+    // euler_angles: roll, pitch, yaw
+    pub fn euler_angles_deg(&self) -> (f64, f64, f64) {
+        let ea = self.rotation.euler_angles();
+        (ea.0.to_degrees(), ea.1.to_degrees(), ea.2.to_degrees())
+    }
+}
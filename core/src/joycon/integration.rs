@@ -0,0 +1,699 @@
+use super::buttons::{Button, ButtonAction, Chords};
+use super::communication::{ChannelData, DeviceSender, ServerStatus, Snapshot};
+use super::imu::JoyconAxisData;
+use super::stick::{self, StickCalibration, Sticks};
+use super::{Battery, ChannelInfo, JoyconDesign, JoyconDesignType};
+use crate::settings;
+use joycon_rs::joycon::device::calibration::imu::IMUCalibration;
+use joycon_rs::prelude::input_report_mode::BatteryLevel;
+use joycon_rs::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+// Gyro: 2000dps
+// Accel: 8G
+// https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/imu_sensor_notes.md
+
+// Convert to acceleration in G
+fn acc(n: i16, offset: i16) -> f64 {
+    let n = n.saturating_sub(offset);
+    n as f64 * 0.00024414435f64 // 16000/65535/1000
+}
+// Convert to acceleration in radians/s
+fn gyro(n: i16, offset: i16) -> f64 {
+    n.saturating_sub(offset) as f64
+    // NOTE: 13371 is technically a value present in flash, in practice it seems to be constant.
+    //* (936.0 / (13371 - offset) as f64) // to degrees/s
+    * 0.07000839246f64 // 4588/65535 - degrees/s
+    .to_radians() // radians/s
+}
+
+fn convert_battery(battery: BatteryLevel) -> Battery {
+    match battery {
+        BatteryLevel::Empty => Battery::Empty,
+        BatteryLevel::Critical => Battery::Critical,
+        BatteryLevel::Low => Battery::Low,
+        BatteryLevel::Medium => Battery::Medium,
+        BatteryLevel::Full => Battery::Full,
+    }
+}
+
+fn convert_button(button: Button) -> Buttons {
+    match button {
+        Button::Up => Buttons::Up,
+        Button::Down => Buttons::Down,
+        Button::Left => Buttons::Left,
+        Button::Right => Buttons::Right,
+        Button::A => Buttons::A,
+        Button::B => Buttons::B,
+        Button::X => Buttons::X,
+        Button::Y => Buttons::Y,
+        Button::L => Buttons::L,
+        Button::R => Buttons::R,
+        Button::ZL => Buttons::ZL,
+        Button::ZR => Buttons::ZR,
+        Button::SL => Buttons::SL,
+        Button::SR => Buttons::SR,
+        Button::Minus => Buttons::Minus,
+        Button::Plus => Buttons::Plus,
+        Button::LStick => Buttons::LStick,
+        Button::RStick => Buttons::RStick,
+        Button::Home => Buttons::Home,
+        Button::Capture => Buttons::Capture,
+    }
+}
+
+fn convert_design(device_type: &JoyConDeviceType) -> JoyconDesignType {
+    match device_type {
+        JoyConDeviceType::JoyConL => JoyconDesignType::Left,
+        JoyConDeviceType::JoyConR => JoyconDesignType::Right,
+        JoyConDeviceType::ProCon => JoyconDesignType::Pro,
+    }
+}
+
+/// Serial numbers of the connected controllers. Many clone controllers share one serial number,
+/// those get the end of their Bluetooth address appended, so their settings and tracker ids
+/// don't collide. The first controller seen with a shared serial number keeps it as it is, its
+/// address is remembered in the settings so it keeps its settings and tracker id in later
+/// sessions, whichever connects first.
+#[derive(Clone)]
+struct Serials {
+    /// The address of each claimed serial number's controller, if it could be read.
+    used: Arc<Mutex<HashMap<Arc<str>, Option<[u8; 6]>>>>,
+    settings: settings::Handler,
+}
+impl Serials {
+    fn new(settings: settings::Handler) -> Self {
+        Self {
+            used: Arc::default(),
+            settings,
+        }
+    }
+    fn claim(&self, serial_number: &str, mac: Option<[u8; 6]>) -> Arc<str> {
+        let mut used = self.used.lock().unwrap();
+        let mut owner = self
+            .settings
+            .load()
+            .shared_serials
+            .get(serial_number)
+            .copied();
+        if let (None, Some(Some(first))) = (owner, used.get(serial_number)) {
+            println!(
+                "[INFO] Another controller already has serial number {serial_number}, telling them apart by their Bluetooth address from now on."
+            );
+            let first = *first;
+            self.settings.change(|ws| {
+                ws.shared_serials.insert(serial_number.to_owned(), first);
+            });
+            owner = Some(first);
+        }
+        let keeps_serial = match owner {
+            Some(owner) => mac == Some(owner),
+            None => !used.contains_key(serial_number),
+        };
+        let mut claimed: Arc<str> = match mac {
+            Some(mac) if !keeps_serial => {
+                format!("{serial_number}#{:02x}{:02x}{:02x}", mac[3], mac[4], mac[5]).into()
+            }
+            _ => serial_number.into(),
+        };
+        // Without an address to go by, numbered in the order they connect.
+        let mut n = 1;
+        while used.contains_key(&claimed) {
+            n += 1;
+            claimed = format!("{serial_number}#{n}").into();
+        }
+        used.insert(claimed.clone(), mac);
+        claimed
+    }
+    fn release(&self, serial_number: &str) {
+        self.used.lock().unwrap().remove(serial_number);
+    }
+}
+
+/// Whether opening failed for lack of access, from missing permissions or another program
+/// holding the controller exclusively. hidapi only passes on the OS error as text.
+fn access_denied(error: &JoyConError) -> bool {
+    let error = format!("{error:?}").to_lowercase();
+    [
+        "denied",
+        "os error 5)",
+        "os error 13)",
+        "os error 32)",
+        "sharing violation",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+/// Whether the controller acknowledged this sub command in its reply.
+fn acknowledged(reply: &[u8], sub_command: u8) -> bool {
+    reply.get(13).map_or(false, |ack| ack & 0x80 != 0) && reply.get(14) == Some(&sub_command)
+}
+
+/// Commands that have to be acknowledged before the controller streams IMU data, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetupStep {
+    PlayerLights,
+    EnableImu,
+    ReportMode,
+    McuOff,
+    Done,
+}
+impl SetupStep {
+    fn command(self) -> Option<(SubCommand, &'static [u8])> {
+        match self {
+            // Player LEDs 1 and 4.
+            SetupStep::PlayerLights => Some((SubCommand::SetPlayerLights, &[0b1001])),
+            SetupStep::EnableImu => Some((SubCommand::EnableIMU, &[0x01])),
+            // Standard full mode, 0x30.
+            SetupStep::ReportMode => Some((SubCommand::SetInputReportMode, &[0x30])),
+            // Suspends the NFC/IR MCU, it draws power even when nothing uses it.
+            SetupStep::McuOff => Some((SubCommand::SetNFCIRMCUState, &[0x00])),
+            SetupStep::Done => None,
+        }
+    }
+    fn next(self) -> Self {
+        match self {
+            SetupStep::PlayerLights => SetupStep::EnableImu,
+            SetupStep::EnableImu => SetupStep::ReportMode,
+            SetupStep::ReportMode => SetupStep::McuOff,
+            SetupStep::McuOff | SetupStep::Done => SetupStep::Done,
+        }
+    }
+    /// The lights and MCU only matter for battery life and looks, the controller works without.
+    fn required(self) -> bool {
+        !matches!(self, SetupStep::PlayerLights | SetupStep::McuOff)
+    }
+}
+
+const SETUP_ATTEMPTS: u32 = 5;
+
+/// Sends every setup command until it is acknowledged, backing off between attempts. Some
+/// controllers drop commands right after connecting and then never stream IMU data.
+fn setup(
+    driver: &mut SimpleJoyConDriver,
+    serial_number: &str,
+    device_type: &JoyConDeviceType,
+) -> Result<(), SetupStep> {
+    let mut step = SetupStep::PlayerLights;
+    while let Some((sub_command, data)) = step.command() {
+        // Only the right Joy-Con and the Pro Controller have the MCU.
+        if step == SetupStep::McuOff && matches!(device_type, JoyConDeviceType::JoyConL) {
+            step = step.next();
+            continue;
+        }
+        let id = sub_command as u8;
+        let mut acked = false;
+        for attempt in 0..SETUP_ATTEMPTS {
+            let (sub_command, _) = step.command().unwrap();
+            if let Ok(SubCommandReply::Checked(reply)) = driver.send_sub_command(sub_command, data)
+            {
+                if acknowledged(&reply, id) {
+                    acked = true;
+                    break;
+                }
+            }
+            if attempt + 1 < SETUP_ATTEMPTS {
+                thread::sleep(Duration::from_millis(50 << attempt));
+            }
+        }
+        if !acked {
+            if step.required() {
+                return Err(step);
+            }
+            println!("[INFO] {serial_number}: {step:?} not acknowledged, continuing.");
+        }
+        step = step.next();
+    }
+    Ok(())
+}
+
+/// What the HOME button light shows, on controllers that have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HomeLight {
+    Off,
+    Tracking,
+    NoServer,
+    LowBattery,
+}
+impl HomeLight {
+    fn new(enabled: bool, server: ServerStatus, battery: Option<BatteryLevel>) -> Self {
+        if !enabled {
+            HomeLight::Off
+        } else if matches!(
+            battery,
+            Some(BatteryLevel::Empty | BatteryLevel::Critical | BatteryLevel::Low)
+        ) {
+            HomeLight::LowBattery
+        } else if server != ServerStatus::Connected {
+            HomeLight::NoServer
+        } else {
+            HomeLight::Tracking
+        }
+    }
+    /// https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/bluetooth_hid_subcommands_notes.md#subcommand-0x38-set-home-light
+    fn pattern(self) -> &'static [u8] {
+        match self {
+            HomeLight::Off => &[0x00, 0x00],
+            // Dim and steady.
+            HomeLight::Tracking => &[0x1F, 0x30, 0x30, 0x0F, 0x00],
+            // On and off, about a second each.
+            HomeLight::NoServer => &[0x2F, 0xF0, 0xF0, 0x06, 0x06],
+            // Fast blinking.
+            HomeLight::LowBattery => &[0x2F, 0xF0, 0xF0, 0x01, 0x01],
+        }
+    }
+}
+
+/// Player lights 1 and 4, flashing while paused.
+fn player_lights(paused: bool) -> [u8; 1] {
+    if paused {
+        [0b1001_0000]
+    } else {
+        [0b1001]
+    }
+}
+
+const HOME_LIGHT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reports without IMU data for this long mean the setup didn't stick.
+const IMU_STALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Reads `N` bytes from the controller's SPI flash.
+fn read_spi<const N: usize>(driver: &mut SimpleJoyConDriver, address: u32) -> Option<[u8; N]> {
+    let mut request = address.to_le_bytes().to_vec();
+    request.push(N as u8);
+    let SubCommandReply::Checked(reply) = driver
+        .send_sub_command(SubCommand::SPIFlashRead, &request)
+        .ok()?
+    else {
+        return None;
+    };
+    // The reply repeats the address and length before the data.
+    if !acknowledged(&reply, SubCommand::SPIFlashRead as u8)
+        || reply.get(15..20) != Some(&request[..])
+    {
+        return None;
+    }
+    reply.get(20..20 + N)?.try_into().ok()
+}
+
+/// The controller's Bluetooth address from its device info, which clone controllers sharing a
+/// serial number don't share.
+fn read_mac(driver: &mut SimpleJoyConDriver) -> Option<[u8; 6]> {
+    let SubCommandReply::Checked(reply) = driver
+        .send_sub_command(SubCommand::RequestDeviceInfo, &[])
+        .ok()?
+    else {
+        return None;
+    };
+    // Firmware version, controller type and a spare byte come before the address.
+    if !acknowledged(&reply, SubCommand::RequestDeviceInfo as u8) {
+        return None;
+    }
+    reply.get(19..25)?.try_into().ok()
+}
+
+/// Prefers the user calibration, from recalibrating in the Switch settings, over the factory one.
+fn read_stick_calibration(driver: &mut SimpleJoyConDriver) -> [StickCalibration; 2] {
+    [
+        (stick::USER_LEFT_ADDRESS, stick::FACTORY_LEFT_ADDRESS, true),
+        (
+            stick::USER_RIGHT_ADDRESS,
+            stick::FACTORY_RIGHT_ADDRESS,
+            false,
+        ),
+    ]
+    .map(|(user, factory, left)| {
+        read_spi::<11>(driver, user)
+            .filter(|b| b[..2] == stick::USER_MAGIC)
+            .and_then(|b| StickCalibration::parse(b[2..].try_into().unwrap(), left))
+            .or_else(|| {
+                read_spi::<9>(driver, factory).and_then(|b| StickCalibration::parse(&b, left))
+            })
+            .unwrap_or_default()
+    })
+}
+
+fn joycon_listen_loop(
+    mut standard: StandardFullMode<SimpleJoyConDriver>,
+    tx: &DeviceSender,
+    serial_number: Arc<str>,
+    calib: IMUCalibration,
+    stick_calib: [StickCalibration; 2],
+    settings: &settings::Handler,
+    snapshot: &watch::Receiver<Arc<Snapshot>>,
+) {
+    let device_type = standard.driver().joycon().device_type();
+    let calib = match calib {
+        IMUCalibration::Available {
+            acc_origin_position: ao,
+            gyro_origin_position: go,
+            ..
+        } => ([ao.x, ao.y, ao.z], [go.x, go.y, go.z]),
+        IMUCalibration::Unavailable => ([0, 0, 0], [0, 0, 0]),
+    };
+    let neg_right: fn(f64) -> f64 = match device_type {
+        JoyConDeviceType::JoyConR => |v| -v,
+        JoyConDeviceType::JoyConL | JoyConDeviceType::ProCon => |v| v,
+    };
+    let has_left = !matches!(device_type, JoyConDeviceType::JoyConR);
+    let has_right = !matches!(device_type, JoyConDeviceType::JoyConL);
+    let mut last_battery = None;
+    let mut last_sticks: Option<Sticks> = None;
+    let mut last_imu = Instant::now();
+    let has_home_light = !matches!(device_type, JoyConDeviceType::JoyConL);
+    let mut home_light = None;
+    let mut last_home_light_check = Instant::now();
+    let mut paused = false;
+    // Off while sending is paused, the IMU is most of what the controller spends on reports.
+    let mut imu_off = false;
+    let mut chords = Chords::default();
+    loop {
+        if last_home_light_check.elapsed() > HOME_LIGHT_INTERVAL {
+            last_home_light_check = Instant::now();
+            let (server, all_paused) = {
+                let snapshot = snapshot.borrow();
+                (snapshot.server, snapshot.paused)
+            };
+            if all_paused != imu_off
+                && standard
+                    .driver_mut()
+                    .send_sub_command(SubCommand::EnableIMU, &[u8::from(!all_paused)])
+                    .is_ok()
+            {
+                imu_off = all_paused;
+            }
+            let light = HomeLight::new(settings.load().home_light, server, last_battery);
+            if has_home_light && home_light != Some(light) {
+                home_light = Some(light);
+                standard
+                    .driver_mut()
+                    .send_sub_command(SubCommand::SetHOMELight, light.pattern())
+                    .ok();
+            }
+        }
+        if last_imu.elapsed() > IMU_STALL_TIMEOUT {
+            println!("[INFO] {serial_number}: No IMU data, setting up the controller again.");
+            return;
+        }
+        match standard.read_input_report() {
+            Ok(report) => {
+                if report.common.input_report_id == 48 {
+                    last_imu = Instant::now();
+                    if Some(report.common.battery.level) != last_battery {
+                        last_battery = Some(report.common.battery.level);
+                        tx.send(ChannelData::new(
+                            serial_number.clone(),
+                            ChannelInfo::Battery(convert_battery(report.common.battery.level)),
+                        ))
+                        .unwrap();
+                    }
+                    let left = &report.common.left_analog_stick_data;
+                    let right = &report.common.right_analog_stick_data;
+                    let sticks = Sticks {
+                        left: has_left
+                            .then(|| stick_calib[0].normalize(left.horizontal, left.vertical)),
+                        right: has_right
+                            .then(|| stick_calib[1].normalize(right.horizontal, right.vertical)),
+                    };
+                    if last_sticks.map_or(true, |last| sticks.moved_from(&last)) {
+                        last_sticks = Some(sticks);
+                        tx.send(ChannelData::new(
+                            serial_number.clone(),
+                            ChannelInfo::Sticks(sticks),
+                        ))
+                        .unwrap();
+                    }
+                    let buttons = &report.common.pushed_buttons;
+                    let actions = chords.update(&settings.load().button_mappings, |b| {
+                        buttons.contains(convert_button(b))
+                    });
+                    for action in actions {
+                        match action {
+                            ButtonAction::YawReset => {
+                                tx.send(ChannelData::new(
+                                    serial_number.clone(),
+                                    ChannelInfo::Reset,
+                                ))
+                                .unwrap();
+                            }
+                            ButtonAction::Pause => {
+                                paused = !paused;
+                                standard
+                                    .driver_mut()
+                                    .send_sub_command(
+                                        SubCommand::SetPlayerLights,
+                                        &player_lights(paused),
+                                    )
+                                    .ok();
+                                tx.send(ChannelData::new(
+                                    serial_number.clone(),
+                                    ChannelInfo::Paused(paused),
+                                ))
+                                .unwrap();
+                            }
+                        }
+                    }
+                    if imu_off {
+                        continue;
+                    }
+                    let imu_data = report.extra.data.map(|data| JoyconAxisData {
+                        accel_x: acc(data.accel_x, calib.0[0]),
+                        accel_y: neg_right(acc(data.accel_y, calib.0[1])),
+                        accel_z: neg_right(acc(data.accel_z, calib.0[2])),
+                        gyro_x: gyro(data.gyro_1, calib.1[0]),
+                        gyro_y: neg_right(gyro(data.gyro_2, calib.1[1])),
+                        gyro_z: neg_right(gyro(data.gyro_3, calib.1[2])),
+                    });
+                    tx.send(ChannelData::new(
+                        serial_number.clone(),
+                        ChannelInfo::ImuData(imu_data),
+                    ))
+                    .unwrap();
+                }
+            }
+            Err(JoyConError::Disconnected) => {
+                tx.send(ChannelData::new(serial_number, ChannelInfo::Disconnected))
+                    .unwrap();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fallback for controllers that only speak the simple HID report mode, there is no IMU data
+/// but the buttons still work for yaw reset.
+fn simple_hid_loop(
+    simple: SimpleHIDMode<SimpleJoyConDriver>,
+    tx: &DeviceSender,
+    serial_number: Arc<str>,
+) {
+    loop {
+        match simple.read_input_report() {
+            Ok(report) => {
+                if report.pushed_buttons.contains(&SimpleHIDButton::Up)
+                    || report.pushed_buttons.contains(&SimpleHIDButton::Down)
+                {
+                    tx.send(ChannelData::new(serial_number.clone(), ChannelInfo::Reset))
+                        .unwrap();
+                }
+            }
+            Err(JoyConError::Disconnected) => {
+                tx.send(ChannelData::new(serial_number, ChannelInfo::Disconnected))
+                    .unwrap();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Waits between attempts to open a controller that another program may be holding, doubling
+/// up to the max.
+const OPEN_RETRY_MIN: Duration = Duration::from_secs(1);
+const OPEN_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Full setups that fail in a row before a controller is used for its buttons only. A single
+/// missed acknowledgement shouldn't cost the IMU until it reconnects.
+const SETUP_FAILURES_BEFORE_FALLBACK: u32 = 4;
+
+/// A panicking device thread shouldn't take the device with it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn design(joycon: &JoyConDevice) -> JoyconDesign {
+    let color = joycon.color();
+    JoyconDesign {
+        color: format!(
+            "#{:02x}{:02x}{:02x}",
+            color.body[0], color.body[1], color.body[2]
+        ),
+        design_type: convert_design(&joycon.device_type()),
+    }
+}
+
+fn joycon_thread(
+    d: Arc<Mutex<JoyConDevice>>,
+    tx: DeviceSender,
+    settings: settings::Handler,
+    snapshot: watch::Receiver<Arc<Snapshot>>,
+    serials: Serials,
+) {
+    let mut retry = OPEN_RETRY_MIN;
+    // Serial number shown as access denied while opening keeps failing.
+    let mut open_failed: Option<Arc<str>> = None;
+    let mut setup_failures = 0;
+    let mut setup_retry = OPEN_RETRY_MIN;
+    loop {
+        if !lock(&d).is_connected() {
+            if let Some(serial_number) = open_failed.take() {
+                tx.send(ChannelData::new(
+                    serial_number.clone(),
+                    ChannelInfo::Disconnected,
+                ))
+                .unwrap();
+                serials.release(&serial_number);
+            }
+            retry = OPEN_RETRY_MIN;
+            setup_failures = 0;
+            setup_retry = OPEN_RETRY_MIN;
+        } else {
+            let mut driver = match SimpleJoyConDriver::new(&d) {
+                Ok(driver) => driver,
+                Err(e) => {
+                    if open_failed.is_none() {
+                        let joycon = lock(&d);
+                        let serial_number = serials.claim(joycon.serial_number(), None);
+                        println!(
+                            "\x1b[0;31m[ERROR]\x1b[0m {serial_number}: Could not open the controller, retrying: {e:?}"
+                        );
+                        tx.send(ChannelData::new(
+                            serial_number.clone(),
+                            ChannelInfo::Connected(design(&joycon)),
+                        ))
+                        .unwrap();
+                        let info = if access_denied(&e) {
+                            ChannelInfo::OpenFailed
+                        } else {
+                            ChannelInfo::SetupFailed
+                        };
+                        tx.send(ChannelData::new(serial_number.clone(), info))
+                            .unwrap();
+                        open_failed = Some(serial_number);
+                    }
+                    thread::sleep(retry);
+                    retry = (retry * 2).min(OPEN_RETRY_MAX);
+                    continue;
+                }
+            };
+            if let Some(serial_number) = open_failed.take() {
+                println!("[INFO] {serial_number}: Opened the controller.");
+                serials.release(&serial_number);
+            }
+            retry = OPEN_RETRY_MIN;
+            let mac = read_mac(&mut driver);
+            let joycon = driver.joycon();
+            let design = design(&joycon);
+
+            let mut calib = joycon.imu_user_calibration().clone();
+            if calib == IMUCalibration::Unavailable {
+                calib = joycon.imu_factory_calibration().clone();
+            }
+
+            let serial_number = serials.claim(joycon.serial_number(), mac);
+            tx.send(ChannelData {
+                serial_number: serial_number.clone(),
+                info: ChannelInfo::Connected(design),
+            })
+            .unwrap();
+
+            let device_type = joycon.device_type();
+            drop(joycon);
+
+            let stick_calib = read_stick_calibration(&mut driver);
+
+            if let Err(step) = setup(&mut driver, &serial_number, &device_type) {
+                setup_failures += 1;
+                println!(
+                    "\x1b[0;31m[ERROR]\x1b[0m {serial_number}: {step:?} was never acknowledged."
+                );
+                if setup_failures < SETUP_FAILURES_BEFORE_FALLBACK {
+                    tx.send(ChannelData::new(
+                        serial_number.clone(),
+                        ChannelInfo::SetupFailed,
+                    ))
+                    .unwrap();
+                    serials.release(&serial_number);
+                    drop(driver);
+                    println!(
+                        "[INFO] {serial_number}: Setting up the controller again in {}s.",
+                        setup_retry.as_secs()
+                    );
+                    thread::sleep(setup_retry);
+                    setup_retry = (setup_retry * 2).min(OPEN_RETRY_MAX);
+                    continue;
+                }
+                match SimpleHIDMode::new(driver) {
+                    Ok(simple) => {
+                        println!("[INFO] {serial_number}: Using buttons only.");
+                        tx.send(ChannelData::new(
+                            serial_number.clone(),
+                            ChannelInfo::ButtonsOnly,
+                        ))
+                        .unwrap();
+                        simple_hid_loop(simple, &tx, serial_number.clone());
+                    }
+                    Err(_) => {
+                        tx.send(ChannelData::new(
+                            serial_number.clone(),
+                            ChannelInfo::SetupFailed,
+                        ))
+                        .unwrap();
+                    }
+                }
+            } else if let Ok(standard) = StandardFullMode::new(driver) {
+                setup_failures = 0;
+                setup_retry = OPEN_RETRY_MIN;
+                joycon_listen_loop(
+                    standard,
+                    &tx,
+                    serial_number.clone(),
+                    calib,
+                    stick_calib,
+                    &settings,
+                    &snapshot,
+                );
+            }
+            serials.release(&serial_number);
+        }
+        // Joycon was disconnected, check for reconnection after 1 second
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
+
+pub fn spawn_thread(
+    tx: DeviceSender,
+    settings: settings::Handler,
+    snapshot: watch::Receiver<Arc<Snapshot>>,
+) {
+    let manager = JoyConManager::get_instance();
+    let devices = lock(&manager).new_devices();
+    let serials = Serials::new(settings.clone());
+    for d in devices.iter() {
+        let tx = tx.clone();
+        let settings = settings.clone();
+        let snapshot = snapshot.clone();
+        let serials = serials.clone();
+        thread::spawn(move || joycon_thread(d, tx, settings, snapshot, serials));
+    }
+}
@@ -1,17 +1,17 @@
 use std::{
     collections::HashSet,
-    sync::{mpsc, Arc},
+    path::Path,
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 use tokio::{sync::Mutex, time::interval};
 
-use evdev::{enumerate, EventStream, InputEventKind, Key};
+use evdev::{enumerate, AbsoluteAxisType, EventStream, InputEventKind, Key};
 use upower_dbus::{DeviceProxy, UPowerProxy};
 
-use crate::settings;
-
 use super::{
-    imu::JoyconAxisData, Battery, ChannelData, ChannelInfo, JoyconDesign, JoyconDesignType,
+    imu::JoyconAxisData, Battery, ChannelData, ChannelInfo, DeviceSender, JoyconDesign,
+    JoyconDesignType, Stick, Sticks,
 };
 
 // Resolution definitions from hid-nintendo.c from linux:
@@ -19,8 +19,8 @@ use super::{
 fn acc(n: i32) -> f64 {
     n as f64 / 4096f64 // JC_IMU_ACCEL_RES_PER_G
 }
-fn gyro(n: i32, scale: f64) -> f64 {
-    (n as f64 * scale / 14247f64) // JC_IMU_GYRO_RES_PER_DPS
+fn gyro(n: i32) -> f64 {
+    (n as f64 / 14247f64) // JC_IMU_GYRO_RES_PER_DPS
         .to_radians()
 }
 
@@ -35,6 +35,20 @@ const USB_DEVICE_ID_NINTENDO_JOYCONR: u16 = 0x2007;
 const USB_DEVICE_ID_NINTENDO_PROCON: u16 = 0x2009;
 const USB_DEVICE_ID_NINTENDO_CHRGGRIP: u16 = 0x200E;
 
+/// The hid-nintendo driver (Linux 5.16+) exposes controllers as evdev devices.
+/// Without it, the hidraw backend shared with other platforms is used instead.
+pub fn kernel_driver_loaded() -> bool {
+    Path::new("/sys/module/hid_nintendo").exists()
+}
+
+pub fn print_hidraw_guidance() {
+    println!("[INFO] hid-nintendo kernel module not loaded, reading controllers through hidraw.");
+    println!("If controllers don't show up, load it with \"sudo modprobe hid-nintendo\", or allow");
+    println!("access to hidraw with a udev rule in /etc/udev/rules.d/70-slimevr-wrangler.rules:");
+    println!("  KERNEL==\"hidraw*\", ATTRS{{idVendor}}==\"057e\", MODE=\"0660\", TAG+=\"uaccess\"");
+    println!("  KERNEL==\"hidraw*\", KERNELS==\"*057E:*\", MODE=\"0660\", TAG+=\"uaccess\"");
+}
+
 fn convert_design(product_code: u16) -> JoyconDesignType {
     match product_code {
         USB_DEVICE_ID_NINTENDO_JOYCONL => JoyconDesignType::Left,
@@ -54,19 +68,67 @@ fn convert_battery(battery: upower_dbus::BatteryLevel) -> Battery {
     }
 }
 
-async fn joycon_listener(tx: mpsc::Sender<ChannelData>, mut input: EventStream) {
-    let mac = input.device().unique_name().unwrap().to_string(); // Joycons always have unique name
+/// hid-nintendo sets the unique name of both the button and IMU device to the controller's MAC,
+/// which clones sharing a serial number don't share. Without one, the HID device both hang off
+/// still tells controllers apart.
+fn device_id(device: &evdev::Device) -> Arc<str> {
+    device
+        .unique_name()
+        .filter(|name| !name.is_empty())
+        .or_else(|| device.physical_path())
+        .unwrap_or_default()
+        .into()
+}
+
+async fn joycon_listener(tx: DeviceSender, mut input: EventStream) {
+    let mac = device_id(input.device());
+
+    let product = input.device().input_id().product();
+    let mut sticks = Sticks {
+        left: (product != USB_DEVICE_ID_NINTENDO_JOYCONR).then(Stick::default),
+        right: (product != USB_DEVICE_ID_NINTENDO_JOYCONL).then(Stick::default),
+    };
+    let mut last_sticks: Option<Sticks> = None;
 
     while let Ok(ev) = input.next_event().await {
-        if let InputEventKind::Key(key) = ev.kind() {
-            // if DPAD_UP or BTN_SOUTH and button is lifted
-            if (key == Key::BTN_DPAD_UP || key == Key::BTN_SOUTH) && ev.value() == 0 {
-                tx.send(ChannelData {
-                    serial_number: mac.clone(),
-                    info: ChannelInfo::Reset,
-                })
-                .unwrap();
+        match ev.kind() {
+            InputEventKind::Key(key) => {
+                // if DPAD_UP or BTN_SOUTH and button is lifted
+                if (key == Key::BTN_DPAD_UP || key == Key::BTN_SOUTH) && ev.value() == 0 {
+                    tx.send(ChannelData {
+                        serial_number: mac.clone(),
+                        info: ChannelInfo::Reset,
+                    })
+                    .ok();
+                }
             }
+            InputEventKind::AbsAxis(axis) => {
+                // The driver already applies the stick calibration, with down positive.
+                let value = ev.value() as f32 / 32767.0;
+                let (stick, x) = match axis {
+                    AbsoluteAxisType::ABS_X => (&mut sticks.left, true),
+                    AbsoluteAxisType::ABS_Y => (&mut sticks.left, false),
+                    AbsoluteAxisType::ABS_RX => (&mut sticks.right, true),
+                    AbsoluteAxisType::ABS_RY => (&mut sticks.right, false),
+                    _ => continue,
+                };
+                if let Some(stick) = stick {
+                    if x {
+                        stick.x = value;
+                    } else {
+                        stick.y = -value;
+                    }
+                }
+                if last_sticks.map_or(true, |last| sticks.moved_from(&last)) {
+                    last_sticks = Some(sticks);
+                    tx.send(ChannelData {
+                        serial_number: mac.clone(),
+                        info: ChannelInfo::Sticks(sticks),
+                    })
+                    .ok();
+                }
+            }
+            _ => {}
         }
     }
 
@@ -74,15 +136,11 @@ async fn joycon_listener(tx: mpsc::Sender<ChannelData>, mut input: EventStream)
         serial_number: mac,
         info: ChannelInfo::Disconnected,
     })
-    .unwrap();
+    .ok();
 }
 
-async fn imu_listener(
-    tx: mpsc::Sender<ChannelData>,
-    settings: settings::Handler,
-    mut input: EventStream,
-) {
-    let mac = input.device().unique_name().unwrap().to_string(); // Joycons always have unique name
+async fn imu_listener(tx: DeviceSender, mut input: EventStream) {
+    let mac = device_id(input.device());
     let mut imu_array = [JoyconAxisData {
         accel_x: 0.0,
         accel_y: 0.0,
@@ -93,20 +151,27 @@ async fn imu_listener(
     }; 3];
     let mut count = 0;
     let mut sys_time = SystemTime::now();
-    let mut last_event = input.device().get_abs_state().unwrap();
+    let Ok(mut last_event) = input.device().get_abs_state() else {
+        return;
+    };
 
     while let Ok(ev) = input.next_event().await {
         // If it's the same timestamp, just skip and remember the event
         if ev.timestamp() == sys_time {
-            last_event = input.device().get_abs_state().unwrap();
+            let Ok(state) = input.device().get_abs_state() else {
+                return;
+            };
+            last_event = state;
             continue;
         }
         sys_time = ev.timestamp();
 
-        let gyro_scale_factor = settings.load().joycon_scale_get(&mac);
         // We grab the last event so we actually announce it on the tx
         let axis = last_event;
-        last_event = input.device().get_abs_state().unwrap();
+        let Ok(state) = input.device().get_abs_state() else {
+            return;
+        };
+        last_event = state;
 
         let accel_axis = &axis[..3];
         let gyro_axis = &axis[3..6];
@@ -114,24 +179,28 @@ async fn imu_listener(
             accel_x: acc(accel_axis[0].value),
             accel_y: acc(accel_axis[1].value),
             accel_z: acc(accel_axis[2].value),
-            gyro_x: gyro(gyro_axis[0].value, gyro_scale_factor),
-            gyro_y: gyro(gyro_axis[1].value, gyro_scale_factor),
-            gyro_z: gyro(gyro_axis[2].value, gyro_scale_factor),
+            gyro_x: gyro(gyro_axis[0].value),
+            gyro_y: gyro(gyro_axis[1].value),
+            gyro_z: gyro(gyro_axis[2].value),
         };
 
         count += 1;
         if count == 3 {
             count = 0;
-            tx.send(ChannelData {
-                serial_number: mac.clone(),
-                info: ChannelInfo::ImuData(imu_array),
-            })
-            .unwrap();
+            if tx
+                .send(ChannelData {
+                    serial_number: mac.clone(),
+                    info: ChannelInfo::ImuData(imu_array),
+                })
+                .is_err()
+            {
+                return;
+            }
         }
     }
 }
 
-async fn check_batteries(tx: mpsc::Sender<ChannelData>, macs: &HashSet<String>) {
+async fn check_batteries(tx: DeviceSender, macs: &HashSet<Arc<str>>) {
     let Ok(connection) = zbus::Connection::system().await else {
         return;
     };
@@ -143,10 +212,10 @@ async fn check_batteries(tx: mpsc::Sender<ChannelData>, macs: &HashSet<String>)
             .unwrap();
         let Ok(serial) = device.serial().await else { continue; };
 
-        if macs.contains(&serial) {
+        if macs.contains(serial.as_str()) {
             let level = convert_battery(device.battery_level().await.unwrap());
             tx.send(ChannelData {
-                serial_number: serial,
+                serial_number: serial.into(),
                 info: ChannelInfo::Battery(level),
             })
             .unwrap();
@@ -155,7 +224,7 @@ async fn check_batteries(tx: mpsc::Sender<ChannelData>, macs: &HashSet<String>)
 }
 
 #[tokio::main]
-pub async fn spawn_thread(tx: mpsc::Sender<ChannelData>, settings: settings::Handler) {
+pub async fn spawn_thread(tx: DeviceSender) {
     if !users::group_access_list()
         .unwrap_or_default()
         .iter()
@@ -163,6 +232,7 @@ pub async fn spawn_thread(tx: mpsc::Sender<ChannelData>, settings: settings::Han
     {
         println!("\x1b[0;31m[ERROR]\x1b[0m Current user not in \"input\" group.");
         println!("You need to add your user to the \"input\" group to use Wrangler.");
+        println!("Run \"sudo usermod -aG input $USER\", then log out and back in.");
     }
 
     let mut slow_stream = interval(Duration::from_secs(2));
@@ -188,28 +258,30 @@ pub async fn spawn_thread(tx: mpsc::Sender<ChannelData>, settings: settings::Han
 
             if device.grab().is_err() {
                 println!(
-                    "Joycon {:?} is in use by another program.",
-                    device.unique_name()
+                    "Joycon {:?} is in use by another program (Steam or a game?).",
+                    device_id(&device)
                 );
                 continue;
             }
 
             paths.lock().await.insert(path.clone());
             let tx = tx.clone();
-            let settings = settings.clone();
 
             // The device name is defined on all nintendo devices in the kernel,
             // so unwrap shouldn't fail...
-            if device.name().unwrap().contains("IMU") {
+            if device.name().unwrap_or_default().contains("IMU") {
                 // Make IMU event listener
-                let stream = device.into_event_stream().unwrap();
+                let Ok(stream) = device.into_event_stream() else {
+                    paths.lock().await.remove(&path);
+                    continue;
+                };
                 let paths = paths.clone();
                 tokio::spawn(async move {
-                    imu_listener(tx, settings, stream).await;
+                    imu_listener(tx, stream).await;
                     paths.lock().await.remove(&path);
                 });
             } else {
-                let mac = device.unique_name().unwrap().to_string();
+                let mac = device_id(&device);
 
                 // Announce that a new device was connected
                 tx.send(ChannelData {
@@ -219,10 +291,13 @@ pub async fn spawn_thread(tx: mpsc::Sender<ChannelData>, settings: settings::Han
                         design_type: convert_design(device.input_id().product()),
                     }),
                 })
-                .unwrap();
+                .ok();
 
                 // Listen to events of the joycon
-                let stream = device.into_event_stream().unwrap();
+                let Ok(stream) = device.into_event_stream() else {
+                    paths.lock().await.remove(&path);
+                    continue;
+                };
 
                 let paths = paths.clone();
                 tokio::spawn(async move {
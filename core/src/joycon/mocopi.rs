@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    time::Duration,
+};
+
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+
+use super::{
+    communication::{ChannelData, ChannelInfo, DeviceSender},
+    imu::JoyconAxisData,
+    JoyconDesign, JoyconDesignType,
+};
+
+/// The mocopi app sends 50 frames per second.
+const FRAME_TIME: f64 = 0.02;
+/// Time between the samples made up for the fusion, the same as a Joy-Con's.
+const SAMPLE_TIME: f64 = 0.005;
+/// Made up angular velocities stay below this, in radians/s, so catching up after dropped
+/// frames isn't rejected as an outlier.
+const MAX_GYRO: f64 = 30.0;
+/// Dropped frames made up for at most, longer gaps are caught up as if they were this long.
+const MAX_CATCH_UP_FRAMES: u32 = 5;
+/// Samples made up for one frame at most, 7 reports. Enough to turn half way round without
+/// going over `MAX_GYRO`, and fits in the default queue of 8 reports per device.
+const MAX_SAMPLES: usize = 21;
+/// Without frames for this long, the trackers count as disconnected.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Skeleton bones where the six mocopi sensors sit, with the name their tracker gets. The
+/// ankle sensors turn with the lower leg.
+const SENSORS: [(u16, &str); 6] = [
+    (0, "hip"),
+    (10, "head"),
+    (14, "left_wrist"),
+    (18, "right_wrist"),
+    (20, "left_ankle"),
+    (24, "right_ankle"),
+];
+
+/// The chunks of a packet, each a little endian length, a four letter name and its data.
+fn chunks(mut data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = vec![];
+    while data.len() >= 8 {
+        let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let Some(body) = data.get(8..8 + len) else {
+            break;
+        };
+        chunks.push((&data[4..8], body));
+        data = &data[8 + len..];
+    }
+    chunks
+}
+
+fn find<'a>(data: &'a [u8], name: &[u8]) -> Option<&'a [u8]> {
+    chunks(data)
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, body)| body)
+}
+
+fn find_u16(data: &[u8], name: &[u8]) -> Option<u16> {
+    find(data, name).and_then(|b| Some(u16::from_le_bytes(b.get(..2)?.try_into().ok()?)))
+}
+
+/// The rotation of a bone transform: x, y, z and w, followed by the position.
+fn rotation(data: &[u8]) -> Option<UnitQuaternion<f64>> {
+    let tran = find(data, b"tran")?;
+    let value = |i: usize| -> Option<f64> {
+        Some(f32::from_le_bytes(tran.get(i * 4..i * 4 + 4)?.try_into().ok()?).into())
+    };
+    let (x, y, z, w) = (value(0)?, value(1)?, value(2)?, value(3)?);
+    Some(UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)))
+}
+
+/// Unity's left handed Y up axes to the right handed Z up ones of the fusion.
+fn to_z_up(rotation: UnitQuaternion<f64>) -> UnitQuaternion<f64> {
+    let q = rotation.quaternion();
+    UnitQuaternion::new_unchecked(Quaternion::new(q.w, -q.i, -q.k, -q.j))
+}
+
+/// Turns the rotations of one tracker into IMU samples, so they go through the same fusion,
+/// scale and mounting settings as a controller.
+struct Tracker {
+    serial_number: Arc<str>,
+    rotation: Option<UnitQuaternion<f64>>,
+    frame: u32,
+    pending: Vec<JoyconAxisData>,
+}
+impl Tracker {
+    fn new(name: &str) -> Self {
+        Self {
+            serial_number: format!("mocopi_{name}").into(),
+            rotation: None,
+            frame: 0,
+            pending: vec![],
+        }
+    }
+
+    /// Samples that turn the last rotation into this one, at a constant angular velocity. The
+    /// first frame only sets where to start from.
+    fn update(&mut self, frame: u32, rotation: UnitQuaternion<f64>) {
+        let previous_frame = std::mem::replace(&mut self.frame, frame);
+        let Some(previous) = self.rotation.replace(rotation) else {
+            return;
+        };
+        let frames = frame
+            .wrapping_sub(previous_frame)
+            .clamp(1, MAX_CATCH_UP_FRAMES);
+        let delta = previous.inverse() * rotation;
+        let count = ((f64::from(frames) * FRAME_TIME / SAMPLE_TIME)
+            .max(delta.angle() / (MAX_GYRO * SAMPLE_TIME))
+            .ceil() as usize)
+            .min(MAX_SAMPLES);
+        let gyro = delta.scaled_axis() / (count as f64 * SAMPLE_TIME);
+        for i in 1..=count {
+            let at = previous.slerp(&rotation, i as f64 / count as f64);
+            let accel = at.inverse() * Vector3::z();
+            self.pending.push(JoyconAxisData {
+                accel_x: accel.x,
+                accel_y: accel.y,
+                accel_z: accel.z,
+                gyro_x: gyro.x,
+                gyro_y: gyro.y,
+                gyro_z: gyro.z,
+            });
+        }
+    }
+
+    /// Sends the samples in reports of three, like a Joy-Con.
+    fn send(&mut self, tx: &DeviceSender) {
+        let full = self.pending.len() / 3 * 3;
+        let samples: Vec<_> = self.pending.drain(..full).collect();
+        for report in samples.chunks(3) {
+            tx.send(ChannelData::new(
+                self.serial_number.clone(),
+                ChannelInfo::ImuData([report[0], report[1], report[2]]),
+            ))
+            .ok();
+        }
+    }
+}
+
+/// Global rotations of the sensor bones out of the local ones in a frame.
+fn sensor_rotations(
+    fram: &[u8],
+    parents: &HashMap<u16, u16>,
+) -> Option<Vec<(usize, UnitQuaternion<f64>)>> {
+    let local: HashMap<u16, UnitQuaternion<f64>> = chunks(find(fram, b"btrs")?)
+        .into_iter()
+        .filter(|(name, _)| *name == b"btdt")
+        .filter_map(|(_, bone)| Some((find_u16(bone, b"bnid")?, rotation(bone)?)))
+        .collect();
+    let global = |mut id: u16| {
+        let mut rotation = UnitQuaternion::identity();
+        // The depth limit guards against a broken skeleton that loops.
+        for _ in 0..32 {
+            rotation = local.get(&id)? * rotation;
+            match parents.get(&id) {
+                Some(&parent) if parent != id => id = parent,
+                _ => return Some(rotation),
+            }
+        }
+        None
+    };
+    SENSORS
+        .iter()
+        .enumerate()
+        .map(|(index, &(bone, _))| Some((index, to_z_up(global(bone)?))))
+        .collect()
+}
+
+fn connected() -> ChannelInfo {
+    ChannelInfo::Connected(JoyconDesign {
+        color: "#dcdcdc".into(),
+        design_type: JoyconDesignType::Pro,
+    })
+}
+
+/// Receives the skeleton the mocopi app sends to a PC, and feeds the bones where its sensors
+/// sit as six trackers.
+pub fn spawn_thread(tx: DeviceSender, port: u16) {
+    let socket = match UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port))) {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m mocopi: Could not listen on port {port}: {e}");
+            return;
+        }
+    };
+    socket.set_read_timeout(Some(TIMEOUT)).ok();
+    println!("[INFO] mocopi: Listening on port {port}.");
+
+    let mut trackers: Vec<Tracker> = SENSORS.iter().map(|(_, name)| Tracker::new(name)).collect();
+    // Bone ids to their parent's, from the skeleton definition sent now and then.
+    let mut parents: HashMap<u16, u16> = HashMap::new();
+    let mut connected_trackers = false;
+    let mut buf = [0; 4096];
+    loop {
+        let Ok(len) = socket.recv(&mut buf) else {
+            if connected_trackers {
+                println!("[INFO] mocopi: No data for a while, disconnecting the trackers.");
+                for tracker in &mut trackers {
+                    tracker.rotation = None;
+                    tx.send(ChannelData::new(
+                        tracker.serial_number.clone(),
+                        ChannelInfo::Disconnected,
+                    ))
+                    .ok();
+                }
+                connected_trackers = false;
+            }
+            continue;
+        };
+        let packet = &buf[..len];
+        if let Some(skdf) = find(packet, b"skdf") {
+            if let Some(bons) = find(skdf, b"bons") {
+                parents = chunks(bons)
+                    .into_iter()
+                    .filter(|(name, _)| *name == b"bndt")
+                    .filter_map(|(_, bone)| {
+                        Some((find_u16(bone, b"bnid")?, find_u16(bone, b"pbid")?))
+                    })
+                    .collect();
+            }
+        }
+        let Some(fram) = find(packet, b"fram") else {
+            continue;
+        };
+        // Frames before the skeleton definition can't be put together.
+        if parents.is_empty() {
+            continue;
+        }
+        let Some(rotations) = sensor_rotations(fram, &parents) else {
+            continue;
+        };
+        let frame = find(fram, b"fnum")
+            .and_then(|b| Some(u32::from_le_bytes(b.get(..4)?.try_into().ok()?)))
+            .unwrap_or(0);
+        if !connected_trackers {
+            println!("[INFO] mocopi: Receiving, connecting the trackers.");
+            for tracker in &trackers {
+                tx.send(ChannelData::new(tracker.serial_number.clone(), connected()))
+                    .ok();
+            }
+            connected_trackers = true;
+        }
+        for (index, rotation) in rotations {
+            trackers[index].update(frame, rotation);
+            trackers[index].send(&tx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Unit;
+
+    use super::*;
+
+    fn chunk(name: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = (body.len() as u32).to_le_bytes().to_vec();
+        data.extend(name);
+        data.extend(body);
+        data
+    }
+    #[test]
+    fn chunks_in_order() {
+        let mut data = chunk(b"head", &[1, 2]);
+        data.extend(chunk(b"fram", &[3, 4, 5, 6]));
+        let found = chunks(&data);
+        let names: Vec<&[u8]> = found.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, [b"head", b"fram"]);
+        assert_eq!(found[1].1, [3, 4, 5, 6]);
+        assert_eq!(find_u16(&data, b"fram"), Some(0x0403));
+        assert_eq!(find_u16(&data, b"btdt"), None);
+    }
+    #[test]
+    fn chunks_stop_at_truncated() {
+        let mut data = chunk(b"head", &[1, 2]);
+        data.extend(&chunk(b"fram", &[3, 4, 5, 6])[..10]);
+        assert_eq!(chunks(&data).len(), 1);
+    }
+    #[test]
+    fn rotation_from_tran() {
+        let tran: Vec<u8> = [0.0f32, 0.6, 0.0, 0.8, 1.0, 2.0, 3.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let out = rotation(&chunk(b"tran", &tran)).unwrap();
+        let expected = UnitQuaternion::from_quaternion(Quaternion::new(0.8, 0.0, 0.6, 0.0));
+        assert!(out.angle_to(&expected) < 1.0e-6);
+        assert_eq!(rotation(&chunk(b"tran", &tran[..12])), None);
+    }
+    #[test]
+    fn z_up() {
+        let degrees = |axis: &Unit<Vector3<f64>>, angle: f64| {
+            UnitQuaternion::from_axis_angle(axis, f64::to_radians(angle))
+        };
+        // Turning left in Unity is a negative angle about y, up, and a positive one about z.
+        let turn = to_z_up(degrees(&Vector3::y_axis(), -90.0));
+        assert!(turn.angle_to(&degrees(&Vector3::z_axis(), 90.0)) < 1.0e-9);
+        let tilt = to_z_up(degrees(&Vector3::x_axis(), 30.0));
+        assert!(tilt.angle_to(&degrees(&Vector3::x_axis(), -30.0)) < 1.0e-9);
+    }
+}
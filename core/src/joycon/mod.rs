@@ -0,0 +1,53 @@
+//mod ui;
+mod axes;
+pub use axes::{Axes, Axis};
+mod buttons;
+pub use buttons::{Button, ButtonAction, ButtonMapping};
+mod imu;
+mod pipeline;
+pub use pipeline::{Pipeline, RotationStage, SampleStage};
+mod raw_log;
+mod rerun_viewer;
+mod script;
+mod stick;
+pub use stick::{Stick, Sticks};
+
+mod communication;
+pub use communication::*;
+
+#[cfg(target_os = "windows")]
+mod ble_integration;
+mod integration;
+#[cfg(target_os = "linux")]
+mod linux_integration;
+use integration::spawn_thread;
+#[cfg(target_os = "linux")]
+pub use linux_integration::kernel_driver_loaded;
+mod demo_integration;
+mod mocopi;
+mod replay;
+mod serial;
+pub use replay::Playback;
+
+/// Demo trackers and replayed logs aren't real controllers, nothing about them is remembered.
+pub(crate) fn is_simulated(serial_number: &str) -> bool {
+    serial_number.starts_with(demo_integration::SERIAL_PREFIX)
+        || serial_number.ends_with(replay::SERIAL_SUFFIX)
+}
+
+/// The serial number a controller's tuning is stored under. A replayed log goes through the
+/// settings of the controller that recorded it, so tuning it tunes that controller.
+pub(crate) fn tuning_serial(serial_number: &str) -> &str {
+    serial_number
+        .strip_suffix(replay::SERIAL_SUFFIX)
+        .unwrap_or(serial_number)
+}
+mod server_lost;
+mod steamvr;
+mod usb;
+
+mod wrapper;
+pub use wrapper::*;
+
+mod design;
+pub use design::*;
@@ -0,0 +1,301 @@
+use std::f64::consts::TAU;
+
+use nalgebra::{Quaternion, Unit, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+use super::{imu::JoyconAxisData, script::Script, Axes};
+
+/// Largest angular velocity the Joy-Con gyro reports, in radians/s. A saturated reading is
+/// 32767 * 0.07 degrees/s, a little above its nominal 2000 degrees/s.
+const GYRO_RANGE: f64 = 40.1;
+/// Largest acceleration the Joy-Con accelerometer measures, in G.
+const ACCEL_RANGE: f64 = 8.0;
+
+/// A step an IMU sample goes through before fusion.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleStage {
+    /// Clamps samples to what the sensor can measure, from corrupted reports. Samples are never
+    /// dropped here, fusion assumes a fixed time between them.
+    #[serde(alias = "OutlierReject")]
+    Clamp,
+    /// Multiplies the gyro by the controller's scale factor. Pitch and roll are scaled too, and
+    /// then pulled back by the accelerometer.
+    Scale,
+    /// Subtracts the controller's gyro bias.
+    Calibration,
+    /// Remaps the axes.
+    Axes,
+    /// Runs the controller's script.
+    Script,
+}
+
+/// A step the fused rotation goes through before it is sent.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotationStage {
+    /// Scales the turn about `scale_axis` by the controller's yaw scale. The tilt away from
+    /// that axis is left alone, unless the controller has a tilt scale.
+    Scale,
+    /// Limits how fast the rotation turns to `max_angular_velocity`.
+    Smoothing,
+    /// Turns the rotation by the controller's mounting rotation.
+    Mounting,
+}
+
+/// The stages a controller's data goes through in order, samples before fusion and the
+/// rotation after it. Stages left out are skipped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Pipeline {
+    pub samples: Vec<SampleStage>,
+    pub rotation: Vec<RotationStage>,
+}
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            samples: vec![
+                SampleStage::Clamp,
+                SampleStage::Calibration,
+                SampleStage::Axes,
+                SampleStage::Script,
+            ],
+            rotation: vec![
+                RotationStage::Scale,
+                RotationStage::Smoothing,
+                RotationStage::Mounting,
+            ],
+        }
+    }
+}
+impl Pipeline {
+    /// The default before the scale moved after fusion, saved in older settings files.
+    fn gyro_scale_default() -> Self {
+        Self {
+            samples: vec![
+                SampleStage::Clamp,
+                SampleStage::Scale,
+                SampleStage::Calibration,
+                SampleStage::Axes,
+                SampleStage::Script,
+            ],
+            rotation: vec![RotationStage::Smoothing, RotationStage::Mounting],
+        }
+    }
+
+    /// The current default for a pipeline left at the old one, others are kept.
+    pub(crate) fn upgraded(self) -> Self {
+        if self == Self::gyro_scale_default() {
+            Self::default()
+        } else {
+            self
+        }
+    }
+}
+
+/// Settings of one controller the sample stages use, read once per report.
+pub struct SampleSettings {
+    pub gyro_scale: f64,
+    /// In radians/s.
+    pub gyro_bias: [f64; 3],
+    pub axes: Axes,
+    pub flip_handedness: bool,
+}
+
+impl Pipeline {
+    /// Runs a sample through the stages before fusion, `None` if one dropped it.
+    pub(crate) fn samples(
+        &self,
+        frame: JoyconAxisData,
+        settings: &SampleSettings,
+        script: &mut Option<Script>,
+    ) -> Option<JoyconAxisData> {
+        self.samples
+            .iter()
+            .try_fold(frame, |frame, stage| match stage {
+                SampleStage::Clamp => Some(frame.clamped()),
+                SampleStage::Scale => Some(frame.with_gyro_scale(settings.gyro_scale)),
+                SampleStage::Calibration => Some(frame.without_gyro_bias(settings.gyro_bias)),
+                SampleStage::Axes => Some(settings.axes.apply(settings.flip_handedness, frame)),
+                SampleStage::Script => match script {
+                    Some(script) => script.transform(frame),
+                    None => Some(frame),
+                },
+            })
+    }
+}
+
+/// The angle turned about the scale axis, counted on past half a turn so the scaled rotation
+/// doesn't jump when crossing it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Twist {
+    last: f64,
+    total: f64,
+}
+
+/// Splits `rotation` into a turn about `axis` and the tilt away from it, and scales their
+/// angles separately, so neither leaks into the other. `axis` is in world space, like the up
+/// axis for yaw.
+pub(crate) fn scale_about_axis(
+    rotation: UnitQuaternion<f64>,
+    axis: &Unit<Vector3<f64>>,
+    turn_scale: f64,
+    tilt_scale: f64,
+    twist: &mut Option<Twist>,
+) -> UnitQuaternion<f64> {
+    let q = rotation.quaternion();
+    let along = q.imag().dot(axis);
+    // Tilted half a turn, the turn about the axis is undefined.
+    let Some(turn) = Unit::try_new(Quaternion::from_parts(q.w, axis.scale(along)), 1.0e-9) else {
+        return rotation;
+    };
+    let tilt = turn.inverse() * rotation;
+    // Between -180 and 180 degrees, `q` and `-q` are the same rotation.
+    let wrap = |angle: f64| angle - TAU * (angle / TAU).round();
+    let angle = wrap(2.0 * along.atan2(q.w));
+    let twist = twist.get_or_insert(Twist {
+        last: angle,
+        total: angle,
+    });
+    twist.total += wrap(angle - twist.last);
+    twist.last = angle;
+    UnitQuaternion::from_axis_angle(axis, twist.total * turn_scale)
+        * UnitQuaternion::from_scaled_axis(tilt.scaled_axis() * tilt_scale)
+}
+
+impl JoyconAxisData {
+    fn in_range(&self) -> bool {
+        let accel = [self.accel_x, self.accel_y, self.accel_z];
+        let gyro = [self.gyro_x, self.gyro_y, self.gyro_z];
+        accel.iter().all(|a| a.abs() <= ACCEL_RANGE) && gyro.iter().all(|g| g.abs() <= GYRO_RANGE)
+    }
+
+    fn clamped(self) -> Self {
+        if self.in_range() {
+            return self;
+        }
+        // NaN from a corrupted report would poison the fusion for good.
+        let clamp = |v: f64, range: f64| {
+            if v.is_nan() {
+                0.0
+            } else {
+                v.clamp(-range, range)
+            }
+        };
+        Self {
+            accel_x: clamp(self.accel_x, ACCEL_RANGE),
+            accel_y: clamp(self.accel_y, ACCEL_RANGE),
+            accel_z: clamp(self.accel_z, ACCEL_RANGE),
+            gyro_x: clamp(self.gyro_x, GYRO_RANGE),
+            gyro_y: clamp(self.gyro_y, GYRO_RANGE),
+            gyro_z: clamp(self.gyro_z, GYRO_RANGE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use super::{scale_about_axis, Axes, JoyconAxisData, Pipeline, SampleSettings, SampleStage};
+
+    fn sample(gyro_x: f64) -> JoyconAxisData {
+        JoyconAxisData {
+            accel_x: 0.0,
+            accel_y: 0.0,
+            accel_z: 1.0,
+            gyro_x,
+            gyro_y: 0.0,
+            gyro_z: 0.0,
+        }
+    }
+    fn settings() -> SampleSettings {
+        SampleSettings {
+            gyro_scale: 2.0,
+            gyro_bias: [0.5, 0.0, 0.0],
+            axes: Axes::default(),
+            flip_handedness: false,
+        }
+    }
+    #[test]
+    fn in_range() {
+        assert!(sample(0.0).in_range());
+        // A saturated gyro is a real reading.
+        assert!(sample(32767.0 * 0.07f64.to_radians()).in_range());
+        assert!(!sample(100.0).in_range());
+        assert!(!sample(f64::NAN).in_range());
+    }
+    #[test]
+    fn clamp_keeps_fast_samples() {
+        let saturated = sample(-32767.0 * 0.07f64.to_radians());
+        let out = Pipeline::default()
+            .samples(saturated, &settings(), &mut None)
+            .unwrap();
+        assert_eq!(out.gyro_x, saturated.gyro_x - 0.5);
+
+        let out = Pipeline::default()
+            .samples(sample(1000.0), &settings(), &mut None)
+            .unwrap();
+        assert_eq!(out.gyro_x, super::GYRO_RANGE - 0.5);
+    }
+    #[test]
+    fn samples_in_order() {
+        let scale_first = Pipeline {
+            samples: vec![SampleStage::Scale, SampleStage::Calibration],
+            rotation: vec![],
+        };
+        let bias_first = Pipeline {
+            samples: vec![SampleStage::Calibration, SampleStage::Scale],
+            rotation: vec![],
+        };
+        let out = scale_first.samples(sample(1.0), &settings(), &mut None);
+        assert_eq!(out.unwrap().gyro_x, 1.5);
+        let out = bias_first.samples(sample(1.0), &settings(), &mut None);
+        assert_eq!(out.unwrap().gyro_x, 1.0);
+    }
+    #[test]
+    fn samples_skip_left_out_stages() {
+        let pipeline = Pipeline {
+            samples: vec![],
+            rotation: vec![],
+        };
+        let out = pipeline.samples(sample(1000.0), &settings(), &mut None);
+        assert_eq!(out, Some(sample(1000.0)));
+    }
+    fn yaw(degrees: f64) -> UnitQuaternion<f64> {
+        UnitQuaternion::from_axis_angle(&Vector3::z_axis(), degrees.to_radians())
+    }
+    #[test]
+    fn scale_turn() {
+        let out = scale_about_axis(yaw(40.0), &Vector3::z_axis(), 1.5, 1.0, &mut None);
+        assert!(out.angle_to(&yaw(60.0)) < 1.0e-9);
+    }
+    #[test]
+    fn scale_turn_past_half() {
+        let mut twist = None;
+        scale_about_axis(yaw(170.0), &Vector3::z_axis(), 1.5, 1.0, &mut twist);
+        // 190 degrees comes back as -170, it's counted on instead of jumping back.
+        let out = scale_about_axis(yaw(190.0), &Vector3::z_axis(), 1.5, 1.0, &mut twist);
+        assert!(out.angle_to(&yaw(285.0)) < 1.0e-9);
+    }
+    #[test]
+    fn scale_tilt_separately() {
+        let tilt = |degrees: f64| {
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), f64::to_radians(degrees))
+        };
+        let out = scale_about_axis(tilt(30.0), &Vector3::z_axis(), 2.0, 1.0, &mut None);
+        assert!(out.angle_to(&tilt(30.0)) < 1.0e-9);
+        let out = scale_about_axis(tilt(30.0), &Vector3::z_axis(), 1.0, 0.5, &mut None);
+        assert!(out.angle_to(&tilt(15.0)) < 1.0e-9);
+        let out = scale_about_axis(
+            yaw(40.0) * tilt(20.0),
+            &Vector3::z_axis(),
+            1.0,
+            0.0,
+            &mut None,
+        );
+        assert!(out.angle_to(&yaw(40.0)) < 1.0e-9);
+    }
+    #[test]
+    fn old_stage_name() {
+        let stage: SampleStage = serde_json::from_str("\"OutlierReject\"").unwrap();
+        assert_eq!(stage, SampleStage::Clamp);
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use directories::ProjectDirs;
+
+use super::imu::JoyconAxisData;
+
+/// Folder the raw IMU logs go in, in the app data folder.
+fn folder() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "SlimeVR Wrangler").map(|pd| pd.data_dir().join("raw-imu"))
+}
+
+/// Calibrated gyro (radians/s) and accelerometer (G) samples of one controller, as received
+/// and before the pipeline, for offline analysis and replay.
+pub struct RawLog {
+    file: BufWriter<File>,
+    start: Instant,
+}
+impl RawLog {
+    pub fn create(serial_number: &str) -> io::Result<(Self, PathBuf)> {
+        let dir = folder().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        fs::create_dir_all(&dir)?;
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        // Clone suffixes like #2 don't belong in file names.
+        let path = dir.join(format!("{}-{secs}.csv", serial_number.replace('#', "_")));
+        let mut file = BufWriter::new(File::create(&path)?);
+        // The file name can't hold the serial number as it is, replay needs it for the tuning.
+        writeln!(file, "# serial_number={serial_number}")?;
+        writeln!(
+            file,
+            "time,sample,accel_x,accel_y,accel_z,gyro_x,gyro_y,gyro_z"
+        )?;
+        let log = Self {
+            file,
+            start: Instant::now(),
+        };
+        Ok((log, path))
+    }
+
+    /// Writes the samples of one report. The time is monotonic, in seconds since the log
+    /// started, taken when the report is processed. A report holds three samples about 5 ms
+    /// apart, numbered oldest first.
+    pub fn write(&mut self, frames: &[JoyconAxisData]) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        for (i, f) in frames.iter().enumerate() {
+            writeln!(
+                self.file,
+                "{time:.6},{i},{},{},{},{},{},{}",
+                f.accel_x, f.accel_y, f.accel_z, f.gyro_x, f.gyro_y, f.gyro_z
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{
+    communication::{ChannelData, ChannelInfo, DeviceSender},
+    imu::JoyconAxisData,
+    JoyconDesign, JoyconDesignType,
+};
+
+/// Added to the serial number of the controller that recorded a replayed log.
+pub(crate) const SERIAL_SUFFIX: &str = "#replay";
+
+/// Longest pause kept between reports, longer gaps in the log are dropouts that would only
+/// hold up the replay.
+const MAX_GAP: f64 = 0.1;
+
+/// One report of a raw IMU log, with the seconds since the log started.
+struct Report {
+    time: f64,
+    samples: [JoyconAxisData; 3],
+}
+
+/// A raw IMU log, with the serial number of the controller that recorded it if the log has it.
+struct Log {
+    recorded_by: Option<String>,
+    reports: Vec<Report>,
+}
+
+/// Reads a raw IMU log, three rows with the same time per report after a line of column names.
+/// Lines starting with `#` are comments, `# serial_number=...` names the controller.
+fn load(path: &Path) -> Result<Log, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut recorded_by = None;
+    let mut header = true;
+    let mut reports: Vec<Report> = vec![];
+    for (number, line) in content.lines().enumerate() {
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(serial_number) = comment.trim().strip_prefix("serial_number=") {
+                recorded_by = Some(serial_number.to_owned());
+            }
+            continue;
+        }
+        if std::mem::take(&mut header) {
+            continue;
+        }
+        let values: Vec<f64> = line
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("line {} is not a raw IMU sample", number + 1))?;
+        let [time, sample, accel_x, accel_y, accel_z, gyro_x, gyro_y, gyro_z] = values[..] else {
+            return Err(format!("line {} is not a raw IMU sample", number + 1));
+        };
+        let frame = JoyconAxisData {
+            accel_x,
+            accel_y,
+            accel_z,
+            gyro_x,
+            gyro_y,
+            gyro_z,
+        };
+        match reports.last_mut() {
+            Some(report) if sample > 0.0 && report.time == time => {
+                report.samples[(sample as usize).min(2)] = frame;
+            }
+            _ => reports.push(Report {
+                time,
+                samples: [frame; 3],
+            }),
+        }
+    }
+    if reports.is_empty() {
+        return Err("the log has no samples".into());
+    }
+    Ok(Log {
+        recorded_by,
+        reports,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct State {
+    speed: f64,
+    looping: bool,
+    position: f64,
+    duration: f64,
+    seek: Option<f64>,
+}
+
+/// Controls for a replayed raw IMU log, shared with its thread.
+#[derive(Clone)]
+pub struct Playback(Arc<Mutex<State>>);
+impl Playback {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// 1.0 is the speed it was recorded at.
+    pub fn speed(&self) -> f64 {
+        self.state().speed
+    }
+    pub fn set_speed(&self, speed: f64) {
+        self.state().speed = speed.max(0.01);
+    }
+    pub fn looping(&self) -> bool {
+        self.state().looping
+    }
+    pub fn set_looping(&self, looping: bool) {
+        self.state().looping = looping;
+    }
+    /// Seconds into the log.
+    pub fn position(&self) -> f64 {
+        self.state().position
+    }
+    pub fn duration(&self) -> f64 {
+        self.state().duration
+    }
+    /// Continues from this many seconds into the log, also after the end was reached.
+    pub fn seek(&self, seconds: f64) {
+        self.state().seek = Some(seconds);
+    }
+}
+
+fn connected() -> ChannelInfo {
+    ChannelInfo::Connected(JoyconDesign {
+        color: "#828282".into(),
+        design_type: JoyconDesignType::Left,
+    })
+}
+
+/// Feeds a raw IMU log back as a controller named after the one that recorded it, so the same
+/// motion can be watched again while tuning the settings it goes through. The fusion starts
+/// over on every seek and loop, like the controller reconnected.
+pub fn spawn(tx: DeviceSender, path: &Path) -> Result<Playback, String> {
+    let Log {
+        recorded_by,
+        reports,
+    } = load(path)?;
+    let start = reports[0].time;
+    let playback = Playback(Arc::new(Mutex::new(State {
+        speed: 1.0,
+        looping: true,
+        position: 0.0,
+        duration: reports[reports.len() - 1].time - start,
+        seek: None,
+    })));
+    // Older raw logs only have the serial number in their name, with the time they started.
+    let recorded_by = recorded_by.unwrap_or_else(|| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.rsplit_once('-'))
+            .map_or("replay", |(serial_number, _)| serial_number)
+            .to_owned()
+    });
+    let sn: Arc<str> = format!("{recorded_by}{SERIAL_SUFFIX}").into();
+    println!("[INFO] Replaying {} as {sn}", path.display());
+
+    let controls = playback.clone();
+    thread::spawn(move || {
+        let mut index = 0;
+        tx.send(ChannelData::new(sn.clone(), connected())).ok();
+        loop {
+            let (speed, looping, seek) = {
+                let mut state = controls.state();
+                (state.speed, state.looping, state.seek.take())
+            };
+            let restart = match seek {
+                Some(seconds) => Some(reports.partition_point(|r| r.time - start < seconds)),
+                None if index >= reports.len() && looping => Some(0),
+                None => None,
+            };
+            if let Some(restart) = restart {
+                index = restart;
+                tx.send(ChannelData::new(sn.clone(), connected())).ok();
+            }
+            let Some(report) = reports.get(index) else {
+                // Stopped at the end until seeking or looping.
+                thread::sleep(Duration::from_millis(50));
+                continue;
+            };
+            if tx
+                .send(ChannelData::new(
+                    sn.clone(),
+                    ChannelInfo::ImuData(report.samples),
+                ))
+                .is_err()
+            {
+                return;
+            }
+            controls.state().position = report.time - start;
+            index += 1;
+            let gap = reports
+                .get(index)
+                .map_or(0.0, |next| (next.time - report.time).clamp(0.0, MAX_GAP));
+            thread::sleep(Duration::from_secs_f64(gap / speed));
+        }
+    });
+    Ok(playback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(name: &str, content: &str) -> Result<Log, String> {
+        let path = std::env::temp_dir().join(format!("wrangler-{name}-{}.csv", std::process::id()));
+        fs::write(&path, content).unwrap();
+        let log = load(&path);
+        fs::remove_file(path).ok();
+        log
+    }
+    #[test]
+    fn groups_samples() {
+        let reports = load_str(
+            "replay-groups",
+            "time,sample,ax,ay,az,gx,gy,gz\n\
+             0.0,0,0,0,1,1,0,0\n\
+             0.0,1,0,0,1,2,0,0\n\
+             0.0,2,0,0,1,3,0,0\n\
+             0.015,0,0,0,1,4,0,0\n",
+        )
+        .unwrap()
+        .reports;
+        assert_eq!(reports.len(), 2);
+        let gyro_x: Vec<f64> = reports[0].samples.iter().map(|s| s.gyro_x).collect();
+        assert_eq!(gyro_x, [1.0, 2.0, 3.0]);
+        assert_eq!(reports[1].time, 0.015);
+        assert_eq!(reports[1].samples, [reports[1].samples[0]; 3]);
+    }
+    #[test]
+    fn serial_number_from_comment() {
+        let log = load_str(
+            "replay-serial",
+            "# serial_number=000000000001#a1b2c3\n\
+             time,sample,ax,ay,az,gx,gy,gz\n\
+             0.0,0,0,0,1,1,0,0\n",
+        )
+        .unwrap();
+        assert_eq!(log.recorded_by.as_deref(), Some("000000000001#a1b2c3"));
+        assert_eq!(log.reports.len(), 1);
+        let log = load_str("replay-no-serial", "header\n0.0,0,0,0,1,1,0,0\n").unwrap();
+        assert_eq!(log.recorded_by, None);
+    }
+    #[test]
+    fn rejects_bad_lines() {
+        let error = load_str("replay-short", "header\n0.0,0,0,0,1\n").err();
+        assert_eq!(error.as_deref(), Some("line 2 is not a raw IMU sample"));
+        let error = load_str("replay-text", "header\n0.0,0,a,0,1,0,0,0\n").err();
+        assert_eq!(error.as_deref(), Some("line 2 is not a raw IMU sample"));
+        let error = load_str("replay-empty", "header\n").err();
+        assert_eq!(error.as_deref(), Some("the log has no samples"));
+    }
+}
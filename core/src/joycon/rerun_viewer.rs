@@ -0,0 +1,98 @@
+//! Streams IMU samples and fused rotations to a [rerun](https://rerun.io) viewer, for
+//! debugging fusion and filters. Only built with the `rerun` feature, otherwise a no-op.
+
+use nalgebra::UnitQuaternion;
+
+use super::imu::JoyconAxisData;
+
+#[cfg(feature = "rerun")]
+pub struct Viewer {
+    rec: rerun::RecordingStream,
+    start: std::time::Instant,
+}
+#[cfg(feature = "rerun")]
+impl Viewer {
+    /// Connects to a viewer on the default port, started with `rerun`. Data is buffered until
+    /// the viewer shows up.
+    pub fn connect() -> Option<Self> {
+        match rerun::RecordingStreamBuilder::new("slimevr-wrangler")
+            .connect(rerun::default_server_addr(), rerun::default_flush_timeout())
+        {
+            Ok(rec) => {
+                println!("[INFO] Streaming to rerun viewer");
+                Some(Self {
+                    rec,
+                    start: std::time::Instant::now(),
+                })
+            }
+            Err(e) => {
+                println!("\x1b[0;31m[ERROR]\x1b[0m Could not connect to rerun viewer: {e}");
+                None
+            }
+        }
+    }
+
+    fn scalar(&self, path: String, value: f64) {
+        self.rec
+            .log(path, &rerun::TimeSeriesScalar::new(value))
+            .ok();
+    }
+
+    /// Logs the samples of one report, and the rotation fused from them.
+    pub fn log(
+        &self,
+        serial_number: &str,
+        frames: &[JoyconAxisData],
+        rotation: UnitQuaternion<f64>,
+    ) {
+        self.rec
+            .set_time_seconds("time", self.start.elapsed().as_secs_f64());
+        for f in frames {
+            for (name, value) in [
+                ("accel/x", f.accel_x),
+                ("accel/y", f.accel_y),
+                ("accel/z", f.accel_z),
+                ("gyro/x", f.gyro_x),
+                ("gyro/y", f.gyro_y),
+                ("gyro/z", f.gyro_z),
+            ] {
+                self.scalar(format!("trackers/{serial_number}/{name}"), value);
+            }
+        }
+        let (roll, pitch, yaw) = rotation.euler_angles();
+        for (name, value) in [("roll", roll), ("pitch", pitch), ("yaw", yaw)] {
+            self.scalar(
+                format!("trackers/{serial_number}/rotation/{name}"),
+                value.to_degrees(),
+            );
+        }
+
+        // A box about the shape of a Joy-Con, turning with the tracker.
+        let q = rotation.coords;
+        let path = format!("world/{serial_number}");
+        self.rec
+            .log(
+                path.as_str(),
+                &rerun::Transform3D::from_rotation(rerun::Quaternion::from_xyzw([
+                    q.x as f32, q.y as f32, q.z as f32, q.w as f32,
+                ])),
+            )
+            .ok();
+        self.rec
+            .log(
+                format!("{path}/box"),
+                &rerun::Boxes3D::from_half_sizes([(0.5, 1.0, 0.3)]),
+            )
+            .ok();
+    }
+}
+
+#[cfg(not(feature = "rerun"))]
+pub struct Viewer;
+#[cfg(not(feature = "rerun"))]
+impl Viewer {
+    pub fn connect() -> Option<Self> {
+        None
+    }
+    pub fn log(&self, _: &str, _: &[JoyconAxisData], _: UnitQuaternion<f64>) {}
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+
+use super::imu::JoyconAxisData;
+
+/// Runs for every sample, so a script stuck in a loop is stopped instead of holding up every
+/// controller.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Scripts return `0` as readily as `0.0`.
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|i| i as f64))
+}
+
+/// A user script that can change or drop every IMU sample of a controller before fusion.
+///
+/// The script defines `fn transform(sample)`, where `sample` is a map with `accel_x`, `accel_y`,
+/// `accel_z` (in G) and `gyro_x`, `gyro_y`, `gyro_z` (in radians/s). It returns the changed map,
+/// or `()` to drop the sample.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    name: String,
+    failed: bool,
+}
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(MAX_CALL_LEVELS);
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            engine,
+            ast,
+            name: path.display().to_string(),
+            failed: false,
+        })
+    }
+
+    pub fn transform(&mut self, sample: JoyconAxisData) -> Option<JoyconAxisData> {
+        // A broken script would print an error for every sample, pass them through instead.
+        if self.failed {
+            return Some(sample);
+        }
+        let mut map = Map::new();
+        for (key, value) in [
+            ("accel_x", sample.accel_x),
+            ("accel_y", sample.accel_y),
+            ("accel_z", sample.accel_z),
+            ("gyro_x", sample.gyro_x),
+            ("gyro_y", sample.gyro_y),
+            ("gyro_z", sample.gyro_z),
+        ] {
+            map.insert(key.into(), value.into());
+        }
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(
+                // Top level statements would run again for every sample.
+                CallFnOptions::new().eval_ast(false),
+                &mut Scope::new(),
+                &self.ast,
+                "transform",
+                (map,),
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|result| {
+                if result.is_unit() {
+                    return Ok(None);
+                }
+                let map = result
+                    .try_cast::<Map>()
+                    .ok_or("transform must return the sample or ()")?;
+                let get = |key: &str| {
+                    map.get(key)
+                        .and_then(number)
+                        .ok_or(format!("{key} is missing or not a number"))
+                };
+                Ok(Some(JoyconAxisData {
+                    accel_x: get("accel_x")?,
+                    accel_y: get("accel_y")?,
+                    accel_z: get("accel_z")?,
+                    gyro_x: get("gyro_x")?,
+                    gyro_y: get("gyro_y")?,
+                    gyro_z: get("gyro_z")?,
+                }))
+            });
+        match result {
+            Ok(sample) => sample,
+            Err(e) => {
+                println!(
+                    "\x1b[0;31m[ERROR]\x1b[0m Script {} failed, it is disabled until the controller reconnects: {e}",
+                    self.name
+                );
+                self.failed = true;
+                Some(sample)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{JoyconAxisData, Script};
+
+    fn script(name: &str, source: &str) -> Script {
+        let path = std::env::temp_dir().join(format!("wrangler-test-{name}.rhai"));
+        fs::write(&path, source).unwrap();
+        let script = Script::load(&PathBuf::from(&path)).unwrap();
+        fs::remove_file(path).ok();
+        script
+    }
+    fn sample() -> JoyconAxisData {
+        JoyconAxisData {
+            accel_x: 0.0,
+            accel_y: 0.0,
+            accel_z: 1.0,
+            gyro_x: 0.5,
+            gyro_y: 0.0,
+            gyro_z: 0.0,
+        }
+    }
+    #[test]
+    fn integer_values() {
+        let mut script = script("int", "fn transform(s) { s.gyro_x = 0; s }");
+        let out = script.transform(sample()).unwrap();
+        assert_eq!(out.gyro_x, 0.0);
+        assert!(!script.failed);
+    }
+    #[test]
+    fn drops_samples() {
+        let mut script = script("drop", "fn transform(s) { () }");
+        assert_eq!(script.transform(sample()), None);
+    }
+    #[test]
+    fn endless_loop_is_stopped() {
+        let mut script = script("loop", "fn transform(s) { loop {} }");
+        assert_eq!(script.transform(sample()), Some(sample()));
+        assert!(script.failed);
+    }
+    #[test]
+    fn top_level_not_run() {
+        // Would hit the operation limit if it ran for every sample.
+        let mut script = script("top", "loop {}\nfn transform(s) { s }");
+        assert_eq!(script.transform(sample()), Some(sample()));
+        assert!(!script.failed);
+    }
+}
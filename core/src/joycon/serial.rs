@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    communication::{ChannelData, ChannelInfo, DeviceSender},
+    imu::JoyconAxisData,
+    JoyconDesign, JoyconDesignType,
+};
+
+/// Time between the samples the fusion expects, the same as a Joy-Con's.
+const SAMPLE_TIME: f64 = 0.005;
+/// Longer gaps between lines are the board resetting or stalling, not motion to catch up on.
+const MAX_GAP: f64 = 0.1;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// A board that sends nothing for this long counts as gone, and the port is opened again.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One line from the board: accelerometer x, y, z in G and gyro x, y, z in degrees/s,
+/// separated by commas, spaces or tabs. Boards with several IMUs put the sensor's index first.
+fn parse_line(line: &str) -> Option<(usize, JoyconAxisData)> {
+    let values: Vec<f64> = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let (index, values) = match values.len() {
+        6 => (0, &values[..]),
+        7 => (values[0] as usize, &values[1..]),
+        _ => return None,
+    };
+    Some((
+        index,
+        JoyconAxisData {
+            accel_x: values[0],
+            accel_y: values[1],
+            accel_z: values[2],
+            gyro_x: values[3].to_radians(),
+            gyro_y: values[4].to_radians(),
+            gyro_z: values[5].to_radians(),
+        },
+    ))
+}
+
+/// Repeats or skips lines to make samples every [`SAMPLE_TIME`], whatever rate the board
+/// sends at, and sends them in reports of three like a Joy-Con. Also used for other devices
+/// that send one sample at a time.
+pub(super) struct Sensor {
+    serial_number: Arc<str>,
+    last: Instant,
+    due: f64,
+    pending: Vec<JoyconAxisData>,
+}
+impl Sensor {
+    pub(super) fn new(serial_number: Arc<str>) -> Self {
+        Self {
+            serial_number,
+            last: Instant::now(),
+            due: 0.0,
+            pending: vec![],
+        }
+    }
+    pub(super) fn push(&mut self, tx: &DeviceSender, sample: JoyconAxisData) {
+        let now = Instant::now();
+        self.due += now.duration_since(self.last).as_secs_f64().min(MAX_GAP);
+        self.last = now;
+        while self.due >= SAMPLE_TIME {
+            self.due -= SAMPLE_TIME;
+            self.pending.push(sample);
+        }
+        let full = self.pending.len() / 3 * 3;
+        let samples: Vec<_> = self.pending.drain(..full).collect();
+        for report in samples.chunks(3) {
+            tx.send(ChannelData::new(
+                self.serial_number.clone(),
+                ChannelInfo::ImuData([report[0], report[1], report[2]]),
+            ))
+            .ok();
+        }
+    }
+}
+
+/// Reads the port until it fails or goes quiet.
+fn read_port(tx: &DeviceSender, path: &str, baud_rate: u32) -> Result<(), String> {
+    let port = serialport::new(path, baud_rate)
+        .timeout(READ_TIMEOUT)
+        .open()
+        .map_err(|e| e.to_string())?;
+    println!("[INFO] Serial: Reading IMU data from {path}.");
+    // Port names like /dev/ttyUSB0 or COM3 become serial_ttyUSB0 and serial_COM3.
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let mut sensors: HashMap<usize, Sensor> = HashMap::new();
+    let mut reader = BufReader::new(port);
+    let mut line = vec![];
+    let result = loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break Ok(()),
+            Ok(_) => {}
+            Err(e) => break Err(e.to_string()),
+        }
+        // Boot messages, debug prints and garbage from a reset are skipped.
+        let Some((index, sample)) = parse_line(&String::from_utf8_lossy(&line)) else {
+            continue;
+        };
+        let sensor = sensors.entry(index).or_insert_with(|| {
+            let serial_number: Arc<str> = match index {
+                0 => format!("serial_{name}"),
+                index => format!("serial_{name}_{index}"),
+            }
+            .into();
+            tx.send(ChannelData::new(
+                serial_number.clone(),
+                ChannelInfo::Connected(JoyconDesign {
+                    color: "#2e8b57".into(),
+                    design_type: JoyconDesignType::Pro,
+                }),
+            ))
+            .ok();
+            Sensor::new(serial_number)
+        });
+        sensor.push(tx, sample);
+    };
+    for sensor in sensors.values() {
+        tx.send(ChannelData::new(
+            sensor.serial_number.clone(),
+            ChannelInfo::Disconnected,
+        ))
+        .ok();
+    }
+    result
+}
+
+/// Reads raw IMU lines from a serial port, like a dev board without Wi-Fi firmware prints
+/// them, and feeds each IMU as a tracker. The port is opened again when it goes away.
+pub fn spawn_thread(tx: DeviceSender, path: String, baud_rate: u32) {
+    thread::spawn(move || loop {
+        if let Err(e) = read_port(&tx, &path, baud_rate) {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Serial: {path}: {e}");
+        }
+        thread::sleep(RETRY_DELAY);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_separators() {
+        let expected = JoyconAxisData {
+            accel_x: 0.0,
+            accel_y: -0.5,
+            accel_z: 1.0,
+            gyro_x: 90f64.to_radians(),
+            gyro_y: 0.0,
+            gyro_z: -180f64.to_radians(),
+        };
+        assert_eq!(parse_line("0,-0.5,1,90,0,-180"), Some((0, expected)));
+        assert_eq!(parse_line("0, -0.5\t1 90  0,-180\r"), Some((0, expected)));
+    }
+    #[test]
+    fn parse_sensor_index() {
+        let (index, _) = parse_line("2,0,0,1,0,0,0").unwrap();
+        assert_eq!(index, 2);
+    }
+    #[test]
+    fn parse_rejects_other_lines() {
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("IMU ready"), None);
+        assert_eq!(parse_line("0,0,1,0,0"), None);
+        assert_eq!(parse_line("0,0,1,0,0,0,0,0"), None);
+    }
+}
@@ -0,0 +1,47 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::events::Event;
+use crate::settings;
+
+use super::{Remote, ServerStatus};
+
+/// Pauses sending once SlimeVR Server has been gone for as long as set in the settings, so a
+/// Wrangler left running overnight doesn't keep the controllers busy, and asks the frontend to
+/// quit if set to. Resumes when the server comes back, unless paused by hand in between.
+pub fn watch(remote: Remote, settings: settings::Handler) {
+    let mut connected_at = Instant::now();
+    let mut auto_paused = false;
+    loop {
+        thread::sleep(Duration::from_secs(5));
+        if remote.snapshot().server == ServerStatus::Connected {
+            connected_at = Instant::now();
+            if auto_paused {
+                if remote.paused() {
+                    println!("[INFO] SlimeVR Server is back, resuming.");
+                    remote.set_paused(false);
+                }
+                auto_paused = false;
+            }
+            continue;
+        }
+        let (minutes, exit) = {
+            let s = settings.load();
+            (s.server_lost_pause_minutes, s.server_lost_exit)
+        };
+        if minutes == 0 || auto_paused || remote.paused() {
+            continue;
+        }
+        if connected_at.elapsed() < Duration::from_secs(u64::from(minutes) * 60) {
+            continue;
+        }
+        println!("[INFO] No SlimeVR Server for {minutes} minutes, pausing.");
+        remote.set_paused(true);
+        auto_paused = true;
+        if exit {
+            remote.events().publish(Event::Quit);
+        }
+    }
+}
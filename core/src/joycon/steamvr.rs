@@ -0,0 +1,36 @@
+use std::{thread, time::Duration};
+
+use crate::{process, settings};
+
+use super::Remote;
+
+/// Pauses sending while SteamVR isn't running, if enabled in the settings. Only acts when SteamVR
+/// starts or stops, so pausing or resuming by hand in between is left alone.
+pub fn watch(remote: Remote, settings: settings::Handler) {
+    let mut auto_paused = false;
+    let mut was_running = None;
+    loop {
+        if settings.load().steamvr_auto_pause {
+            let running = process::steamvr_running();
+            if was_running != Some(running) {
+                if running && auto_paused {
+                    println!("[INFO] SteamVR started, resuming.");
+                    remote.set_paused(false);
+                    auto_paused = false;
+                } else if !running && !remote.paused() {
+                    println!("[INFO] SteamVR isn't running, pausing.");
+                    remote.set_paused(true);
+                    auto_paused = true;
+                }
+                was_running = Some(running);
+            }
+        } else {
+            if auto_paused {
+                remote.set_paused(false);
+                auto_paused = false;
+            }
+            was_running = None;
+        }
+        thread::sleep(Duration::from_secs(5));
+    }
+}
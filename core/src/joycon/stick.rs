@@ -0,0 +1,178 @@
+use serde::Serialize;
+
+// https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/spi_flash_notes.md
+
+pub const FACTORY_LEFT_ADDRESS: u32 = 0x603D;
+pub const FACTORY_RIGHT_ADDRESS: u32 = 0x6046;
+/// User calibration starts with the 0xB2 0xA1 magic when set.
+pub const USER_LEFT_ADDRESS: u32 = 0x8010;
+pub const USER_RIGHT_ADDRESS: u32 = 0x801B;
+pub const USER_MAGIC: [u8; 2] = [0xB2, 0xA1];
+
+/// Analog stick position, -1.0 to 1.0 with right and up positive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Stick {
+    pub x: f32,
+    pub y: f32,
+}
+impl Stick {
+    /// Whether the stick moved enough to be worth sending on.
+    pub fn moved_from(&self, other: &Stick) -> bool {
+        (self.x - other.x).abs() > 0.02 || (self.y - other.y).abs() > 0.02
+    }
+}
+
+/// Joy-Cons only have the stick on their side, Pro Controllers have both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Sticks {
+    pub left: Option<Stick>,
+    pub right: Option<Stick>,
+}
+impl Sticks {
+    pub fn moved_from(&self, other: &Sticks) -> bool {
+        let moved = |a: Option<Stick>, b: Option<Stick>| match (a, b) {
+            (Some(a), Some(b)) => a.moved_from(&b),
+            (a, b) => a.is_some() != b.is_some(),
+        };
+        moved(self.left, other.left) || moved(self.right, other.right)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AxisCalibration {
+    center: u16,
+    below: u16,
+    above: u16,
+}
+impl AxisCalibration {
+    fn normalize(&self, raw: u16) -> f32 {
+        let (offset, range) = if raw >= self.center {
+            (raw - self.center, self.above)
+        } else {
+            (self.center - raw, self.below)
+        };
+        let value = (offset as f32 / range.max(1) as f32).min(1.0);
+        if raw >= self.center {
+            value
+        } else {
+            -value
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickCalibration {
+    x: AxisCalibration,
+    y: AxisCalibration,
+}
+impl Default for StickCalibration {
+    /// Typical values, for controllers where the calibration couldn't be read.
+    fn default() -> Self {
+        let axis = AxisCalibration {
+            center: 2048,
+            below: 1400,
+            above: 1400,
+        };
+        Self { x: axis, y: axis }
+    }
+}
+
+/// Unpacks the 6 12-bit values of a stick calibration.
+fn unpack(b: &[u8; 9]) -> [u16; 6] {
+    let b = b.map(u16::from);
+    [
+        (b[1] << 8) & 0xF00 | b[0],
+        (b[2] << 4) | (b[1] >> 4),
+        (b[4] << 8) & 0xF00 | b[3],
+        (b[5] << 4) | (b[4] >> 4),
+        (b[7] << 8) & 0xF00 | b[6],
+        (b[8] << 4) | (b[7] >> 4),
+    ]
+}
+
+impl StickCalibration {
+    /// The left and right stick store their values in a different order.
+    pub fn parse(bytes: &[u8; 9], left: bool) -> Option<Self> {
+        let d = unpack(bytes);
+        let [above_x, above_y, center_x, center_y, below_x, below_y] = if left {
+            d
+        } else {
+            [d[4], d[5], d[0], d[1], d[2], d[3]]
+        };
+        // Erased flash reads as all ones.
+        if center_x == 0xFFF || center_x == 0 || above_x == 0 {
+            return None;
+        }
+        Some(Self {
+            x: AxisCalibration {
+                center: center_x,
+                below: below_x,
+                above: above_x,
+            },
+            y: AxisCalibration {
+                center: center_y,
+                below: below_y,
+                above: above_y,
+            },
+        })
+    }
+
+    pub fn normalize(&self, horizontal: u16, vertical: u16) -> Stick {
+        Stick {
+            x: self.x.normalize(horizontal),
+            y: self.y.normalize(vertical),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs 6 12-bit values like they are stored in flash.
+    fn pack(values: [u16; 6]) -> [u8; 9] {
+        let mut bytes = [0; 9];
+        for (i, pair) in values.chunks(2).enumerate() {
+            bytes[i * 3] = pair[0] as u8;
+            bytes[i * 3 + 1] = ((pair[0] >> 8) & 0xF) as u8 | ((pair[1] & 0xF) << 4) as u8;
+            bytes[i * 3 + 2] = (pair[1] >> 4) as u8;
+        }
+        bytes
+    }
+    fn expected() -> StickCalibration {
+        StickCalibration {
+            x: AxisCalibration {
+                center: 2048,
+                below: 1200,
+                above: 1400,
+            },
+            y: AxisCalibration {
+                center: 2000,
+                below: 1100,
+                above: 1300,
+            },
+        }
+    }
+    #[test]
+    fn parse_left() {
+        let bytes = pack([1400, 1300, 2048, 2000, 1200, 1100]);
+        assert_eq!(StickCalibration::parse(&bytes, true), Some(expected()));
+    }
+    #[test]
+    fn parse_right() {
+        let bytes = pack([2048, 2000, 1200, 1100, 1400, 1300]);
+        assert_eq!(StickCalibration::parse(&bytes, false), Some(expected()));
+    }
+    #[test]
+    fn parse_erased() {
+        assert_eq!(StickCalibration::parse(&[0xFF; 9], true), None);
+        assert_eq!(StickCalibration::parse(&[0; 9], false), None);
+    }
+    #[test]
+    fn normalize() {
+        let stick = expected().normalize(2048 + 1400, 2000 - 1100);
+        assert_eq!(stick, Stick { x: 1.0, y: -1.0 });
+        assert_eq!(expected().normalize(2048, 4095).x, 0.0);
+        assert_eq!(expected().normalize(2048, 4095).y, 1.0);
+    }
+}
@@ -0,0 +1,75 @@
+use std::{collections::HashSet, ffi::CString, thread, time::Duration};
+
+use hidapi::{DeviceInfo, HidApi, HidResult};
+
+const NINTENDO: u16 = 0x057e;
+/// Wired Pro Controllers, and wireless USB adapters like 8BitDo's, which show up as one.
+const PRO_CONTROLLER: u16 = 0x2009;
+
+/// Handshake, then turn off the USB timeout, after which the controller speaks the same reports
+/// as over Bluetooth. Until then it doesn't answer sub commands at all.
+/// https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/USB-HID-Notes.md
+const COMMANDS: [[u8; 2]; 2] = [[0x80, 0x02], [0x80, 0x04]];
+
+fn handshake(api: &HidApi, info: &DeviceInfo) -> HidResult<()> {
+    let device = info.open_device(api)?;
+    for command in COMMANDS {
+        device.write(&command)?;
+        // Replies start with 0x81, nothing in them is needed.
+        let mut reply = [0; 64];
+        device.read_timeout(&mut reply, 100)?;
+    }
+    Ok(())
+}
+
+/// Sends the USB handshake to every controller connected over USB, directly or through an
+/// adapter, so they can be used like the Bluetooth ones. Bluetooth devices have no interface
+/// number.
+pub fn spawn_thread() {
+    thread::spawn(|| {
+        let mut api = match HidApi::new() {
+            Ok(api) => api,
+            Err(e) => {
+                println!("\x1b[0;31m[ERROR]\x1b[0m Could not look for USB controllers: {e}");
+                return;
+            }
+        };
+        let mut done: HashSet<CString> = HashSet::new();
+        // Retried, but only reported once.
+        let mut failed: HashSet<CString> = HashSet::new();
+        loop {
+            api.refresh_devices().ok();
+            let usb: Vec<DeviceInfo> = api
+                .device_list()
+                .filter(|d| {
+                    d.vendor_id() == NINTENDO
+                        && d.product_id() == PRO_CONTROLLER
+                        && d.interface_number() >= 0
+                })
+                .cloned()
+                .collect();
+            // Forget unplugged ones, they need the handshake again when plugged back in.
+            done.retain(|path| usb.iter().any(|d| d.path() == path.as_c_str()));
+            failed.retain(|path| usb.iter().any(|d| d.path() == path.as_c_str()));
+            for info in usb {
+                if done.contains(info.path()) {
+                    continue;
+                }
+                match handshake(&api, &info) {
+                    Ok(()) => {
+                        println!("[INFO] Set up a controller connected over USB.");
+                        done.insert(info.path().to_owned());
+                    }
+                    Err(e) => {
+                        if failed.insert(info.path().to_owned()) {
+                            println!(
+                                "\x1b[0;31m[ERROR]\x1b[0m Could not set up USB controller, retrying: {e}"
+                            );
+                        }
+                    }
+                }
+            }
+            thread::sleep(Duration::from_secs(2));
+        }
+    });
+}
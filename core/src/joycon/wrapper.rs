@@ -0,0 +1,249 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    watch,
+};
+
+use crate::events::Bus;
+use crate::settings;
+
+#[cfg(target_os = "windows")]
+use super::ble_integration;
+#[cfg(target_os = "linux")]
+use super::linux_integration;
+use super::{
+    communication::{device_channel, Control, DeviceSender, LearnedDrift, ServerStatus, Snapshot},
+    demo_integration::demo_controllers,
+    mocopi, replay, serial, server_lost, spawn_thread, steamvr, usb, Communication, Playback,
+    Status,
+};
+
+/// Cloneable handle for controlling the communication thread from other threads.
+#[derive(Clone)]
+pub struct Remote {
+    control_tx: UnboundedSender<Control>,
+    paused: Arc<AtomicBool>,
+    waiting_for_server: Arc<AtomicBool>,
+    snapshot: watch::Receiver<Arc<Snapshot>>,
+    events: Bus,
+    replay: Option<Playback>,
+    learned_drift: LearnedDrift,
+}
+impl Remote {
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.borrow().clone()
+    }
+    /// Receiver that is notified whenever the snapshot changes.
+    pub fn updates(&self) -> watch::Receiver<Arc<Snapshot>> {
+        self.snapshot.clone()
+    }
+    /// Device, server and settings changes as they happen.
+    pub fn events(&self) -> &Bus {
+        &self.events
+    }
+    pub fn yaw_reset(&self) {
+        self.control_tx.send(Control::YawReset).ok();
+    }
+    /// Opens the network socket again, after the snapshot showed a socket error.
+    pub fn retry_connection(&self) {
+        self.control_tx.send(Control::Retry).ok();
+    }
+    /// Asks the server to recalculate where every tracker is mounted on the body.
+    pub fn mounting_reset(&self) {
+        self.control_tx.send(Control::MountingReset).ok();
+    }
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+        self.control_tx.send(Control::Pause(paused)).ok();
+    }
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    /// Set to wait for SlimeVR Server, and it hasn't shown up yet. No controllers are read
+    /// until it does.
+    pub fn waiting_for_server(&self) -> bool {
+        self.waiting_for_server.load(Ordering::Relaxed)
+    }
+    /// Controls for the raw IMU log replayed with `--replay`, if any.
+    pub fn replay(&self) -> Option<Playback> {
+        self.replay.clone()
+    }
+    /// Writes the yaw drift learned since the last save to the settings file. It's saved every
+    /// few minutes while running, call this before quitting to keep the rest.
+    pub fn save_learned_drift(&self) {
+        self.learned_drift.save();
+    }
+}
+
+/// Starts reading the controllers, from every source this platform has.
+fn start_controllers(
+    tx: DeviceSender,
+    settings: settings::Handler,
+    snapshot: watch::Receiver<Arc<Snapshot>>,
+) {
+    // Sony mocopi trackers, streamed over the network by the mocopi app
+    if settings.load().mocopi_enabled {
+        let tx = tx.clone();
+        let port = settings.load().mocopi_port;
+        std::thread::spawn(move || mocopi::spawn_thread(tx, port));
+    }
+
+    // IMUs on dev boards, printing their samples to a serial port
+    for path in &settings.load().serial_ports {
+        serial::spawn_thread(tx.clone(), path.clone(), settings.load().serial_baud_rate);
+    }
+
+    // Bluetooth LE devices, read next to the hidapi ones
+    #[cfg(target_os = "windows")]
+    {
+        let tx = tx.clone();
+        std::thread::spawn(move || ble_integration::spawn_thread(tx));
+    }
+
+    // evdev integration
+    #[cfg(target_os = "linux")]
+    if linux_integration::kernel_driver_loaded() {
+        let tx = tx.clone();
+        std::thread::spawn(move || linux_integration::spawn_thread(tx));
+    }
+
+    // On Linux the kernel driver owns the controllers when it's loaded, reading hidraw at
+    // the same time would fight it over the controller's report mode.
+    #[cfg(target_os = "linux")]
+    let use_hidraw = !linux_integration::kernel_driver_loaded();
+    #[cfg(not(target_os = "linux"))]
+    let use_hidraw = true;
+    if use_hidraw {
+        #[cfg(target_os = "linux")]
+        linux_integration::print_hidraw_guidance();
+        usb::spawn_thread();
+        std::thread::spawn(move || spawn_thread(tx, settings, snapshot));
+    }
+}
+
+pub struct Wrapper {
+    status_updates: Mutex<watch::Receiver<Arc<Snapshot>>>,
+    server_updates: Mutex<watch::Receiver<Arc<Snapshot>>>,
+    remote: Remote,
+}
+impl Wrapper {
+    pub fn new(settings: settings::Handler) -> Self {
+        let (control_tx, control_rx) = unbounded_channel();
+        let (tx, rx) = device_channel(settings.load().imu_queue_size.into());
+        let (snapshot_tx, snapshot) = watch::channel(Arc::new(Snapshot::default()));
+        let events = Bus::default();
+        let learned_drift = LearnedDrift::new(settings.clone());
+
+        {
+            let settings = settings.clone();
+            let events = events.clone();
+            let learned_drift = learned_drift.clone();
+            std::thread::spawn(move || {
+                Communication::start(rx, control_rx, snapshot_tx, events, settings, learned_drift);
+            });
+        }
+
+        let demo_trackers = settings.load().demo_trackers;
+        if demo_trackers > 0 {
+            demo_controllers(tx.clone(), demo_trackers);
+        }
+        let replay = settings.load().replay.as_deref().and_then(|path| {
+            match replay::spawn(tx.clone(), path) {
+                Ok(playback) => Some(playback),
+                Err(e) => {
+                    println!(
+                        "\x1b[0;31m[ERROR]\x1b[0m Could not replay {}: {e}",
+                        path.display()
+                    );
+                    None
+                }
+            }
+        });
+
+        let waiting_for_server = Arc::new(AtomicBool::new(settings.load().wait_for_server));
+        if waiting_for_server.load(Ordering::Relaxed) {
+            println!("[INFO] Waiting for SlimeVR Server before connecting controllers.");
+            let settings = settings.clone();
+            let snapshot = snapshot.clone();
+            let waiting = waiting_for_server.clone();
+            std::thread::spawn(move || {
+                while snapshot.borrow().server != ServerStatus::Connected {
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+                println!("[INFO] SlimeVR Server found, connecting controllers.");
+                waiting.store(false, Ordering::Relaxed);
+                start_controllers(tx, settings, snapshot);
+            });
+        } else {
+            start_controllers(tx, settings.clone(), snapshot.clone());
+        }
+
+        let remote = Remote {
+            control_tx,
+            paused: Arc::new(AtomicBool::new(false)),
+            waiting_for_server,
+            snapshot: snapshot.clone(),
+            events,
+            replay,
+            learned_drift,
+        };
+        {
+            let remote = remote.clone();
+            let settings = settings.clone();
+            std::thread::spawn(move || steamvr::watch(remote, settings));
+        }
+        {
+            let remote = remote.clone();
+            std::thread::spawn(move || server_lost::watch(remote, settings));
+        }
+
+        Self {
+            status_updates: Mutex::new(snapshot.clone()),
+            server_updates: Mutex::new(snapshot),
+            remote,
+        }
+    }
+    /// The trackers, if anything changed since the last call.
+    pub fn poll_status(&self) -> Option<Vec<Status>> {
+        let mut updates = self.status_updates.lock().unwrap();
+        updates
+            .has_changed()
+            .unwrap_or(false)
+            .then(|| updates.borrow_and_update().trackers.clone())
+    }
+    pub fn poll_server(&self) -> Option<ServerStatus> {
+        let mut updates = self.server_updates.lock().unwrap();
+        updates
+            .has_changed()
+            .unwrap_or(false)
+            .then(|| updates.borrow_and_update().server)
+    }
+    pub fn remote(&self) -> Remote {
+        self.remote.clone()
+    }
+    /// Device, server and settings changes as they happen, see [`Remote::events`].
+    pub fn events(&self) -> &Bus {
+        self.remote.events()
+    }
+    pub fn yaw_reset(&self) {
+        self.remote.yaw_reset();
+    }
+    pub fn set_paused(&self, paused: bool) {
+        self.remote.set_paused(paused);
+    }
+    pub fn paused(&self) -> bool {
+        self.remote.paused()
+    }
+}
+impl Drop for Wrapper {
+    fn drop(&mut self) {
+        self.remote.save_learned_drift();
+    }
+}
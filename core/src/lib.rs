@@ -0,0 +1,28 @@
+//! The core of SlimeVR Wrangler: finding Joy-Con and Pro controllers, fusing their IMU data into
+//! rotations and sending them to a SlimeVR Server as trackers.
+//!
+//! [`settings::Handler`] loads and saves the settings shared by every part of the app.
+//! [`joycon::Wrapper`] starts the controller and server threads; poll it for changes, or get a
+//! [`joycon::Remote`] to control it and read the latest [`joycon::Snapshot`] from other threads.
+//! To react to changes instead, subscribe to the [`events::Bus`] of the wrapper.
+//!
+//! ```no_run
+//! use slimevr_wrangler_core::{joycon, settings};
+//!
+//! let settings = settings::Handler::new(None, &settings::Overrides::default());
+//! let wrapper = joycon::Wrapper::new(settings);
+//! loop {
+//!     if let Some(statuses) = wrapper.poll_status() {
+//!         for status in statuses {
+//!             println!("{}: {}", status.serial_number, status.status);
+//!         }
+//!     }
+//!     std::thread::sleep(std::time::Duration::from_millis(100));
+//! }
+//! ```
+
+pub mod backup;
+pub mod events;
+pub mod joycon;
+pub mod process;
+pub mod settings;
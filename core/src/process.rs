@@ -0,0 +1,30 @@
+//! Checks for other programs running on this machine.
+
+/// Whether a process with this executable name (without `.exe`) is running.
+#[cfg(target_os = "windows")]
+pub fn running(name: &str) -> bool {
+    let exe = format!("{name}.exe");
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("IMAGENAME eq {exe}"), "/NH"])
+        .output()
+        .map_or(false, |o| {
+            String::from_utf8_lossy(&o.stdout)
+                .to_lowercase()
+                .contains(&exe)
+        })
+}
+/// Whether a process with this executable name (without `.exe`) is running.
+#[cfg(not(target_os = "windows"))]
+pub fn running(name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.filter_map(Result::ok).any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm")).map_or(false, |comm| comm.trim() == name)
+    })
+}
+
+/// SteamVR's server process, running whenever SteamVR is.
+pub fn steamvr_running() -> bool {
+    running("vrserver")
+}
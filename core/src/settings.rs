@@ -0,0 +1,970 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    fs::File,
+    io::BufReader,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use arc_swap::{ArcSwap, Guard};
+use directories::ProjectDirs;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::backup::{self, Backup};
+use crate::joycon::{is_simulated, tuning_serial, Axes, ButtonMapping, JoyconDesignType, Pipeline};
+
+pub(crate) fn file_name(profile: Option<&str>) -> Option<PathBuf> {
+    let name = match profile {
+        Some(profile) => format!("config-{profile}.json"),
+        None => "config.json".into(),
+    };
+    ProjectDirs::from("", "", "SlimeVR Wrangler").map(|pd| pd.config_dir().join(name))
+}
+
+/// Names of all profiles with a settings file, sorted.
+pub fn profiles() -> Vec<String> {
+    let Some(dir) = file_name(None).and_then(|f| f.parent().map(PathBuf::from)) else {
+        return vec![];
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut profiles: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let profile = name.strip_prefix("config-")?.strip_suffix(".json")?;
+            Some(profile.to_owned())
+        })
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+/// The controllers of every profile that lists some, for picking one automatically.
+pub fn profile_devices() -> Vec<(String, Vec<String>)> {
+    profiles()
+        .into_iter()
+        .filter_map(|profile| {
+            let settings = WranglerSettings::load(Some(&profile))?;
+            Some((profile, settings.devices)).filter(|(_, devices)| !devices.is_empty())
+        })
+        .collect()
+}
+
+/// The profile with the most controllers that are all connected, so a rig with an extra
+/// controller wins over the same rig without it. `None` for the default profile.
+pub fn matching_profile(
+    profiles: &[(String, Vec<String>)],
+    connected: &[String],
+) -> Option<String> {
+    profiles
+        .iter()
+        .filter(|(_, devices)| devices.iter().all(|d| connected.contains(d)))
+        .max_by_key(|(_, devices)| devices.len())
+        .map(|(profile, _)| profile.clone())
+}
+
+/// What gets sent to the server for a controller.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TrackerData {
+    Rotation,
+    #[default]
+    RotationAcceleration,
+    /// Raw gyro and accelerometer samples, for servers that do their own fusion.
+    RawImu,
+}
+impl TrackerData {
+    pub const ALL: [TrackerData; 3] = [
+        TrackerData::Rotation,
+        TrackerData::RotationAcceleration,
+        TrackerData::RawImu,
+    ];
+}
+impl Display for TrackerData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TrackerData::Rotation => "Rotation",
+            TrackerData::RotationAcceleration => "Rotation + acceleration",
+            TrackerData::RawImu => "Raw IMU",
+        })
+    }
+}
+
+/// How the tracker boxes show the rotation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AngleDisplay {
+    #[default]
+    Degrees,
+    Radians,
+    /// The rotation sent to the server, as w, x, y, z.
+    Quaternion,
+}
+impl AngleDisplay {
+    pub const ALL: [AngleDisplay; 3] = [
+        AngleDisplay::Degrees,
+        AngleDisplay::Radians,
+        AngleDisplay::Quaternion,
+    ];
+}
+impl Display for AngleDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AngleDisplay::Degrees => "Degrees",
+            AngleDisplay::Radians => "Radians",
+            AngleDisplay::Quaternion => "Quaternion",
+        })
+    }
+}
+
+/// Size of the tracker boxes in the window.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BoxSize {
+    /// Only the rotation, status and battery, to fit many trackers.
+    Small,
+    #[default]
+    Medium,
+    /// With a larger yaw graph.
+    Large,
+}
+impl BoxSize {
+    pub const ALL: [BoxSize; 3] = [BoxSize::Small, BoxSize::Medium, BoxSize::Large];
+}
+impl Display for BoxSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BoxSize::Small => "Small",
+            BoxSize::Medium => "Medium",
+            BoxSize::Large => "Large",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorTheme {
+    #[default]
+    Dark,
+    /// Pure black, for OLED screens that stay on.
+    Amoled,
+}
+impl ColorTheme {
+    pub const ALL: [ColorTheme; 2] = [ColorTheme::Dark, ColorTheme::Amoled];
+}
+impl Display for ColorTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ColorTheme::Dark => "Dark",
+            ColorTheme::Amoled => "AMOLED black",
+        })
+    }
+}
+
+/// Which events play a sound, for hearing about problems with the headset on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Alerts {
+    #[serde(default)]
+    pub tracker_disconnect: bool,
+    #[serde(default)]
+    pub server_disconnect: bool,
+    #[serde(default)]
+    pub battery_critical: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Joycon {
+    #[serde(default)]
+    pub rotation: i32,
+    #[serde(default = "return_f64_one")]
+    pub gyro_scale_factor: f64,
+    #[serde(default)]
+    pub keep_id: u8,
+    #[serde(default)]
+    pub design: Option<JoyconDesignType>,
+    /// Name shown instead of the serial number.
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Tracker id sent to the server, instead of the one derived from the serial number.
+    #[serde(default)]
+    pub tracker_id: Option<u8>,
+    /// Tracker id derived from the serial number when the controller first connected. Kept, so
+    /// which one gets a free id after a collision doesn't depend on the order they connect in.
+    #[serde(default)]
+    pub derived_id: Option<u8>,
+    /// Path to a Rhai script that transforms the IMU samples before fusion.
+    #[serde(default)]
+    pub script: Option<String>,
+    #[serde(default)]
+    pub data: TrackerData,
+    /// Overrides the global axis mapping for this controller.
+    #[serde(default)]
+    pub axes: Option<Axes>,
+    /// Overrides the global handedness flip for this controller.
+    #[serde(default)]
+    pub flip_handedness: Option<bool>,
+    /// Write the raw IMU samples to a CSV file.
+    #[serde(default)]
+    pub log_raw: bool,
+    /// Yaw drift in degrees/s learned while the controller lay still.
+    #[serde(default)]
+    pub yaw_drift: f64,
+    /// Gyro reading in degrees/s at rest, per controller axis, subtracted from every sample.
+    #[serde(default)]
+    pub gyro_bias: [f64; 3],
+    /// Overrides the global pipeline for this controller.
+    #[serde(default)]
+    pub pipeline: Option<Pipeline>,
+    /// Scale of the turn about `scale_axis`, usually yaw, instead of `gyro_scale_factor`.
+    #[serde(default)]
+    pub yaw_scale_factor: Option<f64>,
+    /// Scale of the tilt away from `scale_axis` after fusion. Unscaled if not set, the
+    /// accelerometer already measures it against gravity.
+    #[serde(default)]
+    pub tilt_scale_factor: Option<f64>,
+}
+fn return_f64_one() -> f64 {
+    1.0
+}
+
+impl Default for Joycon {
+    fn default() -> Self {
+        Joycon {
+            rotation: 0,
+            gyro_scale_factor: 1.0,
+            keep_id: 0,
+            design: None,
+            alias: None,
+            tracker_id: None,
+            derived_id: None,
+            script: None,
+            data: TrackerData::default(),
+            axes: None,
+            flip_handedness: None,
+            log_raw: false,
+            yaw_drift: 0.0,
+            gyro_bias: [0.0; 3],
+            pipeline: None,
+            yaw_scale_factor: None,
+            tilt_scale_factor: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WranglerSettings {
+    pub address: String,
+    /// Server addresses used before, most recent first.
+    #[serde(default)]
+    pub address_history: Vec<String>,
+    #[serde(default)]
+    pub joycon: HashMap<String, Joycon>,
+    #[serde(default = "return_true")]
+    pub send_reset: bool,
+    #[serde(default = "return_mac")]
+    pub emulated_mac: [u8; 6],
+    #[serde(default = "return_false")]
+    pub keep_ids: bool,
+    /// Serial numbers more than one connected controller had, like many clones do, with the
+    /// Bluetooth address of the controller that keeps the serial number as it is. The others
+    /// are told apart by the end of their address.
+    #[serde(default)]
+    pub shared_serials: HashMap<String, [u8; 6]>,
+    #[serde(default = "return_false")]
+    pub api_enabled: bool,
+    #[serde(default = "return_api_port")]
+    pub api_port: u16,
+    #[serde(default = "return_false")]
+    pub websocket_enabled: bool,
+    #[serde(default = "return_websocket_port")]
+    pub websocket_port: u16,
+    #[serde(default = "return_false")]
+    pub mqtt_enabled: bool,
+    /// Host and port of the MQTT broker.
+    #[serde(default = "return_mqtt_broker")]
+    pub mqtt_broker: String,
+    /// Topic everything is published under.
+    #[serde(default = "return_mqtt_topic")]
+    pub mqtt_topic: String,
+    #[serde(default)]
+    pub mqtt_username: Option<String>,
+    #[serde(default)]
+    pub mqtt_password: Option<String>,
+    #[serde(default = "return_false")]
+    pub prerelease_updates: bool,
+    #[serde(default)]
+    pub steam_path: Option<String>,
+    #[serde(default = "return_false")]
+    pub blacklist_auto_fix: bool,
+    /// Ids Wrangler added to Steam's controller blacklist, undoing the fix removes only these.
+    #[serde(default)]
+    pub blacklist_added: Vec<String>,
+    #[serde(default = "return_false")]
+    pub steamvr_auto_pause: bool,
+    /// Minutes without SlimeVR Server before pausing, 0 to keep sending.
+    #[serde(default)]
+    pub server_lost_pause_minutes: u32,
+    /// Quit instead of pausing when the server is gone.
+    #[serde(default = "return_false")]
+    pub server_lost_exit: bool,
+    /// Only connect the controllers once SlimeVR Server is found, read at startup.
+    #[serde(default = "return_false")]
+    pub wait_for_server: bool,
+    /// Receive Sony mocopi trackers from the mocopi app, read at startup.
+    #[serde(default = "return_false")]
+    pub mocopi_enabled: bool,
+    #[serde(default = "return_mocopi_port")]
+    pub mocopi_port: u16,
+    /// Serial ports with an IMU board printing raw samples, like `COM3` or `/dev/ttyUSB0`.
+    #[serde(default)]
+    pub serial_ports: Vec<String>,
+    #[serde(default = "return_serial_baud_rate")]
+    pub serial_baud_rate: u32,
+    /// Connect to nothing but SlimeVR Server: no update checks and no MQTT broker.
+    #[serde(default = "return_false")]
+    pub offline: bool,
+    #[serde(default = "return_true")]
+    pub home_light: bool,
+    /// Show tracker status in the headset, in builds with the SteamVR overlay.
+    #[serde(default = "return_false")]
+    pub overlay_enabled: bool,
+    #[serde(default)]
+    pub alerts: Alerts,
+    #[serde(default)]
+    pub angle_display: AngleDisplay,
+    #[serde(default)]
+    pub color_theme: ColorTheme,
+    #[serde(default)]
+    pub box_size: BoxSize,
+    /// Shows the small always on top window instead of the full one.
+    #[serde(default)]
+    pub widget_mode: bool,
+    /// Color of buttons and highlights, as `#rrggbb`.
+    #[serde(default = "return_accent_color")]
+    pub accent_color: String,
+    /// Buttons and chords of buttons that trigger actions on the controller pressing them.
+    #[serde(default = "ButtonMapping::defaults")]
+    pub button_mappings: Vec<ButtonMapping>,
+    /// Which controller axis becomes roll, pitch and yaw.
+    #[serde(default)]
+    pub axes: Axes,
+    /// Mirror the tracker rotation, for the other side of the body.
+    #[serde(default = "return_false")]
+    pub flip_handedness: bool,
+    /// Remove the yaw drift each controller learned from its rotation.
+    #[serde(default = "return_true")]
+    pub yaw_drift_compensation: bool,
+    /// Stages the IMU data of every controller goes through, before and after fusion.
+    #[serde(default)]
+    pub pipeline: Pipeline,
+    /// World axis the rotation scale turns about, z is up.
+    #[serde(default = "return_scale_axis")]
+    pub scale_axis: [f64; 3],
+    /// Rotations per second sent for each controller, 0 to send one for every report.
+    #[serde(default)]
+    pub output_rate: u16,
+    /// Fastest a tracker may turn in degrees/s, 0 for no limit.
+    #[serde(default = "return_max_angular_velocity")]
+    pub max_angular_velocity: f64,
+    #[serde(default)]
+    pub server_path: Option<String>,
+    #[serde(default = "return_local_port")]
+    pub local_port: u16,
+    /// Address of the network interface to send from, for PCs with VPNs or virtual adapters.
+    /// Any interface if not set, picked by the OS for the server's address.
+    #[serde(default)]
+    pub bind_address: Option<IpAddr>,
+    /// Pass on trackers sent to `relay_port` by other machines to the server.
+    #[serde(default = "return_false")]
+    pub relay_enabled: bool,
+    #[serde(default = "return_relay_port")]
+    pub relay_port: u16,
+    /// Machines allowed to send to the relay. Any machine on the local network if empty.
+    #[serde(default)]
+    pub relay_allowed: Vec<IpAddr>,
+    /// IMU reports queued per controller, about 15ms each.
+    #[serde(default = "return_imu_queue_size")]
+    pub imu_queue_size: u8,
+    /// How often to send a heartbeat to the server, or a handshake while not connected.
+    #[serde(default = "return_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u16,
+    /// Time without a ping from the server before the connection counts as timed out.
+    #[serde(default = "return_server_timeout_ms")]
+    pub server_timeout_ms: u16,
+    /// Serial number of the controller whose rotation is sent to opentrack, for head tracking.
+    #[serde(default)]
+    pub opentrack_tracker: Option<String>,
+    #[serde(default = "return_opentrack_address")]
+    pub opentrack_address: String,
+    /// Serial numbers of the controllers this instance uses, all of them if empty.
+    #[serde(default)]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub skipped_update: Option<String>,
+    #[serde(default)]
+    pub update_snoozed_until: u64,
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Number of simulated trackers to add, only ever set from the command line.
+    #[serde(skip)]
+    pub demo_trackers: usize,
+    /// Raw IMU log to replay as a controller, only ever set from the command line.
+    #[serde(skip)]
+    pub replay: Option<PathBuf>,
+}
+
+fn return_accent_color() -> String {
+    "#663499".into()
+}
+fn return_true() -> bool {
+    true
+}
+fn return_false() -> bool {
+    false
+}
+fn return_api_port() -> u16 {
+    6970
+}
+fn return_websocket_port() -> u16 {
+    6971
+}
+fn return_mqtt_broker() -> String {
+    "127.0.0.1:1883".into()
+}
+fn return_mqtt_topic() -> String {
+    "slimevr-wrangler".into()
+}
+fn return_scale_axis() -> [f64; 3] {
+    [0.0, 0.0, 1.0]
+}
+/// The gyro saturates at 2000 degrees/s, anything faster can't be real movement.
+fn return_max_angular_velocity() -> f64 {
+    3000.0
+}
+fn return_imu_queue_size() -> u8 {
+    8
+}
+fn return_heartbeat_interval_ms() -> u16 {
+    3000
+}
+fn return_server_timeout_ms() -> u16 {
+    3000
+}
+/// opentrack's default port for the "UDP over network" input.
+fn return_opentrack_address() -> String {
+    "127.0.0.1:4242".into()
+}
+fn return_mocopi_port() -> u16 {
+    12351
+}
+fn return_serial_baud_rate() -> u32 {
+    115200
+}
+fn return_relay_port() -> u16 {
+    6972
+}
+fn return_local_port() -> u16 {
+    47589
+}
+fn return_mac() -> [u8; 6] {
+    let mut r = rand::thread_rng();
+    [0x00, 0x0F, r.gen(), r.gen(), r.gen(), r.gen()]
+}
+
+const DEFAULT_ADDR: &str = "127.0.0.1:6969";
+const ADDRESS_HISTORY_LEN: usize = 5;
+
+impl WranglerSettings {
+    pub fn save(&self) {
+        let file = file_name(self.profile.as_deref()).unwrap();
+        if !file.exists() {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+        }
+        File::create(file)
+            .ok()
+            .and_then(|file| serde_json::to_writer_pretty(file, self).ok());
+    }
+    fn load(profile: Option<&str>) -> Option<Self> {
+        file_name(profile)
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+    }
+    pub fn load_and_save(profile: Option<&str>) -> Self {
+        let mut settings = Self::load(profile).unwrap_or_else(|| Self {
+            address: DEFAULT_ADDR.into(),
+            address_history: vec![],
+            joycon: HashMap::new(),
+            send_reset: true,
+            emulated_mac: return_mac(),
+            keep_ids: false,
+            shared_serials: HashMap::new(),
+            api_enabled: false,
+            api_port: return_api_port(),
+            websocket_enabled: false,
+            websocket_port: return_websocket_port(),
+            mqtt_enabled: false,
+            mqtt_broker: return_mqtt_broker(),
+            mqtt_topic: return_mqtt_topic(),
+            mqtt_username: None,
+            mqtt_password: None,
+            prerelease_updates: false,
+            steam_path: None,
+            blacklist_auto_fix: false,
+            blacklist_added: vec![],
+            steamvr_auto_pause: false,
+            server_lost_pause_minutes: 0,
+            server_lost_exit: false,
+            wait_for_server: false,
+            mocopi_enabled: false,
+            mocopi_port: return_mocopi_port(),
+            serial_ports: vec![],
+            serial_baud_rate: return_serial_baud_rate(),
+            offline: false,
+            home_light: true,
+            overlay_enabled: false,
+            alerts: Alerts::default(),
+            angle_display: AngleDisplay::default(),
+            color_theme: ColorTheme::default(),
+            box_size: BoxSize::default(),
+            widget_mode: false,
+            accent_color: return_accent_color(),
+            button_mappings: ButtonMapping::defaults(),
+            axes: Axes::default(),
+            flip_handedness: false,
+            yaw_drift_compensation: true,
+            pipeline: Pipeline::default(),
+            scale_axis: return_scale_axis(),
+            output_rate: 0,
+            max_angular_velocity: return_max_angular_velocity(),
+            server_path: None,
+            local_port: return_local_port(),
+            bind_address: None,
+            relay_enabled: false,
+            relay_port: return_relay_port(),
+            relay_allowed: vec![],
+            imu_queue_size: return_imu_queue_size(),
+            heartbeat_interval_ms: return_heartbeat_interval_ms(),
+            server_timeout_ms: return_server_timeout_ms(),
+            opentrack_tracker: None,
+            opentrack_address: return_opentrack_address(),
+            devices: vec![],
+            skipped_update: None,
+            update_snoozed_until: 0,
+            profile: None,
+            demo_trackers: 0,
+            replay: None,
+        });
+        settings.profile = profile.map(str::to_owned);
+        settings.remember_address();
+        settings.upgrade_pipelines();
+        // Saving rewrites a file with added or unreadable settings, keep what was there. Compared
+        // as values, the controllers are in a different order every time they are written.
+        let before = file_name(profile).and_then(|path| fs::read_to_string(path).ok());
+        let before_value = before
+            .as_deref()
+            .and_then(|b| serde_json::from_str::<serde_json::Value>(b).ok());
+        let after = serde_json::to_value(&settings).ok();
+        if before.is_some() && (before_value.is_none() || before_value != after) {
+            if let Err(e) = backup::take(profile, false) {
+                println!("\x1b[0;31m[ERROR]\x1b[0m Could not back up the settings: {e}");
+            }
+        }
+        settings.save();
+        settings
+    }
+    /// These settings with the values in `changes` instead. Settings that aren't saved stay.
+    fn merged(&self, changes: &serde_json::Map<String, serde_json::Value>) -> Result<Self, String> {
+        let mut current = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        if let serde_json::Value::Object(ref mut current) = current {
+            current.extend(changes.clone());
+        }
+        let mut new: Self = serde_json::from_value(current).map_err(|e| e.to_string())?;
+        new.profile = self.profile.clone();
+        new.demo_trackers = self.demo_trackers;
+        new.replay = self.replay.clone();
+        Ok(new)
+    }
+    /// Moves pipelines left at the old default to the rotation scale.
+    fn upgrade_pipelines(&mut self) {
+        self.pipeline = self.pipeline.clone().upgraded();
+        for joycon in self.joycon.values_mut() {
+            joycon.pipeline = joycon.pipeline.take().map(Pipeline::upgraded);
+        }
+    }
+    /// Puts the address Wrangler starts with first in the history, if it's a valid one.
+    fn remember_address(&mut self) {
+        if self.address.parse::<SocketAddr>().is_err() {
+            return;
+        }
+        let address = self.address.clone();
+        self.address_history.retain(|a| a != &address);
+        self.address_history.insert(0, address);
+        self.address_history.truncate(ADDRESS_HISTORY_LEN);
+    }
+    pub fn joycon_rotation_add(&mut self, serial_number: String, degrees: i32) {
+        let entry = self
+            .joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default();
+        entry.rotation = (entry.rotation + degrees).rem_euclid(360);
+    }
+    pub fn joycon_rotation_get(&self, serial_number: &str) -> i32 {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .map_or(0, |j| j.rotation)
+    }
+    pub fn joycon_scale_set(&mut self, serial_number: String, scale: f64) {
+        let entry = self
+            .joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default();
+        entry.gyro_scale_factor = scale;
+    }
+    pub fn joycon_scale_get(&self, serial_number: &str) -> f64 {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .map_or(1.0, |j| j.gyro_scale_factor)
+    }
+    pub fn joycon_yaw_scale_set(&mut self, serial_number: String, scale: f64) {
+        let entry = self
+            .joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default();
+        entry.yaw_scale_factor = Some(scale);
+    }
+    /// The controller's rotation scale, unless it has its own for yaw.
+    pub fn joycon_yaw_scale_get(&self, serial_number: &str) -> f64 {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .and_then(|j| j.yaw_scale_factor)
+            .unwrap_or_else(|| self.joycon_scale_get(serial_number))
+    }
+    pub fn joycon_tilt_scale_get(&self, serial_number: &str) -> f64 {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .and_then(|j| j.tilt_scale_factor)
+            .unwrap_or(1.0)
+    }
+    pub fn joycon_design_set(&mut self, serial_number: String, design: JoyconDesignType) {
+        let entry = self.joycon.entry(serial_number).or_default();
+        entry.design = Some(design);
+    }
+    pub fn joycon_design_get(&self, serial_number: &str) -> Option<JoyconDesignType> {
+        self.joycon.get(serial_number).and_then(|j| j.design)
+    }
+    pub fn joycon_alias_set(&mut self, serial_number: String, alias: String) {
+        let entry = self.joycon.entry(serial_number).or_default();
+        entry.alias = Some(alias).filter(|a| !a.trim().is_empty());
+    }
+    /// The alias if the user set one, otherwise the serial number.
+    pub fn joycon_name(&self, serial_number: &str) -> String {
+        self.joycon
+            .get(serial_number)
+            .and_then(|j| j.alias.clone())
+            .unwrap_or_else(|| serial_number.to_owned())
+    }
+    pub fn joycon_tracker_id_get(&self, serial_number: &str) -> Option<u8> {
+        self.joycon.get(serial_number).and_then(|j| j.tracker_id)
+    }
+    /// The axis mapping and handedness flip of a controller, falling back to the global ones.
+    pub fn joycon_axes_get(&self, serial_number: &str) -> (Axes, bool) {
+        let joycon = self.joycon.get(tuning_serial(serial_number));
+        let axes = joycon.and_then(|j| j.axes).unwrap_or(self.axes);
+        let flip = joycon
+            .and_then(|j| j.flip_handedness)
+            .unwrap_or(self.flip_handedness);
+        (Some(axes).filter(Axes::is_valid).unwrap_or_default(), flip)
+    }
+    pub fn joycon_pipeline_get(&self, serial_number: &str) -> &Pipeline {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .and_then(|j| j.pipeline.as_ref())
+            .unwrap_or(&self.pipeline)
+    }
+    pub fn joycon_data_set(&mut self, serial_number: String, data: TrackerData) {
+        self.joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default()
+            .data = data;
+    }
+    pub fn joycon_data_get(&self, serial_number: &str) -> TrackerData {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .map_or(TrackerData::default(), |j| j.data)
+    }
+    pub fn joycon_gyro_bias_set(&mut self, serial_number: String, axis: usize, bias: f64) {
+        self.joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default()
+            .gyro_bias[axis] = bias;
+    }
+    pub fn joycon_gyro_bias_get(&self, serial_number: &str) -> [f64; 3] {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .map_or([0.0; 3], |j| j.gyro_bias)
+    }
+    pub fn joycon_yaw_drift_set(&mut self, serial_number: String, yaw_drift: f64) {
+        self.joycon
+            .entry(tuning_serial(&serial_number).to_owned())
+            .or_default()
+            .yaw_drift = yaw_drift;
+    }
+    pub fn joycon_yaw_drift_get(&self, serial_number: &str) -> f64 {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .map_or(0.0, |j| j.yaw_drift)
+    }
+    pub fn joycon_log_raw_set(&mut self, serial_number: String, log_raw: bool) {
+        self.joycon.entry(serial_number).or_default().log_raw = log_raw;
+    }
+    pub fn joycon_log_raw_get(&self, serial_number: &str) -> bool {
+        self.joycon.get(serial_number).map_or(false, |j| j.log_raw)
+    }
+    pub fn joycon_script_get(&self, serial_number: &str) -> Option<PathBuf> {
+        self.joycon
+            .get(tuning_serial(serial_number))
+            .and_then(|j| j.script.as_ref())
+            .map(PathBuf::from)
+    }
+    /// Whether this instance uses the controller, another instance may be using it instead.
+    pub fn claims(&self, serial_number: &str) -> bool {
+        self.devices.is_empty() || self.devices.iter().any(|d| d == serial_number)
+    }
+    /// Device types of every controller that has been connected before.
+    pub fn used_designs(&self) -> Vec<JoyconDesignType> {
+        self.joycon
+            .iter()
+            .filter(|(serial_number, _)| !is_simulated(serial_number))
+            .filter_map(|(_, j)| j.design)
+            .collect()
+    }
+    fn joycon_keep_id_set_new(&mut self, serial_number: String) {
+        let max = self.joycon.values().map(|j| j.keep_id).max();
+        let entry = self.joycon.entry(serial_number).or_default();
+        entry.keep_id = max.unwrap_or_default().saturating_add(1);
+        if entry.keep_id == u8::MAX {
+            println!("\x1b[0;31m[ERROR]\x1b[0m TOO MANY JOYCONS SAVED! THIS WILL BREAK THINGS!");
+            println!(" YOU NEED TO DISABLE THE \"Save mounting location on server\" SETTING!!!");
+        }
+    }
+    /// Whether the user chose to skip this version, or to be reminded later.
+    pub fn update_hidden(&self, version: &str) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.skipped_update.as_deref() == Some(version) || self.update_snoozed_until > now
+    }
+    pub fn update_snooze(&mut self, duration: Duration) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.update_snoozed_until = now + duration.as_secs();
+    }
+    pub fn steam_path(&self) -> Option<PathBuf> {
+        self.steam_path.as_ref().map(PathBuf::from)
+    }
+    pub fn server_path(&self) -> Option<PathBuf> {
+        self.server_path.as_ref().map(PathBuf::from)
+    }
+    pub fn get_socket_address(&self) -> SocketAddr {
+        self.address
+            .parse::<SocketAddr>()
+            .unwrap_or_else(|_| DEFAULT_ADDR.parse().unwrap())
+    }
+}
+impl Default for WranglerSettings {
+    fn default() -> Self {
+        WranglerSettings::load_and_save(None)
+    }
+}
+
+/// Settings given on the command line, applied on top of the saved settings but never saved.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    pub address: Option<String>,
+    pub send_reset: Option<bool>,
+    pub keep_ids: Option<bool>,
+    pub demo: Option<usize>,
+    pub local_port: Option<u16>,
+    pub api_port: Option<u16>,
+    pub websocket_port: Option<u16>,
+    pub devices: Option<Vec<String>>,
+    pub replay: Option<PathBuf>,
+    pub wait_for_server: Option<bool>,
+    pub offline: Option<bool>,
+}
+impl Overrides {
+    fn apply(&self, settings: &mut WranglerSettings) {
+        if let Some(address) = &self.address {
+            settings.address = address.clone();
+        }
+        if let Some(send_reset) = self.send_reset {
+            settings.send_reset = send_reset;
+        }
+        if let Some(keep_ids) = self.keep_ids {
+            settings.keep_ids = keep_ids;
+        }
+        if let Some(local_port) = self.local_port {
+            settings.local_port = local_port;
+        }
+        if let Some(api_port) = self.api_port {
+            settings.api_port = api_port;
+        }
+        if let Some(websocket_port) = self.websocket_port {
+            settings.websocket_port = websocket_port;
+        }
+        if let Some(devices) = &self.devices {
+            settings.devices = devices.clone();
+        }
+        if let Some(demo) = self.demo {
+            settings.demo_trackers = demo;
+        }
+        if let Some(wait_for_server) = self.wait_for_server {
+            settings.wait_for_server = wait_for_server;
+        }
+        if let Some(offline) = self.offline {
+            settings.offline = offline;
+        }
+        if let Some(replay) = &self.replay {
+            settings.replay = Some(replay.clone());
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Handler {
+    arc: Arc<ArcSwap<WranglerSettings>>,
+    saved: Arc<ArcSwap<WranglerSettings>>,
+    overrides: Arc<Overrides>,
+    changed: Arc<watch::Sender<()>>,
+}
+impl Handler {
+    pub fn new(profile: Option<&str>, overrides: &Overrides) -> Self {
+        let saved = WranglerSettings::load_and_save(profile);
+        let mut current = saved.clone();
+        overrides.apply(&mut current);
+        Self {
+            arc: Arc::new(ArcSwap::from_pointee(current)),
+            saved: Arc::new(ArcSwap::from_pointee(saved)),
+            overrides: Arc::new(overrides.clone()),
+            changed: Arc::new(watch::channel(()).0),
+        }
+    }
+    /// Swaps in the settings of another profile. Settings that are only read at startup, like
+    /// the ports, keep the values of the first profile.
+    pub fn switch_profile(&self, profile: Option<&str>) {
+        let saved = WranglerSettings::load_and_save(profile);
+        let mut current = saved.clone();
+        self.overrides.apply(&mut current);
+        self.saved.store(Arc::new(saved));
+        self.arc.store(Arc::new(current));
+        self.changed.send_replace(());
+    }
+    pub fn load(&self) -> Guard<Arc<WranglerSettings>> {
+        self.arc.load()
+    }
+    pub fn change<T>(&self, func: T)
+    where
+        T: FnOnce(&mut WranglerSettings) + Clone,
+    {
+        let mut saved = (**self.saved.load()).clone();
+        func.clone()(&mut saved);
+        if let Err(e) = backup::take(saved.profile.as_deref(), true) {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not back up the settings: {e}");
+        }
+        saved.save();
+        self.saved.store(Arc::new(saved));
+
+        let mut current = (**self.arc.load()).clone();
+        func(&mut current);
+        self.arc.store(Arc::new(current));
+        self.changed.send_replace(());
+    }
+    /// Changes the settings named in a JSON object, like the ones in the config file. Nothing
+    /// changes if a value doesn't fit its setting.
+    pub fn change_json(&self, changes: serde_json::Value) -> Result<(), String> {
+        let serde_json::Value::Object(changes) = changes else {
+            return Err("Expected a JSON object".into());
+        };
+        self.load().merged(&changes)?;
+        self.change(|ws| {
+            if let Ok(new) = ws.merged(&changes) {
+                *ws = new;
+            }
+        });
+        Ok(())
+    }
+    /// Copies of the current profile's settings file, newest first.
+    pub fn backups(&self) -> Vec<Backup> {
+        backup::list(self.saved.load().profile.as_deref())
+    }
+    /// Replaces the settings with a copy from [`Handler::backups`], after backing up the
+    /// current ones. Settings only read at startup apply after a restart.
+    pub fn restore(&self, backup: &Backup) -> Result<(), String> {
+        let profile = self.saved.load().profile.clone();
+        let content = fs::read_to_string(&backup.path).map_err(|e| e.to_string())?;
+        let mut saved: WranglerSettings = serde_json::from_str(&content)
+            .map_err(|e| format!("the backup is not readable: {e}"))?;
+        backup::take(profile.as_deref(), false)?;
+        saved.profile = profile;
+        saved.save();
+
+        let mut current = saved.clone();
+        self.overrides.apply(&mut current);
+        self.saved.store(Arc::new(saved));
+        self.arc.store(Arc::new(current));
+        self.changed.send_replace(());
+        Ok(())
+    }
+    /// Receiver that is notified whenever the settings change or another profile is loaded.
+    pub fn changes(&self) -> watch::Receiver<()> {
+        self.changed.subscribe()
+    }
+    pub fn joycon_keep_id(&self, serial_number: String) -> u8 {
+        let keep_id = self
+            .load()
+            .joycon
+            .get(&serial_number)
+            .map_or(0, |j| j.keep_id);
+        if keep_id != 0 {
+            return keep_id;
+        }
+        self.change(|ws| ws.joycon_keep_id_set_new(serial_number.clone()));
+        self.load()
+            .joycon
+            .get(&serial_number)
+            .map_or(0, |j| j.keep_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serials(serials: &[&str]) -> Vec<String> {
+        serials.iter().map(|s| s.to_string()).collect()
+    }
+    fn rigs() -> Vec<(String, Vec<String>)> {
+        vec![
+            ("desk".into(), serials(&["A", "B"])),
+            ("full".into(), serials(&["A", "B", "C"])),
+            ("other".into(), serials(&["D"])),
+        ]
+    }
+    #[test]
+    fn matching_profile_prefers_more_controllers() {
+        let profile = matching_profile(&rigs(), &serials(&["C", "B", "A"]));
+        assert_eq!(profile.as_deref(), Some("full"));
+        let profile = matching_profile(&rigs(), &serials(&["A", "B", "E"]));
+        assert_eq!(profile.as_deref(), Some("desk"));
+    }
+    #[test]
+    fn matching_profile_needs_all_controllers() {
+        assert_eq!(matching_profile(&rigs(), &serials(&["A", "C"])), None);
+        assert_eq!(matching_profile(&rigs(), &[]), None);
+    }
+}
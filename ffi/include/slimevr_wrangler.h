@@ -0,0 +1,64 @@
+/* C interface to the SlimeVR Wrangler core. Build with `cargo build -p slimevr-wrangler-ffi
+ * --release` and link against slimevr_wrangler (.dll, .so, .dylib or the static library).
+ *
+ * None of the functions may run at the same time for one Wrangler. */
+
+#ifndef SLIMEVR_WRANGLER_H
+#define SLIMEVR_WRANGLER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct Wrangler Wrangler;
+
+/* One tracker, as of the last wrangler_poll. */
+typedef struct WranglerTracker {
+    char serial_number[64];
+    char name[64];
+    uint8_t tracker_id;
+    /* 0 empty, 1 critical, 2 low, 3 medium, 4 full. */
+    int battery;
+    /* 0 healthy, 1 laggy IMU, 2 no IMU, 3 setup failed, 4 buttons only, 5 access denied,
+     * 6 disconnected. */
+    int status;
+    /* w, x, y, z. */
+    double quaternion[4];
+    /* Roll, pitch and yaw in degrees. */
+    double rotation[3];
+    /* IMU reports in the last second. */
+    uint32_t report_rate;
+} WranglerTracker;
+
+/* Loads the settings of profile, or the default ones for NULL, and starts looking for
+ * controllers and SlimeVR Server. Works once per process: later calls return NULL, even after
+ * wrangler_free, since the controller threads keep running until the process exits. */
+Wrangler *wrangler_init(const char *profile);
+/* Stops sending. The controller threads end with the process. */
+void wrangler_free(Wrangler *wrangler);
+
+/* Takes the latest trackers. Returns 1 if they changed since the last poll, 0 if not. */
+int wrangler_poll(Wrangler *wrangler);
+/* Number of trackers as of the last poll. */
+size_t wrangler_tracker_count(const Wrangler *wrangler);
+/* Fills out with the tracker at index. Returns 0, or -1 if there is no such tracker. */
+int wrangler_tracker(const Wrangler *wrangler, size_t index, WranglerTracker *out);
+/* 0 disconnected, 1 unknown, 2 connected, 3 timed out. */
+int wrangler_server_status(const Wrangler *wrangler);
+
+/* Changes settings from a JSON object with the names of the config file, like
+ * {"address": "192.168.1.20:6969"}. Returns 0, or -1 if the JSON doesn't fit the settings. */
+int wrangler_configure(const Wrangler *wrangler, const char *json);
+/* Asks SlimeVR Server for a yaw reset. */
+void wrangler_yaw_reset(const Wrangler *wrangler);
+/* Stops (1) or resumes (0) sending to SlimeVR Server. */
+void wrangler_set_paused(const Wrangler *wrangler, int paused);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
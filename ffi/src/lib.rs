@@ -0,0 +1,243 @@
+//! C interface to the SlimeVR Wrangler core, for tools that aren't written in Rust, like Unity
+//! test rigs or Python scripts. See `include/slimevr_wrangler.h` for the C declarations.
+//!
+//! A [`Wrangler`] runs the same controller and server threads as the app. Poll it for changes,
+//! then read the trackers by index. Every function takes the pointer from [`wrangler_init`],
+//! and none of them may run at the same time for one pointer.
+
+use std::{
+    ffi::{c_char, c_int, CStr},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use slimevr_wrangler_core::{
+    joycon::{Battery, DeviceStatus, ServerStatus, Status, Wrapper},
+    settings::{Handler, Overrides},
+};
+
+/// Longest serial number and name copied into a [`WranglerTracker`], including the final NUL.
+const NAME_LEN: usize = 64;
+
+/// Set by the first [`wrangler_init`]. Its threads keep the controllers and ports until the
+/// process exits, so a second set would fight them over both.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub struct Wrangler {
+    settings: Handler,
+    wrapper: Wrapper,
+    trackers: Vec<Status>,
+}
+
+/// One tracker, as of the last [`wrangler_poll`].
+#[repr(C)]
+pub struct WranglerTracker {
+    pub serial_number: [c_char; NAME_LEN],
+    pub name: [c_char; NAME_LEN],
+    pub tracker_id: u8,
+    /// 0 empty, 1 critical, 2 low, 3 medium, 4 full.
+    pub battery: c_int,
+    /// 0 healthy, 1 laggy IMU, 2 no IMU, 3 setup failed, 4 buttons only, 5 access denied,
+    /// 6 disconnected.
+    pub status: c_int,
+    /// w, x, y, z.
+    pub quaternion: [f64; 4],
+    /// Roll, pitch and yaw in degrees.
+    pub rotation: [f64; 3],
+    /// IMU reports in the last second.
+    pub report_rate: u32,
+}
+
+/// Copies as much of `value` as fits, always NUL terminated.
+fn copy_name(value: &str, out: &mut [c_char; NAME_LEN]) {
+    *out = [0; NAME_LEN];
+    for (out, byte) in out.iter_mut().zip(value.bytes().take(NAME_LEN - 1)) {
+        *out = byte as c_char;
+    }
+}
+
+fn battery_number(battery: Battery) -> c_int {
+    match battery {
+        Battery::Empty => 0,
+        Battery::Critical => 1,
+        Battery::Low => 2,
+        Battery::Medium => 3,
+        Battery::Full => 4,
+    }
+}
+
+fn status_number(status: DeviceStatus) -> c_int {
+    match status {
+        DeviceStatus::Healthy => 0,
+        DeviceStatus::LaggyIMU => 1,
+        DeviceStatus::NoIMU => 2,
+        DeviceStatus::SetupFailed => 3,
+        DeviceStatus::ButtonsOnly => 4,
+        DeviceStatus::AccessDenied => 5,
+        DeviceStatus::Disconnected => 6,
+    }
+}
+
+/// Reads a C string argument, `None` for a null pointer or invalid UTF-8.
+///
+/// # Safety
+/// `value` is null or a NUL terminated string.
+unsafe fn string_arg<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    CStr::from_ptr(value).to_str().ok()
+}
+
+/// Loads the settings of `profile`, or the default ones for null, and starts looking for
+/// controllers and SlimeVR Server. Free the result with [`wrangler_free`]. Works once per
+/// process, later calls return null, even after [`wrangler_free`].
+///
+/// # Safety
+/// `profile` is null or a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_init(profile: *const c_char) -> *mut Wrangler {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        println!("\x1b[0;31m[ERROR]\x1b[0m Wrangler is already running in this process");
+        return std::ptr::null_mut();
+    }
+    let settings = Handler::new(string_arg(profile), &Overrides::default());
+    let wrapper = Wrapper::new(settings.clone());
+    Box::into_raw(Box::new(Wrangler {
+        settings,
+        wrapper,
+        trackers: vec![],
+    }))
+}
+
+/// Stops sending. The controller threads end with the process, so [`wrangler_init`] can't be
+/// called again.
+///
+/// # Safety
+/// `wrangler` is null or from [`wrangler_init`], and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_free(wrangler: *mut Wrangler) {
+    if wrangler.is_null() {
+        return;
+    }
+    let wrangler = Box::from_raw(wrangler);
+    wrangler.wrapper.remote().set_paused(true);
+}
+
+/// Takes the latest trackers. Returns 1 if they changed since the last poll, 0 if not.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`].
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_poll(wrangler: *mut Wrangler) -> c_int {
+    let Some(wrangler) = wrangler.as_mut() else {
+        return 0;
+    };
+    match wrangler.wrapper.poll_status() {
+        Some(trackers) => {
+            wrangler.trackers = trackers;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Number of trackers as of the last poll.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`].
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_tracker_count(wrangler: *const Wrangler) -> usize {
+    wrangler.as_ref().map_or(0, |w| w.trackers.len())
+}
+
+/// Fills `out` with the tracker at `index`. Returns 0, or -1 if there is no such tracker.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`], `out` points to a writable `WranglerTracker`.
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_tracker(
+    wrangler: *const Wrangler,
+    index: usize,
+    out: *mut WranglerTracker,
+) -> c_int {
+    let (Some(wrangler), Some(out)) = (wrangler.as_ref(), out.as_mut()) else {
+        return -1;
+    };
+    let Some(status) = wrangler.trackers.get(index) else {
+        return -1;
+    };
+    copy_name(&status.serial_number, &mut out.serial_number);
+    copy_name(&status.name, &mut out.name);
+    out.tracker_id = status.tracker_id;
+    out.battery = battery_number(status.battery);
+    out.status = status_number(status.status);
+    out.quaternion = status.quaternion;
+    out.rotation = [status.rotation.0, status.rotation.1, status.rotation.2];
+    out.report_rate = status.report_rate as u32;
+    0
+}
+
+/// 0 disconnected, 1 unknown, 2 connected, 3 timed out.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`].
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_server_status(wrangler: *const Wrangler) -> c_int {
+    let Some(wrangler) = wrangler.as_ref() else {
+        return 0;
+    };
+    match wrangler.wrapper.remote().snapshot().server {
+        ServerStatus::Disconnected => 0,
+        ServerStatus::Unknown => 1,
+        ServerStatus::Connected => 2,
+        ServerStatus::TimedOut => 3,
+    }
+}
+
+/// Changes settings from a JSON object with the names of the config file, like
+/// `{"address": "192.168.1.20:6969"}`. The settings are saved to the profile. Returns 0, or -1
+/// if the JSON doesn't fit the settings, which are then left alone.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`], `json` is a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_configure(
+    wrangler: *const Wrangler,
+    json: *const c_char,
+) -> c_int {
+    let (Some(wrangler), Some(json)) = (wrangler.as_ref(), string_arg(json)) else {
+        return -1;
+    };
+    let result = serde_json::from_str(json)
+        .map_err(|e| e.to_string())
+        .and_then(|changes| wrangler.settings.change_json(changes));
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not change the settings: {e}");
+            -1
+        }
+    }
+}
+
+/// Asks SlimeVR Server for a yaw reset, like the reset button on a controller.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`].
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_yaw_reset(wrangler: *const Wrangler) {
+    if let Some(wrangler) = wrangler.as_ref() {
+        wrangler.wrapper.remote().yaw_reset();
+    }
+}
+
+/// Stops or resumes sending to SlimeVR Server, with 1 or 0.
+///
+/// # Safety
+/// `wrangler` is from [`wrangler_init`].
+#[no_mangle]
+pub unsafe extern "C" fn wrangler_set_paused(wrangler: *const Wrangler, paused: c_int) {
+    if let Some(wrangler) = wrangler.as_ref() {
+        wrangler.wrapper.remote().set_paused(paused != 0);
+    }
+}
@@ -76,15 +76,36 @@ impl SlimeString {
     }
 }
 
+/// Protocol version that the server appends to its handshake response, like `\x03Hey OVR =D 5`.
+/// Servers from before protocol versions send nothing after the greeting.
+/// The reply is padded with NUL bytes, so those are stripped before parsing.
+pub fn handshake_version(packet: &[u8]) -> Option<u32> {
+    let text = packet.strip_prefix(b"\x03Hey OVR =D")?;
+    std::str::from_utf8(text)
+        .ok()?
+        .trim_end_matches('\0')
+        .trim()
+        .parse()
+        .ok()
+}
+
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
 #[deku(type = "u32")]
 #[deku(endian = "big")]
 pub enum PacketType {
+    #[deku(id = "0")]
+    Heartbeat { packet_id: u64 },
     #[deku(id = "1")]
     Rotation {
         packet_id: u64,
         quat: SlimeQuaternion,
     },
+    #[deku(id = "2")]
+    Gyro {
+        packet_id: u64,
+        vector: (f32, f32, f32),
+        sensor_id: Option<u8>,
+    },
     #[deku(id = "3")]
     Handshake {
         packet_id: u64,
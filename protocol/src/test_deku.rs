@@ -3,7 +3,7 @@ mod tests {
     use deku::{DekuContainerRead, DekuContainerWrite};
     use nalgebra032::{Quaternion, UnitQuaternion};
 
-    use crate::PacketType;
+    use crate::{handshake_version, PacketType};
 
     #[test]
     fn handshake() {
@@ -94,6 +94,29 @@ mod tests {
         assert_eq!(acc.to_bytes().unwrap(), data);
     }
     #[test]
+    fn test_gyro() {
+        let gyro = PacketType::Gyro {
+            packet_id: 16,
+            vector: (0.1, 0.5, 0.9),
+            sensor_id: Some(32),
+        };
+
+        let data: Vec<u8> = vec![
+            0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 16, 61, 204, 204, 205, 63, 0, 0, 0, 63, 102, 102, 102,
+            32,
+        ];
+
+        assert_eq!(gyro.to_bytes().unwrap(), data);
+    }
+    #[test]
+    fn test_heartbeat() {
+        let heartbeat = PacketType::Heartbeat { packet_id: 1 };
+        assert_eq!(
+            heartbeat.to_bytes().unwrap(),
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+    #[test]
     fn test_user_action() {
         let ua = PacketType::UserAction {
             packet_id: 1,
@@ -109,4 +132,22 @@ mod tests {
         let hr = PacketType::HandshakeResponse;
         assert_eq!(hr.to_bytes().unwrap(), "\x03Hey".as_bytes());
     }
+    #[test]
+    fn test_handshake_version() {
+        assert_eq!(handshake_version(b"\x03Hey OVR =D 5"), Some(5));
+        assert_eq!(handshake_version(b"\x03Hey OVR =D"), None);
+        assert_eq!(handshake_version(b"\x03Hey"), None);
+    }
+    #[test]
+    fn test_handshake_version_padded() {
+        let mut packet = [0u8; 64];
+        let reply = b"\x03Hey OVR =D 5";
+        packet[..reply.len()].copy_from_slice(reply);
+        assert_eq!(handshake_version(&packet), Some(5));
+
+        let mut packet = [0u8; 64];
+        let reply = b"\x03Hey OVR =D";
+        packet[..reply.len()].copy_from_slice(reply);
+        assert_eq!(handshake_version(&packet), None);
+    }
 }
@@ -0,0 +1,113 @@
+use std::{collections::HashMap, thread, time::Duration};
+
+use rodio::{
+    source::{SineWave, Zero},
+    OutputStream, Sink, Source,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::joycon::{Battery, DeviceStatus, Remote, ServerStatus, Status};
+use crate::settings::{self, Alerts};
+use slimevr_wrangler_core::events::Event;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alert {
+    TrackerDisconnect,
+    ServerDisconnect,
+    BatteryCritical,
+}
+impl Alert {
+    fn enabled(self, alerts: Alerts) -> bool {
+        match self {
+            Alert::TrackerDisconnect => alerts.tracker_disconnect,
+            Alert::ServerDisconnect => alerts.server_disconnect,
+            Alert::BatteryCritical => alerts.battery_critical,
+        }
+    }
+    /// Tones in Hz, each played for 150 ms. Falling for something lost, a repeated low tone
+    /// for the battery, so they can be told apart without looking.
+    fn tones(self) -> &'static [f32] {
+        match self {
+            Alert::TrackerDisconnect => &[880.0, 660.0],
+            Alert::ServerDisconnect => &[660.0, 440.0, 330.0],
+            Alert::BatteryCritical => &[440.0, 440.0, 440.0],
+        }
+    }
+}
+
+fn play(sink: &Sink, alert: Alert) {
+    for &tone in alert.tones() {
+        sink.append(
+            SineWave::new(tone)
+                .take_duration(Duration::from_millis(150))
+                .amplify(0.2),
+        );
+        // A short gap keeps repeated tones apart.
+        sink.append(Zero::<f32>::new(1, 48000).take_duration(Duration::from_millis(50)));
+    }
+}
+
+/// What changed for the worse in a tracker since it was last seen.
+fn tracker_alert(before: Option<&(DeviceStatus, Battery)>, status: &Status) -> Option<Alert> {
+    let (was, battery) = before?;
+    if status.status == DeviceStatus::Disconnected && *was != DeviceStatus::Disconnected {
+        Some(Alert::TrackerDisconnect)
+    } else if status.battery == Battery::Critical && *battery > Battery::Critical {
+        Some(Alert::BatteryCritical)
+    } else {
+        None
+    }
+}
+
+/// Plays a sound for the events turned on in the settings, listening on the event bus.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    let mut events = remote.events().subscribe();
+    thread::spawn(move || {
+        // Opened on the first alert, no need to hold the audio device otherwise.
+        let mut output: Option<(OutputStream, Sink)> = None;
+        let mut trackers: HashMap<String, (DeviceStatus, Battery)> = HashMap::new();
+        let mut server = ServerStatus::Disconnected;
+        loop {
+            let alert = match events.blocking_recv() {
+                Ok(Event::DeviceAdded(status) | Event::DeviceChanged(status)) => {
+                    let alert = tracker_alert(trackers.get(&status.serial_number), &status);
+                    trackers.insert(status.serial_number, (status.status, status.battery));
+                    alert
+                }
+                Ok(Event::DeviceRemoved { serial_number }) => {
+                    trackers.remove(&serial_number);
+                    None
+                }
+                Ok(Event::ServerChanged(new)) => {
+                    let lost = server == ServerStatus::Connected && new != ServerStatus::Connected;
+                    server = new;
+                    lost.then_some(Alert::ServerDisconnect)
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => return,
+            };
+            let Some(alert) = alert.filter(|a| a.enabled(settings.load().alerts)) else {
+                continue;
+            };
+            if output.is_none() {
+                let opened = OutputStream::try_default()
+                    .map_err(|e| e.to_string())
+                    .and_then(|(stream, handle)| {
+                        Sink::try_new(&handle)
+                            .map(|sink| (stream, sink))
+                            .map_err(|e| e.to_string())
+                    });
+                match opened {
+                    Ok(opened) => output = Some(opened),
+                    Err(e) => {
+                        println!("\x1b[0;31m[ERROR]\x1b[0m Could not play alert sound: {e}");
+                        continue;
+                    }
+                }
+            }
+            if let Some((_, sink)) = &output {
+                play(sink, alert);
+            }
+        }
+    });
+}
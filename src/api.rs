@@ -0,0 +1,188 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use crate::joycon::Remote;
+use crate::settings;
+
+/// A client that doesn't finish its request in this time would hold its thread forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Request {
+    method: String,
+    path: String,
+    origin: Option<String>,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+fn read_request(stream: &mut impl BufRead) -> io::Result<Request> {
+    let mut line = String::new();
+    stream.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut content_length = 0;
+    let mut origin = None;
+    let mut content_type = None;
+    loop {
+        let mut header = String::new();
+        if stream.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_owned());
+            }
+        }
+    }
+    let mut body = vec![0; content_length.min(64 * 1024)];
+    stream.read_exact(&mut body)?;
+    Ok(Request {
+        method,
+        path,
+        origin,
+        content_type,
+        body,
+    })
+}
+
+/// Pages on this computer, like a local dashboard. Browsers send the `Origin` of the page that
+/// made the request, other tools usually don't send one.
+pub(crate) fn is_local_origin(origin: &str) -> bool {
+    let Some(host) = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    let host = match host.strip_prefix("[::1]") {
+        Some(port) => return port.is_empty() || port.starts_with(':'),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    host == "localhost" || host == "127.0.0.1"
+}
+
+/// Any web page could otherwise make the browser change settings or pause tracking. Forms and
+/// simple requests can't send JSON, so requiring it also stops those.
+fn rejected(request: &Request) -> Option<(u16, Value)> {
+    if let Some(origin) = &request.origin {
+        if !is_local_origin(origin) {
+            return Some((
+                403,
+                json!({ "error": "Requests from web pages are not allowed" }),
+            ));
+        }
+    }
+    let json = request.content_type.as_deref().map_or(false, |t| {
+        t.split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .eq_ignore_ascii_case("application/json")
+    });
+    if request.method == "POST" && !json {
+        return Some((
+            415,
+            json!({ "error": "Content-Type must be application/json" }),
+        ));
+    }
+    None
+}
+
+fn respond(stream: &mut TcpStream, code: u16, body: &Value) -> io::Result<()> {
+    let reason = match code {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        415 => "Unsupported Media Type",
+        _ => "Error",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {code} {reason}\r\n\
+        Content-Type: application/json\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn change_settings(settings: &settings::Handler, body: &[u8]) -> Result<(), String> {
+    let changes: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    settings.change_json(changes)
+}
+
+fn route(request: &Request, remote: &Remote, settings: &settings::Handler) -> (u16, Value) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => (200, json!(*remote.snapshot())),
+        ("POST", "/reset") => {
+            remote.yaw_reset();
+            (200, json!({ "ok": true }))
+        }
+        ("POST", "/mounting-reset") => {
+            remote.mounting_reset();
+            (200, json!({ "ok": true }))
+        }
+        ("POST", "/pause") => {
+            remote.set_paused(true);
+            (200, json!({ "ok": true }))
+        }
+        ("POST", "/resume") => {
+            remote.set_paused(false);
+            (200, json!({ "ok": true }))
+        }
+        ("GET", "/settings") => (200, json!(**settings.load())),
+        ("POST", "/settings") => match change_settings(settings, &request.body) {
+            Ok(()) => (200, json!(**settings.load())),
+            Err(e) => (400, json!({ "error": e })),
+        },
+        _ => (404, json!({ "error": "Unknown endpoint" })),
+    }
+}
+
+fn handle(mut stream: TcpStream, remote: &Remote, settings: &settings::Handler) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let request = read_request(&mut BufReader::new(&stream))?;
+    let (code, body) = rejected(&request).unwrap_or_else(|| route(&request, remote, settings));
+    respond(&mut stream, code, &body)
+}
+
+/// Serves a small JSON control API on localhost, if enabled in settings.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    let (enabled, port) = {
+        let s = settings.load();
+        (s.api_enabled, s.api_port)
+    };
+    if !enabled {
+        return;
+    }
+    let listener = match TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port))) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not start control API on port {port}: {e}");
+            return;
+        }
+    };
+    println!("[INFO] Control API listening on http://127.0.0.1:{port}");
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            let remote = remote.clone();
+            let settings = settings.clone();
+            thread::spawn(move || handle(stream, &remote, &settings).ok());
+        }
+    });
+}
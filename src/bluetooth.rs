@@ -0,0 +1,176 @@
+#[derive(Debug, Clone)]
+pub struct Adapter {
+    pub name: String,
+    pub enabled: bool,
+    /// USB vendor and product id, for adapters on USB.
+    pub usb_id: Option<(u16, u16)>,
+}
+impl Adapter {
+    /// Adapters and drivers that often cause laggy trackers, with what to do about it.
+    pub fn known_issue(&self) -> Option<String> {
+        // Genuine CSR8510 chips are rare, most adapters with this id are clones.
+        if self.usb_id == Some((0x0a12, 0x0001)) || self.name.contains("CSR8510") {
+            return Some(format!(
+                "{} is a CSR8510 adapter. Most of these are clones that can't keep up with \
+                several controllers. If trackers show Laggy IMU, try an adapter with an Intel \
+                or Realtek chip.",
+                self.name
+            ));
+        }
+        if self.name == "Generic Bluetooth Radio" {
+            return Some(
+                "Windows is using its generic driver for the Bluetooth adapter. If trackers show \
+                Laggy IMU, install the driver from the adapter's manufacturer."
+                    .into(),
+            );
+        }
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn adapters() -> Result<Vec<Adapter>, String> {
+    let dir = std::fs::read_dir("/sys/class/bluetooth").map_err(|e| e.to_string())?;
+    Ok(dir
+        .filter_map(Result::ok)
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        // Connections show up as hci0:256 next to the adapters.
+        .filter(|name| !name.contains(':'))
+        .map(|name| {
+            let enabled = !rfkill_blocked(&name);
+            let usb_id = usb_id(&name);
+            Adapter {
+                name,
+                enabled,
+                usb_id,
+            }
+        })
+        .collect())
+}
+#[cfg(target_os = "linux")]
+fn usb_id(adapter: &str) -> Option<(u16, u16)> {
+    // The device is the USB interface, the ids are on the USB device above it.
+    let usb_device = std::path::Path::new("/sys/class/bluetooth")
+        .join(adapter)
+        .join("device/..");
+    let id = |file| {
+        let hex = std::fs::read_to_string(usb_device.join(file)).ok()?;
+        u16::from_str_radix(hex.trim(), 16).ok()
+    };
+    Some((id("idVendor")?, id("idProduct")?))
+}
+/// Whether the adapter is turned off, in software (airplane mode, desktop toggle) or by a switch.
+#[cfg(target_os = "linux")]
+fn rfkill_blocked(adapter: &str) -> bool {
+    let Ok(dir) = std::fs::read_dir(format!("/sys/class/bluetooth/{adapter}")) else {
+        return false;
+    };
+    dir.filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with("rfkill"))
+        .any(|e| {
+            ["soft", "hard"].iter().any(|file| {
+                std::fs::read_to_string(e.path().join(file)).map_or(false, |s| s.trim() == "1")
+            })
+        })
+}
+#[cfg(target_os = "windows")]
+pub fn adapters() -> Result<Vec<Adapter>, String> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-PnpDevice -Class Bluetooth -PresentOnly | \
+                Where-Object { $_.FriendlyName -notmatch 'Enumerator|Service|Device Identification' } | \
+                ForEach-Object { \"$($_.Status)|$($_.InstanceId)|$($_.FriendlyName)\" }",
+        ])
+        .output()
+        .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '|');
+            let (status, instance, name) = (parts.next()?, parts.next()?, parts.next()?);
+            Some(Adapter {
+                name: name.to_owned(),
+                // Disabled adapters have the status Error.
+                enabled: status == "OK",
+                usb_id: usb_id(instance),
+            })
+        })
+        .collect())
+}
+/// Reads the ids from an instance id like `USB\VID_0A12&PID_0001\5&2A3B...`.
+#[cfg(target_os = "windows")]
+fn usb_id(instance: &str) -> Option<(u16, u16)> {
+    let id = |prefix: &str| {
+        let start = instance.find(prefix)? + prefix.len();
+        u16::from_str_radix(instance.get(start..start + 4)?, 16).ok()
+    };
+    Some((id("VID_")?, id("PID_")?))
+}
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn adapters() -> Result<Vec<Adapter>, String> {
+    Err("Not supported on this platform".into())
+}
+
+/// Something that keeps controllers from ever showing up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    NoAdapter,
+    Disabled(String),
+    Hid(String),
+}
+impl Problem {
+    pub fn message(&self) -> String {
+        match self {
+            Problem::NoAdapter => {
+                "No Bluetooth adapter found. Plug one in, or check that its driver is installed."
+                    .into()
+            }
+            Problem::Disabled(name) => {
+                format!("Bluetooth is turned off ({name}). Turn it on in your system settings.")
+            }
+            Problem::Hid(e) => format!("Could not look for controllers: {e}"),
+        }
+    }
+}
+
+/// What the adapter check found.
+#[derive(Debug, Clone, Default)]
+pub struct Check {
+    pub problem: Option<Problem>,
+    pub warnings: Vec<String>,
+}
+
+fn check() -> Check {
+    let adapters = adapters();
+    Check {
+        problem: problem(&adapters),
+        warnings: adapters
+            .iter()
+            .flatten()
+            .filter_map(Adapter::known_issue)
+            .collect(),
+    }
+}
+
+fn problem(adapters: &Result<Vec<Adapter>, String>) -> Option<Problem> {
+    if let Err(e) = hidapi::HidApi::new() {
+        return Some(Problem::Hid(e.to_string()));
+    }
+    // Can't tell on this platform, don't warn about it either.
+    let adapters = adapters.as_ref().ok()?;
+    if adapters.is_empty() {
+        return Some(Problem::NoAdapter);
+    }
+    if adapters.iter().any(|a| a.enabled) {
+        return None;
+    }
+    Some(Problem::Disabled(adapters[0].name.clone()))
+}
+
+pub async fn check_adapter() -> Check {
+    tokio::task::spawn_blocking(check).await.unwrap()
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where SlimeVR Server saves its config. Older servers kept it next to the launcher.
+fn config_paths(server_path: Option<&Path>) -> Vec<PathBuf> {
+    directories::BaseDirs::new()
+        .map(|dirs| dirs.config_dir().join("dev.slimevr.SlimeVR"))
+        .into_iter()
+        .chain(
+            crate::server_launch::find_server(server_path)
+                .and_then(|path| path.parent().map(Path::to_path_buf)),
+        )
+        .map(|dir| dir.join("vrconfig.yml"))
+        .collect()
+}
+
+/// "LEFT_UPPER_LEG" to "Left upper leg".
+fn readable(body_part: &str) -> String {
+    let lower = body_part.to_lowercase().replace('_', " ");
+    let mut chars = lower.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Body parts of the trackers sent from `mac`, by tracker id. Only looks at the few lines it
+/// needs from the `trackers` section, where each tracker is keyed like
+/// `"udp://AA:BB:CC:DD:EE:FF/3":` with a `designation: body:LEFT_FOOT` below it.
+fn parse(config: &str, mac: &str) -> HashMap<u8, String> {
+    let mut body_parts = HashMap::new();
+    let mut in_trackers = false;
+    let mut tracker_id = None;
+    for line in config.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if indent == 0 {
+            in_trackers = line == "trackers:";
+            tracker_id = None;
+            continue;
+        }
+        if !in_trackers {
+            continue;
+        }
+        if indent == 2 {
+            let key = line
+                .trim_end_matches(':')
+                .trim_matches(|c| c == '"' || c == '\'');
+            tracker_id = key
+                .to_uppercase()
+                .contains(mac)
+                .then(|| key.rsplit('/').next()?.parse::<u8>().ok())
+                .flatten();
+            continue;
+        }
+        let Some(id) = tracker_id else {
+            continue;
+        };
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if matches!(name.trim(), "designation" | "bodyPosition") {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            let value = value.strip_prefix("body:").unwrap_or(value);
+            if !value.is_empty() && value != "null" && value != "NONE" {
+                body_parts.insert(id, readable(value));
+            }
+        }
+    }
+    body_parts
+}
+
+fn read(server_path: Option<PathBuf>, mac: [u8; 6]) -> Option<HashMap<u8, String>> {
+    let mac = mac
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":");
+    config_paths(server_path.as_deref())
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|config| parse(&config, &mac))
+}
+
+/// Body parts assigned in SlimeVR Server, by tracker id. `None` if the server's config can't be
+/// found, for example with the server on another computer.
+pub async fn body_parts(server_path: Option<PathBuf>, mac: [u8; 6]) -> Option<HashMap<u8, String>> {
+    tokio::task::spawn_blocking(move || read(server_path, mac))
+        .await
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: &str = "AA:BB:CC:DD:EE:FF";
+
+    #[test]
+    fn parse_designations() {
+        let config = "\
+trackers:
+  \"udp://aa:bb:cc:dd:ee:ff/1\":
+    designation: \"body:LEFT_UPPER_LEG\"
+  \"udp://AA:BB:CC:DD:EE:FF/2\":
+    # set in an older server
+    bodyPosition: CHEST
+  \"udp://AA:BB:CC:DD:EE:FF/3\":
+    designation: null
+  \"udp://11:22:33:44:55:66/4\":
+    designation: body:HEAD
+skeleton:
+  designation: body:HIP
+";
+        let body_parts = parse(config, MAC);
+        assert_eq!(body_parts.len(), 2);
+        assert_eq!(body_parts[&1], "Left upper leg");
+        assert_eq!(body_parts[&2], "Chest");
+    }
+    #[test]
+    fn parse_without_trackers() {
+        assert!(parse("server:\n  port: 6969\n", MAC).is_empty());
+    }
+}
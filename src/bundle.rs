@@ -0,0 +1,73 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use slimevr_wrangler_core::{joycon::Snapshot, settings::WranglerSettings};
+use zip::{write::FileOptions, ZipWriter};
+
+/// Where exported files go, the desktop if there is one so they are easy to find.
+pub fn folder() -> PathBuf {
+    directories::UserDirs::new()
+        .and_then(|dirs| dirs.desktop_dir().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn settings_json(settings: &WranglerSettings, redact_address: bool) -> Result<String, String> {
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    if settings.mqtt_password.is_some() {
+        value["mqtt_password"] = "(left out)".into();
+    }
+    if redact_address {
+        value["address"] = "(left out)".into();
+        value["emulated_mac"] = "(left out)".into();
+    }
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+fn write(
+    settings: &WranglerSettings,
+    snapshot: &Snapshot,
+    redact_address: bool,
+) -> Result<PathBuf, String> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = folder().join(format!("slimevr-wrangler-diagnostics-{secs}.zip"));
+    let files = [
+        (
+            "report.txt",
+            crate::diagnose::report(settings, redact_address),
+        ),
+        ("settings.json", settings_json(settings, redact_address)?),
+        (
+            "trackers.json",
+            serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?,
+        ),
+    ];
+
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    for (name, contents) in files {
+        zip.start_file(name, FileOptions::default())
+            .map_err(|e| e.to_string())?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Gathers the diagnose report, settings and tracker stats into one zip for issue reports.
+pub async fn export(
+    settings: WranglerSettings,
+    snapshot: Arc<Snapshot>,
+    redact_address: bool,
+) -> Result<PathBuf, String> {
+    tokio::task::spawn_blocking(move || write(&settings, &snapshot, redact_address))
+        .await
+        .unwrap()
+}
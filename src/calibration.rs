@@ -0,0 +1,230 @@
+//! Guided calibrations, with a countdown to get ready, a progress bar and a warning when the
+//! controller moves, instead of following along in the text of a tracker box.
+
+use std::time::{Duration, Instant};
+
+use crate::joycon::{DeviceStatus, Status};
+
+/// Time to put the controller down or get into pose before anything happens.
+const COUNTDOWN: Duration = Duration::from_secs(3);
+/// Time the gyro is averaged for.
+const GYRO_MEASURE: Duration = Duration::from_secs(5);
+/// Gyro readings this far from the average so far, in degrees/s, are the controller moving.
+const GYRO_MOTION: f64 = 3.0;
+/// Accelerometer totals this far from 1 G are too.
+const ACCEL_MOTION: f64 = 0.1;
+/// How long the motion warning stays up after the controller moved.
+const MOTION_WARNING: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Routine {
+    /// Averages the gyro of a controller lying still into its gyro bias.
+    GyroBias { serial_number: String, name: String },
+    /// Asks SlimeVR Server for a mounting reset once the countdown ends.
+    Mounting,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// In degrees/s, per controller axis.
+    GyroBias {
+        serial_number: String,
+        name: String,
+        bias: [f64; 3],
+    },
+    Mounting,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct Calibration {
+    pub routine: Routine,
+    /// Start of the countdown, or of the measurement after it.
+    since: Instant,
+    measuring: bool,
+    gyro_sum: [f64; 3],
+    samples: u32,
+    /// Last time the controller moved while measuring, which started the measurement over.
+    moved: Option<Instant>,
+    failed: Option<String>,
+}
+
+impl Calibration {
+    pub fn new(routine: Routine) -> Self {
+        Self {
+            routine,
+            since: Instant::now(),
+            measuring: false,
+            gyro_sum: [0.0; 3],
+            samples: 0,
+            moved: None,
+            failed: None,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.routine {
+            Routine::GyroBias { name, .. } => format!("Gyro bias of {name}"),
+            Routine::Mounting => "Mounting reset".into(),
+        }
+    }
+
+    pub fn instructions(&self) -> &'static str {
+        match (&self.routine, self.measuring) {
+            (Routine::GyroBias { .. }, false) => {
+                "Lay the controller on a flat surface and let go of it."
+            }
+            (Routine::GyroBias { .. }, true) => "Measuring, don't touch the controller.",
+            (Routine::Mounting, _) => {
+                "Get into the mounting reset pose: feet together, knees bent, \
+                leaning forward like skiing, arms down."
+            }
+        }
+    }
+
+    /// Whole seconds left of the countdown, `None` once measuring.
+    pub fn countdown(&self, now: Instant) -> Option<u64> {
+        if self.measuring {
+            return None;
+        }
+        let left = COUNTDOWN.saturating_sub(now.duration_since(self.since));
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    /// How far the countdown or measurement is, from 0 to 1.
+    pub fn progress(&self, now: Instant) -> f32 {
+        let length = if self.measuring {
+            GYRO_MEASURE
+        } else {
+            COUNTDOWN
+        };
+        (now.duration_since(self.since).as_secs_f32() / length.as_secs_f32()).min(1.0)
+    }
+
+    pub fn motion_detected(&self, now: Instant) -> bool {
+        self.moved
+            .map_or(false, |moved| now.duration_since(moved) < MOTION_WARNING)
+    }
+
+    /// Adds the latest readings of the controller being calibrated.
+    pub fn sample(&mut self, now: Instant, statuses: &[Status]) {
+        let Routine::GyroBias { serial_number, .. } = &self.routine else {
+            return;
+        };
+        if !self.measuring {
+            return;
+        }
+        let status = statuses.iter().find(|s| &s.serial_number == serial_number);
+        let Some(status) = status.filter(|s| s.status != DeviceStatus::Disconnected) else {
+            self.failed = Some("The controller disconnected during the calibration.".into());
+            return;
+        };
+        let gyro = [status.raw_gyro.0, status.raw_gyro.1, status.raw_gyro.2];
+        let (x, y, z) = status.raw_accel;
+        let accel = (x * x + y * y + z * z).sqrt();
+        let off_average = self.samples > 0
+            && gyro
+                .iter()
+                .zip(self.gyro_sum)
+                .any(|(g, sum)| (g - sum / f64::from(self.samples)).abs() > GYRO_MOTION);
+        if (accel - 1.0).abs() > ACCEL_MOTION || off_average {
+            self.moved = Some(now);
+            self.since = now;
+            self.gyro_sum = [0.0; 3];
+            self.samples = 0;
+            return;
+        }
+        for (sum, g) in self.gyro_sum.iter_mut().zip(gyro) {
+            *sum += g;
+        }
+        self.samples += 1;
+    }
+
+    /// Moves on from the countdown, and returns the outcome once done.
+    pub fn tick(&mut self, now: Instant) -> Option<Outcome> {
+        if let Some(e) = self.failed.take() {
+            return Some(Outcome::Failed(e));
+        }
+        let elapsed = now.duration_since(self.since);
+        match (&self.routine, self.measuring) {
+            (_, false) if elapsed < COUNTDOWN => None,
+            (Routine::Mounting, _) => Some(Outcome::Mounting),
+            (Routine::GyroBias { .. }, false) => {
+                self.measuring = true;
+                self.since = now;
+                None
+            }
+            (Routine::GyroBias { .. }, true) if elapsed < GYRO_MEASURE => None,
+            (Routine::GyroBias { .. }, true) if self.samples == 0 => Some(Outcome::Failed(
+                "No IMU data from the controller, check that it is connected.".into(),
+            )),
+            (
+                Routine::GyroBias {
+                    serial_number,
+                    name,
+                },
+                true,
+            ) => Some(Outcome::GyroBias {
+                serial_number: serial_number.clone(),
+                name: name.clone(),
+                bias: self.gyro_sum.map(|sum| sum / f64::from(self.samples)),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gyro_bias() -> Calibration {
+        Calibration::new(Routine::GyroBias {
+            serial_number: "98:B6:E9:12:34:56".into(),
+            name: "Left foot".into(),
+        })
+    }
+    #[test]
+    fn mounting_after_countdown() {
+        let mut calibration = Calibration::new(Routine::Mounting);
+        let start = calibration.since;
+        assert_eq!(calibration.tick(start + Duration::from_secs(1)), None);
+        assert_eq!(
+            calibration.countdown(start + Duration::from_secs(1)),
+            Some(2)
+        );
+        assert_eq!(calibration.tick(start + COUNTDOWN), Some(Outcome::Mounting));
+    }
+    #[test]
+    fn gyro_bias_averages() {
+        let mut calibration = gyro_bias();
+        let start = calibration.since + COUNTDOWN;
+        assert_eq!(calibration.tick(start), None);
+        assert_eq!(calibration.countdown(start), None);
+        calibration.gyro_sum = [2.0, 4.0, -6.0];
+        calibration.samples = 2;
+        assert_eq!(calibration.tick(start + Duration::from_secs(1)), None);
+        assert_eq!(
+            calibration.tick(start + GYRO_MEASURE),
+            Some(Outcome::GyroBias {
+                serial_number: "98:B6:E9:12:34:56".into(),
+                name: "Left foot".into(),
+                bias: [1.0, 2.0, -3.0],
+            })
+        );
+    }
+    #[test]
+    fn gyro_bias_fails_without_samples() {
+        let mut calibration = gyro_bias();
+        let start = calibration.since + COUNTDOWN;
+        calibration.tick(start);
+        let outcome = calibration.tick(start + GYRO_MEASURE);
+        assert!(matches!(outcome, Some(Outcome::Failed(_))));
+    }
+    #[test]
+    fn failure_ends_at_once() {
+        let mut calibration = gyro_bias();
+        calibration.failed = Some("gone".into());
+        let outcome = calibration.tick(calibration.since);
+        assert_eq!(outcome, Some(Outcome::Failed("gone".into())));
+    }
+}
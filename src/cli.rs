@@ -0,0 +1,201 @@
+use std::env;
+
+use crate::{ipc::Command, settings::Overrides};
+
+#[derive(Debug, Default, Clone)]
+pub struct Args {
+    pub help: bool,
+    pub diagnose: bool,
+    pub headless: bool,
+    pub tui: bool,
+    pub service: bool,
+    pub install_service: bool,
+    pub uninstall_service: bool,
+    pub profile: Option<String>,
+    /// Command for the instance that is already running.
+    pub command: Option<Command>,
+    pub overrides: Overrides,
+}
+
+const DEFAULT_DEMO_TRACKERS: usize = 6;
+
+fn parse_port(name: &str, value: String) -> Result<u16, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid port \"{value}\" for --{name}"))
+}
+
+fn parse_bool(name: &str, value: Option<String>) -> Result<bool, String> {
+    match value.as_deref() {
+        None | Some("true") | Some("1") | Some("yes") => Ok(true),
+        Some("false") | Some("0") | Some("no") => Ok(false),
+        Some(other) => Err(format!("Invalid value \"{other}\" for --{name}")),
+    }
+}
+
+impl Args {
+    /// Parses `--flag`, `--flag value` and `--flag=value` style arguments.
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    fn parse_from(arguments: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = Self::default();
+        let mut iter = arguments.into_iter();
+        while let Some(arg) = iter.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                // "test" is the old name of --demo.
+                if arg == "test" {
+                    args.overrides.demo = Some(DEFAULT_DEMO_TRACKERS);
+                } else if let Some(command) = Command::from_name(&arg) {
+                    args.command = Some(command);
+                }
+                continue;
+            };
+            let (name, inline_value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (flag, None),
+            };
+            let value = |iter: &mut dyn Iterator<Item = String>| {
+                inline_value
+                    .clone()
+                    .or_else(|| iter.next())
+                    .ok_or(format!("Missing value for --{name}"))
+            };
+            match name {
+                "help" => args.help = true,
+                "diagnose" => args.diagnose = true,
+                "headless" => args.headless = true,
+                "tui" => args.tui = true,
+                "service" => args.service = true,
+                "install-service" => args.install_service = true,
+                "uninstall-service" => args.uninstall_service = true,
+                "profile" => args.profile = Some(value(&mut iter)?),
+                "address" => args.overrides.address = Some(value(&mut iter)?),
+                "send-reset" => {
+                    args.overrides.send_reset = Some(parse_bool(name, inline_value.clone())?);
+                }
+                "keep-ids" => {
+                    args.overrides.keep_ids = Some(parse_bool(name, inline_value.clone())?);
+                }
+                "wait-for-server" => {
+                    args.overrides.wait_for_server = Some(parse_bool(name, inline_value.clone())?);
+                }
+                "offline" => {
+                    args.overrides.offline = Some(parse_bool(name, inline_value.clone())?);
+                }
+                "local-port" => {
+                    args.overrides.local_port = Some(parse_port(name, value(&mut iter)?)?)
+                }
+                "api-port" => args.overrides.api_port = Some(parse_port(name, value(&mut iter)?)?),
+                "websocket-port" => {
+                    args.overrides.websocket_port = Some(parse_port(name, value(&mut iter)?)?);
+                }
+                "devices" => {
+                    args.overrides.devices = Some(
+                        value(&mut iter)?
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .map(str::to_owned)
+                            .collect(),
+                    );
+                }
+                "replay" => args.overrides.replay = Some(value(&mut iter)?.into()),
+                "demo" => {
+                    args.overrides.demo = Some(match &inline_value {
+                        Some(count) => count
+                            .parse()
+                            .map_err(|_| format!("Invalid value \"{count}\" for --demo"))?,
+                        None => DEFAULT_DEMO_TRACKERS,
+                    });
+                }
+                _ => return Err(format!("Unknown argument --{name}")),
+            }
+        }
+        Ok(args)
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: slimevr-wrangler [OPTIONS] [COMMAND]
+
+Commands for the instance that is already running:
+  reset-yaw               Send a yaw reset to the SlimeVR server
+  reset-mounting          Send a mounting reset to the SlimeVR server
+  pause, resume           Stop/start sending tracker data
+  status                  Print the server connection and all trackers as JSON
+
+Modes:
+  --help                  Show this help
+  --diagnose              Check bluetooth, controllers, server and Steam, then exit
+  --headless              Run without a window, logging status to the console
+  --tui                   Run with an interactive terminal interface
+  --install-service       Run the headless mode as a service that starts at boot
+  --uninstall-service     Stop and remove the service
+
+Settings for this run only (not saved):
+  --profile <name>        Use a separate settings file for this profile
+  --address <ip:port>     SlimeVR Server address
+  --send-reset[=bool]     Send yaw reset to the server on B/UP button press
+  --keep-ids[=bool]       Save mounting location on server
+  --wait-for-server[=bool] Only connect controllers once SlimeVR Server is found
+  --offline[=bool]        No update checks or MQTT, only send to SlimeVR Server
+  --local-port <port>     UDP port to send from (default 47589)
+  --api-port <port>       Port of the local control API
+  --websocket-port <port> Port of the live status WebSocket
+  --devices <serials>     Only use these controllers, comma separated serial numbers
+  --demo[=count]          Add simulated trackers with scripted motion (default 6)
+  --replay <file>         Replay a raw IMU log as a controller";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse_from(args.iter().map(|a| a.to_string()))
+    }
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            parse(&["reset-yaw"]).unwrap().command,
+            Some(Command::ResetYaw)
+        );
+        assert_eq!(parse(&["resume"]).unwrap().command, Some(Command::Resume));
+        assert_eq!(parse(&[]).unwrap().command, None);
+        assert_eq!(
+            parse(&["test"]).unwrap().overrides.demo,
+            Some(DEFAULT_DEMO_TRACKERS)
+        );
+    }
+    #[test]
+    fn parse_values() {
+        let args = parse(&["--profile", "player2", "--api-port=7000", "--keep-ids=no"]).unwrap();
+        assert_eq!(args.profile.as_deref(), Some("player2"));
+        assert_eq!(args.overrides.api_port, Some(7000));
+        assert_eq!(args.overrides.keep_ids, Some(false));
+        let args = parse(&["--offline", "--devices", "A, B,,C", "--demo=2"]).unwrap();
+        assert_eq!(args.overrides.offline, Some(true));
+        assert_eq!(
+            args.overrides.devices,
+            Some(vec!["A".into(), "B".into(), "C".into()])
+        );
+        assert_eq!(args.overrides.demo, Some(2));
+    }
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse(&["--profile"]).unwrap_err(),
+            "Missing value for --profile"
+        );
+        assert_eq!(
+            parse(&["--local-port", "x"]).unwrap_err(),
+            "Invalid port \"x\" for --local-port"
+        );
+        assert_eq!(
+            parse(&["--keep-ids=maybe"]).unwrap_err(),
+            "Invalid value \"maybe\" for --keep-ids"
+        );
+        assert_eq!(parse(&["--bogus"]).unwrap_err(), "Unknown argument --bogus");
+    }
+}
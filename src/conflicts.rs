@@ -0,0 +1,138 @@
+use slimevr_wrangler_core::process;
+
+const NINTENDO: u16 = 0x057e;
+/// Left, right, both in a grip, Pro Controller, charging grip.
+const PRODUCTS: [u16; 5] = [0x2006, 0x2007, 0x2008, 0x2009, 0x200e];
+
+/// Programs that open Joy-Cons exclusively, by executable name (without `.exe`).
+const PROGRAMS: [(&str, &str); 3] = [
+    ("BetterJoyForCemu", "BetterJoy"),
+    ("BetterJoy", "BetterJoy"),
+    ("DS4Windows", "DS4Windows"),
+];
+
+/// Another program holding controllers, so they never show up here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Names of the running programs that are likely to blame.
+    pub programs: Vec<&'static str>,
+    /// Controllers that are paired but can't be opened.
+    pub blocked: usize,
+    /// Controllers that can't be opened for lack of permission, on Linux.
+    pub denied: usize,
+}
+impl Conflict {
+    pub fn message(&self) -> String {
+        if self.denied > 0 && self.blocked == 0 {
+            return "No permission to open the controllers. Add the udev rule from the README, \
+                then reconnect them."
+                .into();
+        }
+        let who = if self.programs.is_empty() {
+            "Another program".to_owned()
+        } else {
+            self.programs.join(" and ")
+        };
+        match self.blocked {
+            0 => format!(
+                "{who} is running and hides controllers from other programs. \
+                Close it to use your controllers here."
+            ),
+            1 => format!(
+                "A controller can't be opened, {who} has it. Close it, or remove the controller from it."
+            ),
+            n => format!(
+                "{n} controllers can't be opened, {who} has them. Close it, or remove the controllers from it."
+            ),
+        }
+    }
+}
+
+enum OpenError {
+    Busy,
+    Denied,
+}
+
+/// Why a controller can't be opened. On Linux hidraw tells another program holding it apart
+/// from missing permissions, hidapi doesn't.
+fn probe(api: &hidapi::HidApi, device: &hidapi::DeviceInfo) -> Option<OpenError> {
+    #[cfg(target_os = "linux")]
+    if let Some(path) = device
+        .path()
+        .to_str()
+        .ok()
+        .filter(|p| p.starts_with("/dev/"))
+    {
+        return match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+        {
+            Ok(_) => None,
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some(OpenError::Denied),
+            Err(_) => Some(OpenError::Busy),
+        };
+    }
+    api.open_path(device.path()).err().map(|_| OpenError::Busy)
+}
+
+/// Probes the controllers this app failed to open, returning how many another program has and
+/// how many lack permissions. The others aren't touched, opening them would disturb whoever
+/// uses them.
+fn blocked(failed: &[String]) -> (usize, usize) {
+    if failed.is_empty() {
+        return (0, 0);
+    }
+    let Ok(api) = hidapi::HidApi::new() else {
+        return (0, 0);
+    };
+    let (mut busy, mut denied) = (0, 0);
+    for device in api
+        .device_list()
+        .filter(|d| d.vendor_id() == NINTENDO && PRODUCTS.contains(&d.product_id()))
+        .filter(|d| {
+            d.serial_number().map_or(false, |serial| {
+                failed.iter().any(|f| f.split('#').next() == Some(serial))
+            })
+        })
+    {
+        match probe(&api, device) {
+            Some(OpenError::Busy) => busy += 1,
+            Some(OpenError::Denied) => denied += 1,
+            None => {}
+        }
+    }
+    (busy, denied)
+}
+
+fn check(connected: &[String], failed: &[String]) -> Option<Conflict> {
+    let mut programs: Vec<_> = PROGRAMS
+        .iter()
+        .filter(|(exe, _)| process::running(exe))
+        .map(|(_, name)| *name)
+        .collect();
+    programs.dedup();
+    let (blocked, denied) = blocked(failed);
+    // Steam Input only gets in the way with the blacklist missing, so it's only suspected when a
+    // controller is actually blocked.
+    if blocked > 0 && process::running("steam") {
+        programs.push("Steam Input");
+    }
+    // Hidden controllers don't show up at all, only a lack of controllers tells.
+    if blocked > 0 || denied > 0 || (!programs.is_empty() && connected.is_empty()) {
+        Some(Conflict {
+            programs,
+            blocked,
+            denied,
+        })
+    } else {
+        None
+    }
+}
+
+/// `failed` are the controllers that couldn't be opened, only those are probed.
+pub async fn check_conflicts(connected: Vec<String>, failed: Vec<String>) -> Option<Conflict> {
+    tokio::task::spawn_blocking(move || check(&connected, &failed))
+        .await
+        .unwrap()
+}
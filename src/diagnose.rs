@@ -0,0 +1,253 @@
+use std::{
+    fmt::Write,
+    net::{SocketAddr, UdpSocket},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use protocol::deku::{DekuContainerRead, DekuContainerWrite};
+use protocol::PacketType;
+
+use crate::bluetooth;
+use crate::settings::WranglerSettings;
+use crate::steam_blacklist::{
+    get_steam_config_paths, switch_input_enabled, Blacklist, BlacklistError, Device,
+};
+
+const USB_VENDOR_ID_NINTENDO: u16 = 0x057e;
+
+struct Report {
+    text: String,
+}
+impl Report {
+    fn section(&mut self, name: &str) {
+        writeln!(self.text, "\n## {name}").ok();
+    }
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        writeln!(self.text, "{}", line.as_ref()).ok();
+    }
+}
+
+fn check_bluetooth(report: &mut Report) {
+    report.section("Bluetooth adapter");
+    match bluetooth::adapters() {
+        Ok(adapters) if adapters.is_empty() => {
+            report.line("[WARNING] No bluetooth adapter found.");
+        }
+        Ok(adapters) => {
+            for adapter in adapters {
+                if adapter.enabled {
+                    report.line(format!("Found: {}", adapter.name));
+                } else {
+                    report.line(format!("[WARNING] Turned off: {}", adapter.name));
+                }
+                if let Some(issue) = adapter.known_issue() {
+                    report.line(format!("[WARNING] {issue}"));
+                }
+            }
+        }
+        Err(e) => report.line(format!("[WARNING] Could not check for adapters: {e}")),
+    }
+}
+
+fn check_hid(report: &mut Report) {
+    report.section("HID devices");
+    #[cfg(target_os = "linux")]
+    if crate::joycon::kernel_driver_loaded() {
+        report.line("hid-nintendo kernel module: loaded");
+    } else {
+        report.line("[WARNING] hid-nintendo kernel module not loaded, falling back to hidraw.");
+    }
+    let api = match hidapi::HidApi::new() {
+        Ok(api) => api,
+        Err(e) => {
+            report.line(format!("[ERROR] Could not initialize hidapi: {e}"));
+            return;
+        }
+    };
+    let mut found = 0;
+    for device in api
+        .device_list()
+        .filter(|d| d.vendor_id() == USB_VENDOR_ID_NINTENDO)
+    {
+        found += 1;
+        report.line(format!(
+            "{:04x}:{:04x} {} (serial: {})",
+            device.vendor_id(),
+            device.product_id(),
+            device.product_string().unwrap_or("Unknown"),
+            device.serial_number().unwrap_or("none"),
+        ));
+    }
+    if found == 0 {
+        report.line("[WARNING] No Nintendo HID devices found. Are your controllers paired?");
+    }
+}
+
+fn server_handshake(settings: &WranglerSettings, address: SocketAddr) -> Result<Duration, String> {
+    let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0))).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .map_err(|e| e.to_string())?;
+    let handshake = PacketType::Handshake {
+        packet_id: 0,
+        board: 0,
+        imu: 0,
+        mcu_type: 0,
+        imu_info: (0, 0, 0),
+        build: 9,
+        firmware: "slimevr-wrangler".to_string().into(),
+        mac_address: settings.emulated_mac,
+    };
+    let bytes = handshake.to_bytes().map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    let mut buf = [0; 512];
+    while start.elapsed() < Duration::from_secs(3) {
+        socket.send_to(&bytes, address).map_err(|e| e.to_string())?;
+        if let Ok(len) = socket.recv(&mut buf) {
+            if let Ok((_, PacketType::HandshakeResponse)) = PacketType::from_bytes((&buf[..len], 0))
+            {
+                return Ok(start.elapsed());
+            }
+        }
+    }
+    Err("No handshake response within 3 seconds".into())
+}
+
+fn check_server(report: &mut Report, settings: &WranglerSettings, redact_address: bool) {
+    report.section("SlimeVR Server");
+    let address = settings.get_socket_address();
+    if redact_address {
+        report.line("Address: (left out)");
+    } else if settings.address.parse::<SocketAddr>().is_err() {
+        report.line(format!(
+            "[WARNING] Configured address \"{}\" is invalid, using {address}.",
+            settings.address
+        ));
+    } else {
+        report.line(format!("Address: {address}"));
+    }
+    match server_handshake(settings, address) {
+        Ok(time) => report.line(format!("Handshake OK ({} ms)", time.as_millis())),
+        Err(e) => report.line(format!("[ERROR] Server unreachable: {e}")),
+    }
+}
+
+fn check_blacklist(report: &mut Report, settings: &WranglerSettings) {
+    report.section("Steam controller blacklist");
+    let paths = get_steam_config_paths(settings.steam_path.as_deref().map(Path::new));
+    if paths.is_empty() {
+        report.line("Could not find a steam config file.");
+    }
+    for path in paths {
+        report.line(format!("Config: {}", path.display()));
+        match Blacklist::read(&path) {
+            Ok(list) => {
+                for device in Device::ALL {
+                    report.line(format!(
+                        "{device:?}: {}",
+                        if list.has(device) {
+                            "blacklisted"
+                        } else {
+                            "NOT blacklisted"
+                        }
+                    ));
+                }
+            }
+            Err(BlacklistError::IO(e)) => {
+                report.line(format!("Could not read steam config: {e}"));
+            }
+            Err(e) => report.line(format!("[WARNING] Steam config error: {e}")),
+        }
+        if let Ok(true) = switch_input_enabled(&path) {
+            report.line("[WARNING] Steam Input Switch controller support is on");
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn check_firewall(report: &mut Report, settings: &WranglerSettings) {
+    use itertools::Itertools;
+
+    report.section("Firewall");
+    let output = std::process::Command::new("netsh")
+        .args([
+            "advfirewall",
+            "firewall",
+            "show",
+            "rule",
+            "name=all",
+            "verbose",
+        ])
+        .output();
+    let output = match output {
+        Ok(o) => String::from_utf8_lossy(&o.stdout).into_owned(),
+        Err(e) => {
+            report.line(format!("[WARNING] Could not run netsh: {e}"));
+            return;
+        }
+    };
+    let mut found = 0;
+    for rule in output.split("\r\n\r\n").filter(|r| {
+        let lower = r.to_lowercase();
+        lower.contains("slimevr") || lower.contains("wrangler")
+    }) {
+        found += 1;
+        let summary = rule
+            .lines()
+            .filter(|l| {
+                [
+                    "Rule Name",
+                    "Enabled",
+                    "Direction",
+                    "Protocol",
+                    "LocalPort",
+                    "Action",
+                ]
+                .iter()
+                .any(|k| l.starts_with(k))
+            })
+            .map(|l| l.split_whitespace().join(" "))
+            .join(", ");
+        report.line(summary);
+    }
+    if found == 0 {
+        report.line("[WARNING] No firewall rules mentioning SlimeVR or Wrangler found.");
+    }
+    match crate::firewall::blocking_rules(settings.get_socket_address().port()) {
+        Ok(rules) if rules.is_empty() => report.line("No rules blocking Wrangler."),
+        Ok(rules) => report.line(format!("[ERROR] Blocked by: {}", rules.join(", "))),
+        Err(e) => report.line(format!("[WARNING] Could not check blocking rules: {e}")),
+    }
+}
+#[cfg(not(target_os = "windows"))]
+fn check_firewall(report: &mut Report, _settings: &WranglerSettings) {
+    report.section("Firewall");
+    report.line("Not checked on this platform.");
+}
+
+/// The report printed by `--diagnose`, also part of the diagnostics bundle.
+pub fn report(settings: &WranglerSettings, redact_address: bool) -> String {
+    let mut report = Report {
+        text: String::new(),
+    };
+    report.line(format!(
+        "# SlimeVR Wrangler {} diagnostics ({} {})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ));
+    check_bluetooth(&mut report);
+    check_hid(&mut report);
+    check_server(&mut report, settings, redact_address);
+    check_blacklist(&mut report, settings);
+    check_firewall(&mut report, settings);
+    report.text
+}
+
+pub fn run(settings: &WranglerSettings) {
+    let report = report(settings, false);
+    println!("----- Copy everything below this line when reporting an issue -----");
+    println!("{report}");
+    println!("----- End of report -----");
+}
@@ -0,0 +1,132 @@
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub enum FirewallStatus {
+    /// Not checked, or not on Windows.
+    #[default]
+    Unknown,
+    Clear,
+    /// Names of the enabled rules blocking Wrangler or the server port.
+    Blocked(Vec<String>),
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::{env, fs, process::Command};
+
+    /// Quotes a string for a PowerShell script.
+    fn quote(s: &str) -> String {
+        format!("'{}'", s.replace('\'', "''"))
+    }
+
+    fn exe() -> Result<String, String> {
+        env::current_exe()
+            .map(|p| p.display().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Enabled block rules for this executable, or for UDP traffic on the server port.
+    fn rules_script(exe: &str, port: u16) -> String {
+        format!(
+            "$rules = @(Get-NetFirewallApplicationFilter -Program {exe} | Get-NetFirewallRule) + \
+                @(Get-NetFirewallPortFilter -Protocol UDP | \
+                    Where-Object {{ $_.RemotePort -eq '{port}' -or $_.LocalPort -eq '{port}' }} | \
+                    Get-NetFirewallRule); \
+            $blocking = $rules | Where-Object {{ $_.Enabled -eq 'True' -and $_.Action -eq 'Block' }};",
+            exe = quote(exe)
+        )
+    }
+
+    pub fn blocking_rules(port: u16) -> Result<Vec<String>, String> {
+        let script = format!(
+            "{} $blocking | ForEach-Object {{ $_.DisplayName }}",
+            rules_script(&exe()?, port)
+        );
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Removes the block rules and allows UDP for Wrangler in both directions. Block rules
+    /// always win over allow rules, so adding an allow rule alone does nothing.
+    pub fn fix(port: u16) -> Result<(), String> {
+        let exe = exe()?;
+        let script = format!(
+            "{} $blocking | Remove-NetFirewallRule; \
+            foreach ($direction in 'Inbound', 'Outbound') {{ \
+                New-NetFirewallRule -DisplayName 'SlimeVR Wrangler' -Direction $direction \
+                    -Program {} -Protocol UDP -Action Allow | Out-Null \
+            }}",
+            rules_script(&exe, port),
+            quote(&exe)
+        );
+        let path = env::temp_dir().join("slimevr-wrangler-firewall.ps1");
+        fs::write(&path, script).map_err(|e| e.to_string())?;
+        // Changing firewall rules needs administrator rights, this shows the UAC prompt.
+        let elevate = format!(
+            "Start-Process powershell -Verb RunAs -Wait -WindowStyle Hidden -ArgumentList \
+                '-NoProfile -ExecutionPolicy Bypass -File \"{}\"'",
+            path.display().to_string().replace('\'', "''")
+        );
+        let status = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &elevate])
+            .status();
+        fs::remove_file(&path).ok();
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(_) => Err("Cancelled".into()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+#[cfg(target_os = "windows")]
+pub use windows::blocking_rules;
+
+#[cfg(target_os = "windows")]
+fn inner_check(port: u16) -> FirewallStatus {
+    match blocking_rules(port) {
+        Ok(rules) if rules.is_empty() => FirewallStatus::Clear,
+        Ok(rules) => {
+            println!(
+                "[INFO] Firewall - Rules blocking SlimeVR Server traffic: {}",
+                rules.join(", ")
+            );
+            FirewallStatus::Blocked(rules)
+        }
+        Err(e) => {
+            println!("[INFO] Firewall - Could not check firewall rules: {e}");
+            FirewallStatus::Unknown
+        }
+    }
+}
+#[cfg(not(target_os = "windows"))]
+fn inner_check(_port: u16) -> FirewallStatus {
+    FirewallStatus::Unknown
+}
+
+pub async fn check_firewall(port: u16) -> FirewallStatus {
+    tokio::task::spawn_blocking(move || inner_check(port))
+        .await
+        .unwrap()
+}
+
+pub async fn fix_firewall(port: u16) -> FirewallStatus {
+    tokio::task::spawn_blocking(move || {
+        #[cfg(target_os = "windows")]
+        if let Err(e) = windows::fix(port) {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not change firewall rules: {e}");
+        }
+        inner_check(port)
+    })
+    .await
+    .unwrap()
+}
@@ -0,0 +1,120 @@
+use iced::widget::canvas;
+use iced::widget::canvas::{stroke, Cursor, Frame, Geometry, LineJoin, Path, Stroke};
+use iced::{Color, Point, Rectangle, Theme};
+
+/// Corners of a flat box, about the shape of a Joy-Con lying on its back. Front is +y.
+const CORNERS: [[f32; 3]; 8] = [
+    [-0.5, -1.0, -0.3],
+    [0.5, -1.0, -0.3],
+    [0.5, 1.0, -0.3],
+    [-0.5, 1.0, -0.3],
+    [-0.5, -1.0, 0.3],
+    [0.5, -1.0, 0.3],
+    [0.5, 1.0, 0.3],
+    [-0.5, 1.0, 0.3],
+];
+/// Corner indices of each face, counter-clockwise seen from outside, with the face color.
+const FACES: [([usize; 4], Color); 6] = [
+    ([4, 5, 6, 7], Color::from_rgb(0.40, 0.20, 0.60)), // top
+    ([3, 2, 1, 0], Color::from_rgb(0.25, 0.13, 0.38)), // bottom
+    ([2, 3, 7, 6], Color::from_rgb(0.96, 0.60, 0.30)), // front
+    ([0, 1, 5, 4], Color::from_rgb(0.32, 0.16, 0.48)), // back
+    ([1, 2, 6, 5], Color::from_rgb(0.36, 0.18, 0.54)), // right
+    ([3, 0, 4, 7], Color::from_rgb(0.29, 0.15, 0.43)), // left
+];
+/// The camera looks slightly down, so a level tracker shows its top.
+const CAMERA_TILT: f32 = 0.45;
+
+/// Roll, pitch and yaw combined in one pseudo-3D view of the tracker. The front is orange.
+#[derive(Debug)]
+pub struct Gimbal {
+    matrix: [[f32; 3]; 3],
+}
+impl Gimbal {
+    /// Takes the angles in degrees, in the order of `Status::rotation`.
+    pub fn new((roll, pitch, yaw): (f64, f64, f64)) -> Self {
+        let (sr, cr) = (roll as f32).to_radians().sin_cos();
+        let (sp, cp) = (pitch as f32).to_radians().sin_cos();
+        let (sy, cy) = (yaw as f32).to_radians().sin_cos();
+        // Rz(yaw) * Ry(pitch) * Rx(roll), the same order the angles were taken apart in.
+        let rotation = [
+            [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+            [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+            [-sp, cp * sr, cp * cr],
+        ];
+        let (st, ct) = CAMERA_TILT.sin_cos();
+        let camera = [[1.0, 0.0, 0.0], [0.0, ct, st], [0.0, -st, ct]];
+        Self {
+            matrix: multiply(camera, rotation),
+        }
+    }
+
+    /// Screen x and y (down) of a point. The viewer is in front of the tracker, like looking
+    /// into a mirror.
+    fn project(&self, [x, y, z]: [f32; 3]) -> [f32; 2] {
+        let row = |r: [f32; 3]| r[0] * x + r[1] * y + r[2] * z;
+        [-row(self.matrix[0]), -row(self.matrix[2])]
+    }
+}
+
+fn multiply(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+impl<Message> canvas::Program<Message> for Gimbal {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let center = frame.center();
+        let scale = frame.width().min(frame.height()) / 2.0 / 1.2;
+
+        let corners = CORNERS.map(|c| self.project(c));
+        let point = |i: usize| {
+            Point::new(
+                center.x + corners[i][0] * scale,
+                center.y + corners[i][1] * scale,
+            )
+        };
+
+        // Only faces turned towards the viewer, a convex shape needs no sorting then.
+        for (indices, color) in FACES {
+            let [a, b, c, _] = indices.map(|i| corners[i]);
+            let facing = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            if facing >= 0.0 {
+                continue;
+            }
+            let face = Path::new(|path| {
+                path.move_to(point(indices[0]));
+                for &i in &indices[1..] {
+                    path.line_to(point(i));
+                }
+                path.close();
+            });
+            frame.fill(&face, color);
+            frame.stroke(
+                &face,
+                Stroke {
+                    width: 1.0,
+                    style: stroke::Style::Solid(Color::WHITE),
+                    line_join: LineJoin::Round,
+                    ..Stroke::default()
+                },
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
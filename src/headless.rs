@@ -0,0 +1,44 @@
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::joycon::{self, Status};
+use crate::settings;
+use slimevr_wrangler_core::events::Event;
+
+fn print_device(status: &Status) {
+    println!(
+        "[INFO] {} ({:?}): {}, battery {:?}",
+        status.name, status.design.design_type, status.status, status.battery
+    );
+    if let Some(hint) = status.status.hint() {
+        println!("       {hint}");
+    }
+}
+
+pub fn run(settings: settings::Handler) {
+    let wrapper = joycon::Wrapper::new(settings.clone());
+    let mut events = wrapper.events().subscribe();
+    crate::start_services(&wrapper, &settings);
+    println!(
+        "[INFO] Running without GUI, sending to SlimeVR Server at {}. Press Ctrl+C to quit.",
+        settings.load().get_socket_address()
+    );
+
+    loop {
+        match events.blocking_recv() {
+            Ok(Event::ServerChanged(server)) => println!("[INFO] Server: {server}"),
+            Ok(Event::DeviceAdded(status) | Event::DeviceChanged(status)) => print_device(&status),
+            Ok(Event::DeviceRemoved { serial_number }) => {
+                println!("[INFO] {serial_number}: removed");
+            }
+            Ok(Event::SocketError(Some(error))) => {
+                println!("\x1b[0;31m[ERROR]\x1b[0m {error}");
+            }
+            Ok(Event::Quit) => {
+                println!("[INFO] No SlimeVR Server for a while, quitting.");
+                return;
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
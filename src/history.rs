@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use iced::widget::canvas;
+use iced::widget::canvas::{stroke, Cursor, Frame, Geometry, LineCap, Path, Stroke};
+use iced::{Color, Point, Rectangle, Theme};
+
+/// Time shown in the graph.
+const SPAN: Duration = Duration::from_secs(60);
+/// Snapshots arrive up to 60 times per second, far more points than the graph is wide.
+const INTERVAL: Duration = Duration::from_millis(250);
+/// Smallest range in degrees shown vertically, so noise doesn't look like drift.
+const MIN_RANGE: f64 = 20.0;
+
+/// Yaw of the last minute, drawn as a line so that drift shows up as a slope.
+#[derive(Debug, Default)]
+pub struct YawHistory {
+    /// Unwrapped yaw in degrees, so turning past 180 doesn't jump to the other edge.
+    samples: VecDeque<(Instant, f64)>,
+}
+impl YawHistory {
+    pub fn push(&mut self, now: Instant, yaw: f64) {
+        let yaw = match self.samples.back() {
+            Some((last, _)) if now.duration_since(*last) < INTERVAL => return,
+            Some((_, previous)) => previous + (yaw - previous + 180.0).rem_euclid(360.0) - 180.0,
+            None => yaw,
+        };
+        self.samples.push_back((now, yaw));
+        while let Some((time, _)) = self.samples.front() {
+            if now.duration_since(*time) <= SPAN {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+}
+
+impl<Message> canvas::Program<Message> for YawHistory {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        frame.fill_rectangle(
+            Point::ORIGIN,
+            bounds.size(),
+            Color::from_rgba8(0x66, 0x34, 0x99, 0.3),
+        );
+
+        let Some((newest, _)) = self.samples.back() else {
+            return vec![frame.into_geometry()];
+        };
+        let (min, max) = self
+            .samples
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), (_, yaw)| {
+                (min.min(*yaw), max.max(*yaw))
+            });
+        let middle = (min + max) / 2.0;
+        let range = (max - min).max(MIN_RANGE);
+
+        let (width, height) = (frame.width(), frame.height());
+        let line = Path::new(|path| {
+            for (i, (time, yaw)) in self.samples.iter().enumerate() {
+                let age = newest.duration_since(*time).as_secs_f32() / SPAN.as_secs_f32();
+                let point = Point::new(
+                    width * (1.0 - age),
+                    height * (0.5 - ((yaw - middle) / range) as f32),
+                );
+                if i == 0 {
+                    path.move_to(point);
+                } else {
+                    path.line_to(point);
+                }
+            }
+        });
+        frame.stroke(
+            &line,
+            Stroke {
+                width: 1.5,
+                style: stroke::Style::Solid(Color::WHITE),
+                line_cap: LineCap::Round,
+                ..Stroke::default()
+            },
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
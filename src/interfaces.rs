@@ -0,0 +1,44 @@
+use std::{
+    fmt::Display,
+    net::{IpAddr, SocketAddr, UdpSocket},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    pub ip: IpAddr,
+}
+impl Display for Interface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.ip)
+    }
+}
+
+/// Network interfaces with an address to send from, loopback included for a server on the
+/// same PC.
+pub fn list() -> Vec<Interface> {
+    if_addrs::get_if_addrs()
+        .map(|interfaces| {
+            interfaces
+                .into_iter()
+                .map(|i| Interface {
+                    ip: i.ip(),
+                    name: i.name,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Address the OS sends to `server` from, when not bound to an interface. Connecting a UDP
+/// socket only picks the route, nothing is sent.
+pub fn route_to(server: SocketAddr) -> Option<IpAddr> {
+    let any: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(any).ok()?;
+    socket.connect(server).ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
@@ -0,0 +1,273 @@
+//! Lets a second `slimevr-wrangler <command>` control the instance that is already running,
+//! through a Unix socket or, on Windows, a named pipe. Each connection sends one command per
+//! line and gets a line of JSON back.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use crate::joycon::Remote;
+use crate::settings;
+
+/// Commands for a running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    ResetYaw,
+    ResetMounting,
+    Pause,
+    Resume,
+    Status,
+}
+impl Command {
+    pub const ALL: [Command; 5] = [
+        Command::ResetYaw,
+        Command::ResetMounting,
+        Command::Pause,
+        Command::Resume,
+        Command::Status,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::ResetYaw => "reset-yaw",
+            Command::ResetMounting => "reset-mounting",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+            Command::Status => "status",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+fn answer(line: &str, remote: &Remote) -> Value {
+    let Some(command) = Command::from_name(line.trim()) else {
+        return json!({ "error": format!("Unknown command \"{}\"", line.trim()) });
+    };
+    match command {
+        Command::ResetYaw => remote.yaw_reset(),
+        Command::ResetMounting => remote.mounting_reset(),
+        Command::Pause => remote.set_paused(true),
+        Command::Resume => remote.set_paused(false),
+        Command::Status => return json!(*remote.snapshot()),
+    }
+    json!({ "ok": true })
+}
+
+/// How long to wait for a running instance to answer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Each profile gets its own endpoint, like its own settings file.
+fn endpoint_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("slimevr-wrangler-{profile}"),
+        None => "slimevr-wrangler".into(),
+    }
+}
+
+#[cfg(unix)]
+mod endpoint {
+    use std::{
+        fs::{self, DirBuilder},
+        io::{self, BufRead, BufReader, Write},
+        os::unix::{
+            fs::{DirBuilderExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+        thread,
+    };
+
+    use directories::ProjectDirs;
+
+    use super::TIMEOUT;
+    use crate::joycon::Remote;
+
+    /// In a directory only this user can get into, so other users can't send commands. The
+    /// runtime dir is already like that, the cache dir in the user's home is the fallback.
+    fn path(name: &str) -> io::Result<PathBuf> {
+        let dirs = ProjectDirs::from("", "", "SlimeVR Wrangler")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+        let dir = dirs
+            .runtime_dir()
+            .unwrap_or_else(|| dirs.cache_dir())
+            .to_owned();
+        Ok(dir.join(format!("{name}.sock")))
+    }
+
+    pub fn connect(name: &str) -> io::Result<UnixStream> {
+        let stream = UnixStream::connect(path(name)?)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        Ok(stream)
+    }
+
+    pub fn listen(name: &str, remote: Remote) -> io::Result<()> {
+        let path = path(name)?;
+        if let Some(dir) = path.parent() {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        // A socket nobody answers on is left over from an instance that didn't exit cleanly.
+        if path.exists() && UnixStream::connect(&path).is_err() {
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let remote = remote.clone();
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(&stream);
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).map_or(false, |n| n > 0) {
+                        let reply = super::answer(&line, &remote);
+                        if writeln!(&stream, "{reply}").is_err() {
+                            break;
+                        }
+                        line.clear();
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod endpoint {
+    use std::{
+        fs::{File, OpenOptions},
+        io, thread,
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::windows::named_pipe::{NamedPipeServer, ServerOptions},
+    };
+
+    use crate::joycon::Remote;
+
+    fn path(name: &str) -> String {
+        format!(r"\\.\pipe\{name}")
+    }
+
+    pub fn connect(name: &str) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(path(name))
+    }
+
+    async fn serve(pipe: NamedPipeServer, remote: Remote) -> io::Result<()> {
+        let (read, mut write) = tokio::io::split(pipe);
+        let mut lines = BufReader::new(read).lines();
+        while let Some(line) = lines.next_line().await? {
+            let reply = super::answer(&line, &remote);
+            write.write_all(format!("{reply}\n").as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    pub fn listen(name: &str, remote: Remote) -> io::Result<()> {
+        let path = path(name);
+        // Fails if another instance already has the pipe.
+        let first = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&path)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        thread::spawn(move || {
+            runtime.block_on(async move {
+                let mut pipe = first;
+                loop {
+                    if pipe.connect().await.is_ok() {
+                        tokio::spawn(serve(pipe, remote.clone()));
+                    }
+                    pipe = match ServerOptions::new().create(&path) {
+                        Ok(pipe) => pipe,
+                        Err(e) => {
+                            println!("\x1b[0;31m[ERROR]\x1b[0m Instance control stopped: {e}");
+                            return;
+                        }
+                    };
+                }
+            });
+        });
+        Ok(())
+    }
+}
+
+fn exchange(name: &str, command: Command) -> Result<Value, String> {
+    let stream = endpoint::connect(name).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(&stream);
+    writeln!(reader.get_mut(), "{}", command.name()).map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    serde_json::from_str(&line).map_err(|e| e.to_string())
+}
+
+/// Sends a command to the instance running with this profile, and returns its reply.
+pub fn send(profile: Option<&str>, command: Command) -> Result<Value, String> {
+    // Named pipes have no read timeout, so a hung instance is waited for on another thread.
+    let name = endpoint_name(profile);
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || tx.send(exchange(&name, command)).ok());
+    rx.recv_timeout(TIMEOUT)
+        .map_err(|_| "timed out waiting for an answer".to_owned())?
+}
+
+/// Whether an instance is already running with this profile.
+pub fn running(profile: Option<&str>) -> bool {
+    send(profile, Command::Status).is_ok()
+}
+
+/// Serial numbers of the trackers used by instances running with other profiles than `own`.
+pub fn claimed_elsewhere(own: Option<&str>) -> Vec<String> {
+    std::iter::once(None)
+        .chain(settings::profiles().into_iter().map(Some))
+        .filter(|profile| profile.as_deref() != own)
+        .filter_map(|profile| send(profile.as_deref(), Command::Status).ok())
+        .flat_map(|status| match status.get("trackers") {
+            Some(Value::Array(trackers)) => trackers.clone(),
+            _ => vec![],
+        })
+        .filter_map(|tracker| Some(tracker.get("serial_number")?.as_str()?.to_owned()))
+        .collect()
+}
+
+/// Listens for commands from other invocations of Wrangler.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    let profile = settings.load().profile.clone();
+    if let Err(e) = endpoint::listen(&endpoint_name(profile.as_deref()), remote) {
+        println!(
+            "\x1b[0;31m[ERROR]\x1b[0m Could not listen for commands from other instances: {e}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_names() {
+        for command in Command::ALL {
+            assert_eq!(Command::from_name(command.name()), Some(command));
+        }
+        assert_eq!(
+            Command::from_name("reset-mounting"),
+            Some(Command::ResetMounting)
+        );
+        assert_eq!(Command::from_name("Reset-Yaw"), None);
+        assert_eq!(Command::from_name(""), None);
+    }
+    #[test]
+    fn endpoint_per_profile() {
+        assert_eq!(endpoint_name(None), "slimevr-wrangler");
+        assert_eq!(endpoint_name(Some("player2")), "slimevr-wrangler-player2");
+    }
+}
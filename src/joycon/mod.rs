@@ -1,17 +0,0 @@
-//mod ui;
-mod imu;
-
-mod communication;
-pub use communication::*;
-
-mod integration;
-#[cfg(target_os = "linux")]
-mod linux_integration;
-use integration::spawn_thread;
-mod test_integration;
-
-mod wrapper;
-pub use wrapper::*;
-
-mod svg;
-pub use svg::*;
@@ -5,35 +5,79 @@ use iced::{
     theme::{self, Theme},
     time,
     widget::{
-        button, canvas, checkbox, container, horizontal_space, scrollable, slider, text,
-        text_input, Column, Container, Row, Scrollable, Svg,
+        button, canvas, checkbox, container, horizontal_space, pick_list, progress_bar, scrollable,
+        slider, text, text_input, Column, Container, Row, Scrollable, Svg,
     },
     window, Alignment, Application, Color, Command, Element, Font, Length, Settings, Subscription,
 };
 
 use circle::circle;
+use gimbal::Gimbal;
+use history::YawHistory;
 use iced_aw::Grid;
 use joycon::{Battery, DeviceStatus, ServerStatus};
-use needle::Needle;
-use settings::WranglerSettings;
+use settings::{AngleDisplay, BoxSize, TrackerData, WranglerSettings};
+use slimevr_wrangler_core::{backup::Backup, events::Event, joycon, settings};
 use std::{
+    collections::{HashMap, HashSet},
     io::{
         self,
         prelude::{Read, Write},
     },
     net::SocketAddr,
-    time::{Duration, Instant},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
-mod joycon;
+mod alerts;
+mod api;
+mod bluetooth;
+mod body_parts;
+mod bundle;
+mod calibration;
 mod steam_blacklist;
 use steam_blacklist as blacklist;
 mod circle;
-mod needle;
-mod settings;
+mod cli;
+mod conflicts;
+mod diagnose;
+mod firewall;
+mod gimbal;
+mod headless;
+mod history;
+mod interfaces;
+mod ipc;
+mod mqtt;
+mod opentrack;
+mod overlay;
+mod recording;
+mod relay;
+mod server_launch;
+mod service;
 mod style;
+mod svg;
+mod tui;
 mod update;
+mod websocket;
 
 const WINDOW_SIZE: (u32, u32) = (980, 700);
+/// Small enough for a single column of trackers next to a VR overlay or a phone sized window.
+const MIN_WINDOW_SIZE: (u32, u32) = (420, 500);
+/// Fits a few trackers, to keep next to a game on the same monitor.
+const WIDGET_WINDOW_SIZE: (u32, u32) = (320, 220);
+/// Width a tracker box has at least before stretching, its height and the height of its yaw
+/// graph.
+fn box_metrics(size: BoxSize) -> (f32, f32, f32) {
+    match size {
+        BoxSize::Small => (270.0, 220.0, 0.0),
+        BoxSize::Medium => (300.0, 530.0, 40.0),
+        BoxSize::Large => (420.0, 680.0, 150.0),
+    }
+}
+/// How long a toast stays up.
+const TOAST_DURATION: Duration = Duration::from_secs(10);
+/// Padding around a tracker box in the grid.
+const BOX_PADDING: f32 = 10.0;
 
 pub const ICONS: Font = Font::External {
     name: "Icons",
@@ -46,15 +90,83 @@ pub fn main() -> iced::Result {
     let rgba8 = image_rs::io::Reader::open("assets/icon.png").unwrap().decode().unwrap().to_rgba8();
     std::fs::write("assets/icon_64.rgba8", rgba8.into_raw());
     */
+    let args = match cli::Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{e}\n\n{}", cli::USAGE);
+            return Ok(());
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if let Some(command) = args.command {
+        match ipc::send(args.profile.as_deref(), command) {
+            Ok(reply) => println!("{reply}"),
+            Err(e) => println!("\x1b[0;31m[ERROR]\x1b[0m Could not reach a running Wrangler: {e}"),
+        }
+        return Ok(());
+    }
+    if args.service || args.install_service || args.uninstall_service {
+        let result = if args.install_service {
+            service::install(args.profile.as_deref())
+        } else if args.uninstall_service {
+            service::uninstall()
+        } else {
+            service::run()
+        };
+        if let Err(e) = result {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Service: {e}");
+        }
+        return Ok(());
+    }
+    // Two instances would fight over the same controllers, and over the settings file.
+    if !args.diagnose && ipc::running(args.profile.as_deref()) {
+        println!(
+            "[INFO] Wrangler is already running with this profile, control it with commands like \
+            `slimevr-wrangler reset-yaw`."
+        );
+        return Ok(());
+    }
+    let handler = settings::Handler::new(args.profile.as_deref(), &args.overrides);
+    if args.diagnose {
+        diagnose::run(&handler.load());
+        return Ok(());
+    }
+    if args.headless {
+        headless::run(handler);
+        return Ok(());
+    }
+    if args.tui {
+        if let Err(e) = tui::run(handler) {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Terminal UI failed: {e}");
+        }
+        return Ok(());
+    }
+    // The minimum size can't change while running, so it only holds back the widget when
+    // switching to it from the full window.
+    let widget_mode = handler.load().widget_mode;
     let settings = Settings {
         window: window::Settings {
-            min_size: Some(WINDOW_SIZE),
-            size: WINDOW_SIZE,
+            min_size: Some(if widget_mode {
+                WIDGET_WINDOW_SIZE
+            } else {
+                MIN_WINDOW_SIZE
+            }),
+            size: if widget_mode {
+                WIDGET_WINDOW_SIZE
+            } else {
+                WINDOW_SIZE
+            },
+            always_on_top: widget_mode,
             icon: window::icon::from_rgba(ICON.to_vec(), 64, 64).ok(),
             ..window::Settings::default()
         },
         antialiasing: true,
-        ..Settings::default()
+        // Closing goes through `Message::WindowCloseRequested`, to save first.
+        exit_on_close_request: false,
+        ..Settings::with_flags(handler)
     };
     match MainState::run(settings) {
         Ok(a) => Ok(a),
@@ -68,96 +180,758 @@ pub fn main() -> iced::Result {
     }
 }
 
+/// Starts the optional integrations that run alongside any frontend.
+fn start_services(wrapper: &joycon::Wrapper, settings: &settings::Handler) {
+    api::spawn(wrapper.remote(), settings.clone());
+    ipc::spawn(wrapper.remote(), settings.clone());
+    websocket::spawn(wrapper.remote(), settings.clone());
+    opentrack::spawn(wrapper.remote(), settings.clone());
+    mqtt::spawn(wrapper.remote(), settings.clone());
+    relay::spawn(settings.clone());
+    overlay::spawn(wrapper.remote(), settings.clone());
+    alerts::spawn(wrapper.remote(), settings.clone());
+}
+
+/// Profiles as they are on disk, and the controllers other instances use.
+#[derive(Debug, Clone)]
+struct ProfileScan {
+    names: Vec<String>,
+    devices: Vec<(String, Vec<String>)>,
+    claimed_elsewhere: Vec<String>,
+}
+
+async fn scan_profiles(own_profile: Option<String>) -> ProfileScan {
+    tokio::task::spawn_blocking(move || ProfileScan {
+        names: settings::profiles(),
+        devices: settings::profile_devices(),
+        claimed_elsewhere: ipc::claimed_elsewhere(own_profile.as_deref()),
+    })
+    .await
+    .unwrap()
+}
+
+/// Rate used for the smooth output setting, other rates can be set in the config file.
+const SMOOTH_OUTPUT_RATE: u16 = 100;
+
+/// Profile picked in the top bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProfileChoice {
+    /// The profile whose controllers are connected.
+    Automatic,
+    Default,
+    Named(String),
+}
+impl std::fmt::Display for ProfileChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileChoice::Automatic => f.write_str("Automatic"),
+            ProfileChoice::Default => f.write_str("Default"),
+            ProfileChoice::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Tracker picked for opentrack output in the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OpentrackChoice {
+    Off,
+    Tracker { serial_number: String, name: String },
+}
+impl OpentrackChoice {
+    fn serial_number(&self) -> Option<&str> {
+        match self {
+            OpentrackChoice::Off => None,
+            OpentrackChoice::Tracker { serial_number, .. } => Some(serial_number),
+        }
+    }
+}
+impl std::fmt::Display for OpentrackChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpentrackChoice::Off => f.write_str("Off"),
+            OpentrackChoice::Tracker { name, .. } => f.write_str(name),
+        }
+    }
+}
+
+/// Network interface picked in the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InterfaceChoice {
+    Automatic,
+    Interface(interfaces::Interface),
+}
+impl std::fmt::Display for InterfaceChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceChoice::Automatic => f.write_str("Automatic"),
+            InterfaceChoice::Interface(interface) => interface.fmt(f),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Message {
     SettingsPressed,
     Tick(Instant),
+    JoyconUpdate(Arc<joycon::Snapshot>),
+    JoyconEvent(Event),
     Dot(Instant),
+    WindowResized(u32),
+    WindowCloseRequested,
     AddressChange(String),
-    UpdateFound(Option<String>),
+    UpdateFound(Option<update::UpdateInfo>),
     UpdatePressed,
+    UpdateConfirmed,
+    UpdateCancelled,
+    UpdateSkipped,
+    UpdateSnoozed,
     BlacklistChecked(blacklist::BlacklistResult),
+    BluetoothChecked(bluetooth::Check),
+    ExportRedactToggled(bool),
+    ExportPressed,
+    ExportDone(Result<PathBuf, String>),
+    BluetoothRetryPressed,
+    BluetoothWarningDismissed,
+    BodyPartsWatch(Instant),
+    BodyPartsRead(Option<HashMap<u8, String>>),
+    ConflictWatch(Instant),
+    ConflictChecked(Option<conflicts::Conflict>),
+    ProfilesWatch(Instant),
+    ProfilesRead(ProfileScan),
+    BlacklistWatch(Instant),
+    BlacklistModified(Option<SystemTime>),
+    BlacklistModifiedPolled(Option<SystemTime>),
+    BlacklistRechecked(blacklist::BlacklistResult),
     BlacklistFixPressed,
+    BlacklistFixWhenClosed,
+    BlacklistUndoPressed,
+    BlacklistReverted(blacklist::BlacklistResult),
+    SwitchInputChecked(blacklist::BlacklistResult),
+    SwitchInputFixPressed,
+    SteamPathChange(String),
+    SteamPathSubmitted,
+    ServerPathChange(String),
+    ServerLaunchPressed,
+    SocketRetryPressed,
+    FirewallChecked(firewall::FirewallStatus),
+    FirewallFixPressed,
     JoyconRotate(String, bool),
     JoyconScale(String, f64),
+    JoyconYawScale(String, f64),
+    JoyconAlias(String, String),
+    JoyconData(String, TrackerData),
+    JoyconLogRaw(String, bool),
+    JoyconGyroBias(String, usize, f64),
+    CalibrationStarted(calibration::Routine),
+    CalibrationCancelled,
+    RawValuesToggled(String),
     SettingsResetToggled(bool),
     SettingsIdsToggled(bool),
+    SettingsApiToggled(bool),
+    SettingsWebsocketToggled(bool),
+    SettingsMqttToggled(bool),
+    SettingsRelayToggled(bool),
+    SettingsMocopiToggled(bool),
+    SettingsOverlayToggled(bool),
+    SettingsPrereleaseToggled(bool),
+    SettingsOfflineToggled(bool),
+    SettingsAutoFixToggled(bool),
+    SettingsSteamVrPauseToggled(bool),
+    SettingsImuQueueChanged(u8),
+    SettingsMaxVelocityChanged(f64),
+    SettingsHeartbeatChanged(u16),
+    SettingsServerTimeoutChanged(u16),
+    SettingsHomeLightToggled(bool),
+    SettingsFlipHandednessToggled(bool),
+    SettingsSmoothOutputToggled(bool),
+    SettingsServerLostPauseChanged(u32),
+    SettingsServerLostExitToggled(bool),
+    SettingsWaitForServerToggled(bool),
+    SettingsAlertsChanged(settings::Alerts),
+    AngleDisplaySelected(AngleDisplay),
+    ColorThemeSelected(settings::ColorTheme),
+    BoxSizeSelected(BoxSize),
+    WidgetToggled(bool),
+    YawResetPressed,
+    AccentColorChanged(String),
+    ProfileSelected(ProfileChoice),
+    OpentrackSelected(OpentrackChoice),
+    InterfaceSelected(InterfaceChoice),
+    RecordingFormatSelected(recording::Format),
+    RecordingPressed,
+    RecordingDone(Result<PathBuf, String>),
+    BackupSelected(Backup),
+    BackupRestorePressed,
+    ReplaySpeedChanged(f64),
+    ReplayLoopToggled(bool),
+    ReplaySeeked(f64),
 }
 
-#[derive(Default)]
 struct MainState {
     joycon: Option<joycon::Wrapper>,
+    /// Controls for the raw IMU log replayed with `--replay`.
+    replay: Option<joycon::Playback>,
     joycon_boxes: JoyconBoxes,
     search_dots: usize,
     settings_show: bool,
+    /// Network interfaces, read when the settings open.
+    interfaces: Vec<interfaces::Interface>,
+    /// Where the OS routes traffic to the server, when not bound to an interface.
+    server_route: Option<std::net::IpAddr>,
+    /// Accent color being typed, saved once it's a valid color.
+    accent_input: String,
     server_connected: ServerStatus,
     server_address: String,
+    socket_error: Option<String>,
+    packet_rate: u32,
+    window_width: u32,
 
     settings: settings::Handler,
-    update_found: Option<String>,
+    update_found: Option<update::UpdateInfo>,
+    changelog_show: bool,
+    updater: Option<update::Updater>,
+    update_progress: Option<update::Progress>,
     blacklist_info: blacklist::BlacklistResult,
+    blacklist_modified: Option<SystemTime>,
+    blacklist_fix_pending: bool,
+    /// The running fix was started by the watcher, not by pressing the button.
+    blacklist_auto_fixing: bool,
+    /// Short notice shown on top of the window, and when it was shown.
+    toast: Option<(String, Instant)>,
+    switch_input_info: blacklist::BlacklistResult,
+    bluetooth: bluetooth::Check,
+    conflict: Option<conflicts::Conflict>,
+    server_launched: bool,
+    disconnected_since: Option<Instant>,
+    firewall: firewall::FirewallStatus,
+    firewall_checked: bool,
+    profile_choice: ProfileChoice,
+    /// Profile names and the controllers they list, read again every few seconds.
+    profile_names: Vec<String>,
+    profiles: Vec<(String, Vec<String>)>,
+    connected: Vec<String>,
+    /// Profile this instance started with, other instances reach it under that name.
+    own_profile: Option<String>,
+    /// Controllers other running instances use, left out when picking a profile.
+    claimed_elsewhere: Vec<String>,
+    /// Kept for the diagnostics bundle.
+    snapshot: Arc<joycon::Snapshot>,
+    export_redact: bool,
+    export_result: Option<Result<PathBuf, String>>,
+    recording: Option<recording::Recording>,
+    recording_format: recording::Format,
+    recording_result: Option<Result<PathBuf, String>>,
+    backups: Vec<Backup>,
+    backup_choice: Option<Backup>,
+    restore_result: Option<Result<(), String>>,
+    calibration: Option<calibration::Calibration>,
 }
 impl Application for MainState {
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = settings::Handler;
     type Message = Message;
     type Theme = Theme;
 
-    fn new(_: Self::Flags) -> (Self, Command<Self::Message>) {
-        let mut new = Self::default();
-        new.joycon = Some(joycon::Wrapper::new(new.settings.clone()));
-        new.server_address = format!("{}", new.settings.load().get_socket_address());
+    fn new(settings: Self::Flags) -> (Self, Command<Self::Message>) {
+        let update_check = update_check(&settings.load());
+        let steam_path = settings.load().steam_path();
+        let blacklist_devices = blacklist::Device::for_designs(settings.load().used_designs());
+        let wrapper = joycon::Wrapper::new(settings.clone());
+        start_services(&wrapper, &settings);
+        let new = Self {
+            replay: wrapper.remote().replay(),
+            joycon: Some(wrapper),
+            joycon_boxes: JoyconBoxes::default(),
+            search_dots: 0,
+            settings_show: false,
+            interfaces: vec![],
+            server_route: None,
+            accent_input: settings.load().accent_color.clone(),
+            server_connected: ServerStatus::default(),
+            packet_rate: 0,
+            window_width: WINDOW_SIZE.0,
+            server_address: format!("{}", settings.load().get_socket_address()),
+            socket_error: None,
+            settings,
+            update_found: None,
+            changelog_show: false,
+            updater: None,
+            update_progress: None,
+            blacklist_info: blacklist::BlacklistResult::default(),
+            blacklist_modified: None,
+            blacklist_fix_pending: false,
+            blacklist_auto_fixing: false,
+            toast: None,
+            switch_input_info: blacklist::BlacklistResult::default(),
+            bluetooth: bluetooth::Check::default(),
+            conflict: None,
+            server_launched: false,
+            disconnected_since: Some(Instant::now()),
+            firewall: firewall::FirewallStatus::Unknown,
+            firewall_checked: false,
+            // A profile given on the command line is a manual choice.
+            profile_choice: match &settings.load().profile {
+                Some(profile) => ProfileChoice::Named(profile.clone()),
+                None => ProfileChoice::Automatic,
+            },
+            profile_names: settings::profiles(),
+            profiles: settings::profile_devices(),
+            connected: vec![],
+            own_profile: settings.load().profile.clone(),
+            claimed_elsewhere: vec![],
+            snapshot: Arc::default(),
+            export_redact: true,
+            export_result: None,
+            recording: None,
+            recording_format: recording::Format::Csv,
+            recording_result: None,
+            backups: vec![],
+            backup_choice: None,
+            restore_result: None,
+            calibration: None,
+        };
         (
             new,
             Command::batch(vec![
-                Command::perform(update::check_updates(), Message::UpdateFound),
-                Command::perform(blacklist::check_blacklist(), Message::BlacklistChecked),
+                update_check,
+                Command::perform(
+                    blacklist::check_blacklist(steam_path.clone(), blacklist_devices),
+                    Message::BlacklistChecked,
+                ),
+                Command::perform(
+                    blacklist::check_switch_input(steam_path),
+                    Message::SwitchInputChecked,
+                ),
+                Command::perform(bluetooth::check_adapter(), Message::BluetoothChecked),
             ]),
         )
     }
 
     fn title(&self) -> String {
-        "SlimeVR Wrangler".into()
+        // Tells the windows apart when running several instances.
+        match &self.settings.load().profile {
+            Some(profile) => format!("SlimeVR Wrangler ({profile})"),
+            None => "SlimeVR Wrangler".into(),
+        }
     }
     fn theme(&self) -> Theme {
-        Theme::Dark
+        let settings = self.settings.load();
+        style::theme(settings.color_theme, &settings.accent_color)
     }
 
     fn update(&mut self, message: Message) -> Command<Self::Message> {
         match message {
             Message::SettingsPressed => {
                 self.settings_show = !self.settings_show;
+                if self.settings_show {
+                    self.interfaces = interfaces::list();
+                    self.server_route =
+                        interfaces::route_to(self.settings.load().get_socket_address());
+                    self.backups = self.settings.backups();
+                    self.backup_choice = None;
+                    self.restore_result = None;
+                }
             }
-            Message::Tick(_time) => {
-                if let Some(ref ji) = self.joycon {
-                    if let Some(res) = ji.poll_status() {
-                        self.joycon_boxes.statuses = res;
-                    }
-                    if let Some(connected) = ji.poll_server() {
-                        self.server_connected = connected;
+            Message::JoyconUpdate(snapshot) => {
+                self.joycon_boxes.update(&snapshot.trackers);
+                if let Some(calibration) = &mut self.calibration {
+                    calibration.sample(Instant::now(), &snapshot.trackers);
+                }
+                self.packet_rate = snapshot.packet_rate;
+                self.snapshot = snapshot.clone();
+                if self.connected != snapshot.connected {
+                    self.connected = snapshot.connected.clone();
+                    if self.profile_choice == ProfileChoice::Automatic {
+                        self.auto_profile();
                     }
                 }
             }
+            Message::JoyconEvent(Event::ServerChanged(server)) => {
+                self.server_connected = server;
+                if server == ServerStatus::Connected {
+                    self.server_launched = false;
+                    self.disconnected_since = None;
+                    self.firewall = firewall::FirewallStatus::Unknown;
+                } else if self.disconnected_since.is_none() {
+                    self.disconnected_since = Some(Instant::now());
+                }
+            }
+            Message::JoyconEvent(Event::SocketError(error)) => self.socket_error = error,
+            Message::JoyconEvent(Event::Quit) => {
+                println!("[INFO] No SlimeVR Server for a while, quitting.");
+                return self.update(Message::WindowCloseRequested);
+            }
+            Message::WindowCloseRequested => {
+                if let Some(ref ji) = self.joycon {
+                    ji.remote().save_learned_drift();
+                }
+                return window::close();
+            }
+            Message::JoyconEvent(_) => {}
+            Message::ProfileSelected(choice) => {
+                match &choice {
+                    ProfileChoice::Automatic => self.auto_profile(),
+                    ProfileChoice::Default => self.switch_profile(None),
+                    ProfileChoice::Named(profile) => self.switch_profile(Some(profile.clone())),
+                }
+                self.profile_choice = choice;
+            }
+            Message::Tick(time) => {
+                if let Some(progress) = self.updater.as_ref().and_then(update::Updater::poll) {
+                    self.update_progress = Some(progress);
+                }
+                let outcome = self.calibration.as_mut().and_then(|c| c.tick(time));
+                if let Some(outcome) = outcome {
+                    self.calibration = None;
+                    self.calibration_done(outcome);
+                }
+            }
+            Message::WindowResized(width) => {
+                self.window_width = width;
+            }
             Message::Dot(_time) => {
                 self.search_dots = (self.search_dots + 1) % 4;
+                if let Some((_, shown)) = &self.toast {
+                    if shown.elapsed() > TOAST_DURATION {
+                        self.toast = None;
+                    }
+                }
+                // A handshake takes at most a few seconds, so by now something is in the way.
+                let stuck = self
+                    .disconnected_since
+                    .map_or(false, |since| since.elapsed() > Duration::from_secs(15));
+                if stuck && !self.firewall_checked {
+                    self.firewall_checked = true;
+                    return Command::perform(
+                        firewall::check_firewall(self.settings.load().get_socket_address().port()),
+                        Message::FirewallChecked,
+                    );
+                }
+            }
+            Message::FirewallChecked(status) => {
+                self.firewall = status;
+            }
+            Message::FirewallFixPressed => {
+                return Command::perform(
+                    firewall::fix_firewall(self.settings.load().get_socket_address().port()),
+                    Message::FirewallChecked,
+                );
             }
             Message::AddressChange(value) => {
                 self.settings.change(|ws| ws.address = value);
             }
-            Message::UpdateFound(version) => {
-                self.update_found = version;
+            Message::UpdateFound(info) => {
+                self.update_found =
+                    info.filter(|info| !self.settings.load().update_hidden(&info.version));
             }
-            Message::UpdatePressed => {
+            Message::UpdateSkipped => {
+                if let Some(info) = self.update_found.take() {
+                    self.settings
+                        .change(|ws| ws.skipped_update = Some(info.version));
+                }
+            }
+            Message::UpdateSnoozed => {
                 self.update_found = None;
-                update::update();
+                self.settings
+                    .change(|ws| ws.update_snooze(Duration::from_secs(60 * 60 * 24)));
+            }
+            Message::UpdatePressed => {
+                self.changelog_show = true;
+            }
+            Message::UpdateCancelled => {
+                self.changelog_show = false;
+            }
+            Message::UpdateConfirmed => {
+                self.changelog_show = false;
+                self.update_progress = None;
+                self.updater = self.update_found.take().map(update::Updater::start);
+            }
+            Message::ExportRedactToggled(new) => {
+                self.export_redact = new;
+            }
+            Message::ExportPressed => {
+                self.export_result = None;
+                return Command::perform(
+                    bundle::export(
+                        (**self.settings.load()).clone(),
+                        self.snapshot.clone(),
+                        self.export_redact,
+                    ),
+                    Message::ExportDone,
+                );
+            }
+            Message::ExportDone(result) => {
+                self.export_result = Some(result);
+            }
+            Message::RecordingFormatSelected(format) => {
+                self.recording_format = format;
+            }
+            Message::RecordingPressed => {
+                if let Some(recording) = self.recording.take() {
+                    return Command::perform(recording.finish(), Message::RecordingDone);
+                }
+                if let Some(joycon) = &self.joycon {
+                    self.recording_result = None;
+                    self.recording = Some(recording::Recording::start(
+                        joycon.remote(),
+                        self.recording_format,
+                    ));
+                }
+            }
+            Message::RecordingDone(result) => {
+                self.recording_result = Some(result);
+            }
+            Message::BackupSelected(backup) => {
+                self.backup_choice = Some(backup);
+                self.restore_result = None;
+            }
+            Message::BackupRestorePressed => {
+                if let Some(backup) = self.backup_choice.take() {
+                    self.restore_result = Some(self.settings.restore(&backup));
+                    self.server_address = format!("{}", self.settings.load().get_socket_address());
+                    self.accent_input = self.settings.load().accent_color.clone();
+                    self.backups = self.settings.backups();
+                }
+            }
+            Message::BluetoothChecked(check) => {
+                self.bluetooth = check;
+            }
+            Message::BluetoothWarningDismissed => {
+                self.bluetooth.warnings.clear();
+            }
+            Message::BodyPartsWatch(_) => {
+                return Command::perform(
+                    body_parts::body_parts(
+                        self.settings.load().server_path(),
+                        self.settings.load().emulated_mac,
+                    ),
+                    Message::BodyPartsRead,
+                );
+            }
+            Message::BodyPartsRead(body_parts) => {
+                self.joycon_boxes.body_parts = body_parts;
+            }
+            Message::ConflictWatch(_) => {
+                let failed = self
+                    .snapshot
+                    .trackers
+                    .iter()
+                    .filter(|t| t.status == DeviceStatus::AccessDenied)
+                    .map(|t| t.serial_number.clone())
+                    .collect();
+                return Command::perform(
+                    conflicts::check_conflicts(self.connected.clone(), failed),
+                    Message::ConflictChecked,
+                );
+            }
+            Message::ConflictChecked(conflict) => {
+                self.conflict = conflict;
+            }
+            Message::ProfilesWatch(_) => {
+                return Command::perform(
+                    scan_profiles(self.own_profile.clone()),
+                    Message::ProfilesRead,
+                );
+            }
+            Message::ProfilesRead(scan) => {
+                let changed = self.profiles != scan.devices
+                    || self.claimed_elsewhere != scan.claimed_elsewhere;
+                self.profile_names = scan.names;
+                self.profiles = scan.devices;
+                self.claimed_elsewhere = scan.claimed_elsewhere;
+                if changed && self.profile_choice == ProfileChoice::Automatic {
+                    self.auto_profile();
+                }
+            }
+            Message::BluetoothRetryPressed => {
+                return Command::perform(bluetooth::check_adapter(), Message::BluetoothChecked);
             }
             Message::BlacklistChecked(info) => {
+                if !info.added.is_empty() {
+                    self.settings.change(|ws| {
+                        for id in &info.added {
+                            if !ws.blacklist_added.contains(id) {
+                                ws.blacklist_added.push(id.clone());
+                            }
+                        }
+                    });
+                }
+                if self.blacklist_fix_pending && info.steam_running {
+                    return Command::none();
+                }
+                // Steam would undo the fix when it exits, so wait for that.
+                if self.blacklist_auto_fixing && info.steam_running {
+                    self.blacklist_fix_pending = true;
+                    self.blacklist_info = blacklist::BlacklistResult::info(
+                        "Steam removed the controller blacklist. It will be added back once Steam is closed.",
+                    );
+                    return Command::none();
+                }
+                self.blacklist_fix_pending = false;
+                let auto_fixed = std::mem::take(&mut self.blacklist_auto_fixing) && info.updated;
+                if auto_fixed {
+                    println!("[INFO] Steam config - Blacklist added back after Steam removed it.");
+                    self.toast = Some((
+                        "Steam removed the controller blacklist, it was added back.".into(),
+                        Instant::now(),
+                    ));
+                    self.blacklist_info = blacklist::BlacklistResult::default();
+                } else {
+                    self.blacklist_info = info;
+                }
+                // Don't treat our own writes as Steam changing the config.
+                return Command::perform(
+                    blacklist::config_modified(self.settings.load().steam_path()),
+                    Message::BlacklistModified,
+                );
+            }
+            Message::BlacklistModified(modified) => {
+                self.blacklist_modified = modified;
+            }
+            Message::BlacklistWatch(_) => {
+                if self.blacklist_fix_pending {
+                    // Fails with steam_running until Steam has exited.
+                    return Command::perform(
+                        blacklist::update_blacklist(
+                            self.settings.load().steam_path(),
+                            blacklist::Device::for_designs(self.settings.load().used_designs()),
+                        ),
+                        Message::BlacklistChecked,
+                    );
+                }
+                return Command::perform(
+                    blacklist::config_modified(self.settings.load().steam_path()),
+                    Message::BlacklistModifiedPolled,
+                );
+            }
+            Message::BlacklistModifiedPolled(modified) => {
+                if modified != self.blacklist_modified {
+                    self.blacklist_modified = modified;
+                    println!(
+                        "[INFO] Steam config - Config file changed, checking blacklist again."
+                    );
+                    return Command::batch(vec![
+                        Command::perform(
+                            blacklist::check_blacklist(
+                                self.settings.load().steam_path(),
+                                blacklist::Device::for_designs(self.settings.load().used_designs()),
+                            ),
+                            Message::BlacklistRechecked,
+                        ),
+                        Command::perform(
+                            blacklist::check_switch_input(self.settings.load().steam_path()),
+                            Message::SwitchInputChecked,
+                        ),
+                    ]);
+                }
+            }
+            Message::BlacklistRechecked(info) => {
+                if info.fix_button && self.settings.load().blacklist_auto_fix {
+                    self.blacklist_auto_fixing = true;
+                    return self.update(Message::BlacklistFixPressed);
+                }
                 self.blacklist_info = info;
             }
             Message::BlacklistFixPressed => {
                 self.blacklist_info =
                     blacklist::BlacklistResult::info("Updating steam config file.....");
-                return Command::perform(blacklist::update_blacklist(), Message::BlacklistChecked);
+                return Command::perform(
+                    blacklist::update_blacklist(
+                        self.settings.load().steam_path(),
+                        blacklist::Device::for_designs(self.settings.load().used_designs()),
+                    ),
+                    Message::BlacklistChecked,
+                );
+            }
+            Message::BlacklistFixWhenClosed => {
+                self.blacklist_fix_pending = true;
+                self.blacklist_info = blacklist::BlacklistResult::info(
+                    "The blacklist will be fixed once Steam is closed.",
+                );
+            }
+            Message::BlacklistUndoPressed => {
+                let added = self.settings.load().blacklist_added.clone();
+                if added.is_empty() {
+                    self.blacklist_info = blacklist::BlacklistResult::info(
+                        "Wrangler hasn't added anything to the Steam controller blacklist.",
+                    );
+                    return Command::none();
+                }
+                self.blacklist_info =
+                    blacklist::BlacklistResult::info("Updating steam config file.....");
+                return Command::perform(
+                    blacklist::revert_blacklist(self.settings.load().steam_path(), added),
+                    Message::BlacklistReverted,
+                );
+            }
+            Message::BlacklistReverted(info) => {
+                if info.updated {
+                    self.settings.change(|ws| ws.blacklist_added.clear());
+                }
+                return self.update(Message::BlacklistChecked(info));
+            }
+            Message::SwitchInputChecked(info) => {
+                self.switch_input_info = info;
+            }
+            Message::SwitchInputFixPressed => {
+                self.switch_input_info =
+                    blacklist::BlacklistResult::info("Updating steam config file.....");
+                return Command::perform(
+                    blacklist::turn_off_switch_input(self.settings.load().steam_path()),
+                    Message::SwitchInputChecked,
+                );
+            }
+            Message::SteamPathChange(value) => {
+                self.settings.change(|ws| {
+                    ws.steam_path = Some(value).filter(|v| !v.trim().is_empty());
+                });
+            }
+            Message::SteamPathSubmitted => {
+                return Command::perform(
+                    blacklist::check_blacklist(
+                        self.settings.load().steam_path(),
+                        blacklist::Device::for_designs(self.settings.load().used_designs()),
+                    ),
+                    Message::BlacklistChecked,
+                );
+            }
+            Message::ServerPathChange(value) => {
+                self.settings.change(|ws| {
+                    ws.server_path = Some(value).filter(|v| !v.trim().is_empty());
+                });
+            }
+            Message::ReplaySpeedChanged(speed) => {
+                if let Some(replay) = &self.replay {
+                    replay.set_speed(speed);
+                }
+            }
+            Message::ReplayLoopToggled(looping) => {
+                if let Some(replay) = &self.replay {
+                    replay.set_looping(looping);
+                }
+            }
+            Message::ReplaySeeked(seconds) => {
+                if let Some(replay) = &self.replay {
+                    replay.seek(seconds);
+                }
+            }
+            Message::SocketRetryPressed => {
+                if let Some(joycon) = &self.joycon {
+                    joycon.remote().retry_connection();
+                }
+            }
+            Message::ServerLaunchPressed => {
+                if let Some(path) =
+                    server_launch::find_server(self.settings.load().server_path().as_deref())
+                {
+                    match server_launch::launch(&path) {
+                        Ok(()) => self.server_launched = true,
+                        Err(e) => println!(
+                            "\x1b[0;31m[ERROR]\x1b[0m Could not start SlimeVR Server {}: {e}",
+                            path.display()
+                        ),
+                    }
+                }
             }
             Message::JoyconRotate(serial_number, direction) => {
                 self.settings.change(|ws| {
@@ -168,32 +942,242 @@ impl Application for MainState {
                 self.settings
                     .change(|ws| ws.joycon_scale_set(serial_number, scale));
             }
+            Message::JoyconYawScale(serial_number, scale) => {
+                self.settings
+                    .change(|ws| ws.joycon_yaw_scale_set(serial_number, scale));
+            }
+            Message::JoyconAlias(serial_number, alias) => {
+                self.settings
+                    .change(|ws| ws.joycon_alias_set(serial_number, alias));
+            }
+            Message::JoyconData(serial_number, data) => {
+                self.settings
+                    .change(|ws| ws.joycon_data_set(serial_number, data));
+            }
+            Message::JoyconLogRaw(serial_number, log_raw) => {
+                self.settings
+                    .change(|ws| ws.joycon_log_raw_set(serial_number, log_raw));
+            }
+            Message::JoyconGyroBias(serial_number, axis, bias) => {
+                self.settings
+                    .change(|ws| ws.joycon_gyro_bias_set(serial_number, axis, bias));
+            }
+            Message::CalibrationStarted(routine) => {
+                self.settings_show = false;
+                self.calibration = Some(calibration::Calibration::new(routine));
+            }
+            Message::CalibrationCancelled => {
+                self.calibration = None;
+            }
+            Message::RawValuesToggled(serial_number) => {
+                let shown = &mut self.joycon_boxes.raw_shown;
+                if !shown.remove(&serial_number) {
+                    shown.insert(serial_number);
+                }
+            }
             Message::SettingsResetToggled(new) => {
                 self.settings.change(|ws| ws.send_reset = new);
             }
             Message::SettingsIdsToggled(new) => {
                 self.settings.change(|ws| ws.keep_ids = new);
             }
+            Message::SettingsApiToggled(new) => {
+                self.settings.change(|ws| ws.api_enabled = new);
+            }
+            Message::SettingsWebsocketToggled(new) => {
+                self.settings.change(|ws| ws.websocket_enabled = new);
+            }
+            Message::SettingsMqttToggled(new) => {
+                self.settings.change(|ws| ws.mqtt_enabled = new);
+            }
+            Message::SettingsMocopiToggled(new) => {
+                self.settings.change(|ws| ws.mocopi_enabled = new);
+            }
+            Message::SettingsRelayToggled(new) => {
+                self.settings.change(|ws| ws.relay_enabled = new);
+            }
+            Message::SettingsOverlayToggled(new) => {
+                self.settings.change(|ws| ws.overlay_enabled = new);
+            }
+            Message::SettingsPrereleaseToggled(new) => {
+                self.settings.change(|ws| ws.prerelease_updates = new);
+                self.update_found = None;
+                return update_check(&self.settings.load());
+            }
+            Message::SettingsOfflineToggled(new) => {
+                self.settings.change(|ws| ws.offline = new);
+                self.update_found = None;
+                return update_check(&self.settings.load());
+            }
+            Message::SettingsAutoFixToggled(new) => {
+                self.settings.change(|ws| ws.blacklist_auto_fix = new);
+            }
+            Message::SettingsSteamVrPauseToggled(new) => {
+                self.settings.change(|ws| ws.steamvr_auto_pause = new);
+            }
+            Message::SettingsServerLostPauseChanged(minutes) => {
+                self.settings
+                    .change(|ws| ws.server_lost_pause_minutes = minutes);
+            }
+            Message::SettingsServerLostExitToggled(new) => {
+                self.settings.change(|ws| ws.server_lost_exit = new);
+            }
+            Message::WidgetToggled(widget_mode) => {
+                self.settings.change(|ws| ws.widget_mode = widget_mode);
+                let (width, height) = if widget_mode {
+                    WIDGET_WINDOW_SIZE
+                } else {
+                    WINDOW_SIZE
+                };
+                return Command::batch(vec![
+                    window::change_always_on_top(widget_mode),
+                    window::resize(width, height),
+                ]);
+            }
+            Message::YawResetPressed => {
+                if let Some(ref joycon) = self.joycon {
+                    joycon.remote().yaw_reset();
+                }
+            }
+            Message::BoxSizeSelected(box_size) => {
+                self.settings.change(|ws| ws.box_size = box_size);
+            }
+            Message::ColorThemeSelected(color_theme) => {
+                self.settings.change(|ws| ws.color_theme = color_theme);
+            }
+            Message::AccentColorChanged(color) => {
+                if style::parse_color(&color).is_some() {
+                    let saved = color.clone();
+                    self.settings.change(|ws| ws.accent_color = saved);
+                }
+                self.accent_input = color;
+            }
+            Message::AngleDisplaySelected(display) => {
+                self.settings.change(|ws| ws.angle_display = display);
+            }
+            Message::SettingsAlertsChanged(alerts) => {
+                self.settings.change(|ws| ws.alerts = alerts);
+            }
+            Message::SettingsWaitForServerToggled(new) => {
+                self.settings.change(|ws| ws.wait_for_server = new);
+            }
+            Message::SettingsSmoothOutputToggled(new) => {
+                self.settings
+                    .change(|ws| ws.output_rate = if new { SMOOTH_OUTPUT_RATE } else { 0 });
+            }
+            Message::SettingsHomeLightToggled(new) => {
+                self.settings.change(|ws| ws.home_light = new);
+            }
+            Message::SettingsFlipHandednessToggled(new) => {
+                self.settings.change(|ws| ws.flip_handedness = new);
+            }
+            Message::SettingsMaxVelocityChanged(new) => {
+                self.settings.change(|ws| ws.max_angular_velocity = new);
+            }
+            Message::SettingsHeartbeatChanged(new) => {
+                self.settings.change(|ws| ws.heartbeat_interval_ms = new);
+            }
+            Message::SettingsServerTimeoutChanged(new) => {
+                self.settings.change(|ws| ws.server_timeout_ms = new);
+            }
+            Message::OpentrackSelected(choice) => {
+                self.settings.change(|ws| {
+                    ws.opentrack_tracker = match choice {
+                        OpentrackChoice::Off => None,
+                        OpentrackChoice::Tracker { serial_number, .. } => Some(serial_number),
+                    };
+                });
+            }
+            Message::InterfaceSelected(choice) => {
+                self.settings.change(|ws| {
+                    ws.bind_address = match choice {
+                        InterfaceChoice::Automatic => None,
+                        InterfaceChoice::Interface(interface) => Some(interface.ip),
+                    };
+                });
+            }
+            Message::SettingsImuQueueChanged(new) => {
+                self.settings.change(|ws| ws.imu_queue_size = new);
+            }
         }
         Command::none()
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             time::every(Duration::from_millis(500)).map(Message::Dot),
-            time::every(Duration::from_millis(50)).map(Message::Tick),
-        ])
+            time::every(Duration::from_secs(5)).map(Message::BlacklistWatch),
+            time::every(Duration::from_secs(10)).map(Message::ConflictWatch),
+            time::every(Duration::from_secs(5)).map(Message::BodyPartsWatch),
+            time::every(Duration::from_secs(5)).map(Message::ProfilesWatch),
+            iced::subscription::events_with(|event, _status| match event {
+                iced::Event::Window(window::Event::Resized { width, .. }) => {
+                    Some(Message::WindowResized(width))
+                }
+                iced::Event::Window(window::Event::CloseRequested) => {
+                    Some(Message::WindowCloseRequested)
+                }
+                _ => None,
+            }),
+        ];
+        if let Some(ref ji) = self.joycon {
+            subscriptions.push(joycon_updates(ji.remote()));
+            subscriptions.push(joycon_events(ji.remote()));
+        }
+        if self.updater.is_some() || self.calibration.is_some() {
+            subscriptions.push(time::every(Duration::from_millis(50)).map(Message::Tick));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn view(&self) -> Element<Message> {
-        let mut app = Column::new().push(top_bar(self.update_found.clone()));
+        if self.settings.load().widget_mode {
+            return self.widget_screen().into();
+        }
+        let mut app = Column::new().push(top_bar(
+            self.update_found.as_ref(),
+            self.update_progress.as_ref(),
+            self.profile_picker(),
+        ));
 
+        // A controller showing up proves the check wrong, for example with a USB connection.
+        if let (Some(problem), true) = (
+            &self.bluetooth.problem,
+            self.joycon_boxes.statuses.is_empty(),
+        ) {
+            app = app.push(bluetooth_bar(problem));
+        }
+        if let Some((message, _)) = &self.toast {
+            app = app.push(toast_bar(message));
+        }
+        if let Some(conflict) = &self.conflict {
+            app = app.push(conflict_bar(conflict));
+        }
+        if !self.bluetooth.warnings.is_empty() {
+            app = app.push(adapter_warning_bar(&self.bluetooth.warnings));
+        }
         if self.blacklist_info.visible() {
             app = app.push(blacklist_bar(&self.blacklist_info));
         }
+        if self.switch_input_info.visible() {
+            app = app.push(switch_input_bar(&self.switch_input_info));
+        }
+        if let Some(replay) = &self.replay {
+            app = app.push(replay_bar(replay));
+        }
 
         app.push(
-            if self.settings_show {
+            if let (true, Some(info)) = (self.changelog_show, &self.update_found) {
+                container(changelog_screen(info))
+                    .padding(20)
+                    .center_x()
+                    .center_y()
+            } else if let Some(calibration) = &self.calibration {
+                container(calibration_screen(calibration, Instant::now()))
+                    .padding(20)
+                    .center_x()
+                    .center_y()
+            } else if self.settings_show {
                 container(self.settings_screen()).padding(20)
             } else {
                 container(self.joycon_screen())
@@ -206,58 +1190,765 @@ impl Application for MainState {
             self.server_connected,
             &".".repeat(self.search_dots),
             &self.server_address,
+            self.socket_error.as_deref(),
+            self.server_launch(),
+            &self.firewall,
+            session_stats(&self.joycon_boxes.statuses, self.packet_rate),
         ))
         .into()
     }
 }
 
-impl MainState {
-    fn joycon_screen(&self) -> Scrollable<'_, Message> {
-        let mut grid = Grid::with_column_width(320.0);
-        for bax in self.joycon_boxes.view(&self.settings.load()) {
-            grid.insert(container(bax).padding(10));
-        }
-        let list = Column::new().padding(10).width(Length::Fill).push(grid);
+impl MainState {
+    fn calibration_done(&mut self, outcome: calibration::Outcome) {
+        let message = match outcome {
+            calibration::Outcome::GyroBias {
+                serial_number,
+                name,
+                bias,
+            } => {
+                // A gyro scale before fusion is applied before the bias is subtracted.
+                let settings = self.settings.load();
+                let scale = if settings
+                    .joycon_pipeline_get(&serial_number)
+                    .samples
+                    .contains(&joycon::SampleStage::Scale)
+                {
+                    settings.joycon_scale_get(&serial_number)
+                } else {
+                    1.0
+                };
+                self.settings.change(|ws| {
+                    for (axis, bias) in bias.into_iter().enumerate() {
+                        ws.joycon_gyro_bias_set(serial_number.clone(), axis, bias * scale);
+                    }
+                });
+                format!("Gyro bias of {name} calibrated.")
+            }
+            calibration::Outcome::Mounting => {
+                if let Some(joycon) = &self.joycon {
+                    joycon.remote().mounting_reset();
+                }
+                "Mounting reset sent to SlimeVR Server.".into()
+            }
+            calibration::Outcome::Failed(e) => format!("Calibration failed: {e}"),
+        };
+        self.toast = Some((message, Instant::now()));
+    }
+    fn auto_profile(&mut self) {
+        let unclaimed: Vec<String> = self
+            .connected
+            .iter()
+            .filter(|serial_number| !self.claimed_elsewhere.contains(serial_number))
+            .cloned()
+            .collect();
+        self.switch_profile(settings::matching_profile(&self.profiles, &unclaimed));
+    }
+    fn switch_profile(&mut self, profile: Option<String>) {
+        if self.settings.load().profile == profile {
+            return;
+        }
+        match &profile {
+            Some(profile) => println!("[INFO] Switching to profile {profile}."),
+            None => println!("[INFO] Switching to the default profile."),
+        }
+        self.settings.switch_profile(profile.as_deref());
+        self.server_address = format!("{}", self.settings.load().get_socket_address());
+    }
+    /// Choices for the top bar, hidden while there are no profiles.
+    fn profile_picker(&self) -> Option<(Vec<ProfileChoice>, ProfileChoice)> {
+        if self.profile_names.is_empty() {
+            return None;
+        }
+        let choices = [ProfileChoice::Automatic, ProfileChoice::Default]
+            .into_iter()
+            .chain(self.profile_names.iter().cloned().map(ProfileChoice::Named))
+            .collect();
+        Some((choices, self.profile_choice.clone()))
+    }
+    /// Status dot, battery and a reset button for each tracker, in the small always on top
+    /// window.
+    fn widget_screen(&self) -> Container<'_, Message> {
+        let server = Row::new()
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .push(circle(
+                5.0,
+                if self.server_connected == ServerStatus::Connected {
+                    Color::from_rgb8(0x3d, 0xff, 0x81)
+                } else {
+                    Color::from_rgb8(0xff, 0xe3, 0x3c)
+                },
+            ))
+            .push(text(format!("Server: {}", self.server_connected)).size(14))
+            .push(horizontal_space(Length::Fill))
+            .push(
+                button(text("Expand").size(14))
+                    .padding([2, 8])
+                    .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                    .on_press(Message::WidgetToggled(false)),
+            );
+        let mut list = Column::new().spacing(5).padding(10).push(server);
+        for status in &self.joycon_boxes.statuses {
+            list = list.push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(circle(5.0, status_color(status.status)))
+                    .push(text(&status.name).size(14).width(Length::Fill))
+                    .push(battery_text(status.battery).padding([0, 5]))
+                    // SlimeVR resets every tracker at once, like the controller's own button.
+                    .push(
+                        button(text("Reset").size(14))
+                            .padding([2, 8])
+                            .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                            .on_press(Message::YawResetPressed),
+                    ),
+            );
+        }
+        container(scrollable(list))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(style::container_darker as for<'r> fn(&'r _) -> _)
+    }
+    fn joycon_screen(&self) -> Scrollable<'_, Message> {
+        let (min_width, _, _) = box_metrics(self.settings.load().box_size);
+        let (columns, box_width) = grid_layout(self.window_width, min_width);
+        let mut grid = Grid::with_columns(columns);
+        for bax in self.joycon_boxes.view(&self.settings.load(), box_width) {
+            grid.insert(container(bax).padding(BOX_PADDING));
+        }
+        let list = Column::new().padding(10).width(Length::Fill).push(grid);
+
+        let waiting = self
+            .joycon
+            .as_ref()
+            .map_or(false, |j| j.remote().waiting_for_server());
+        let list = list.push(
+            container(text(if waiting {
+                format!(
+                    "Waiting for SlimeVR Server{}\n\
+                        Controllers are connected once it is found.",
+                    ".".repeat(self.search_dots)
+                )
+            } else {
+                format!(
+                    "Searching for Joycon controllers{}\n\
+                        Please pair controllers in your system's \
+                        bluetooth settings if they don't show up here.",
+                    ".".repeat(self.search_dots)
+                )
+            }))
+            .padding(10),
+        );
+        scrollable(list).height(Length::Fill)
+    }
+    /// Launching only makes sense for a server on this machine that isn't running yet.
+    fn server_launch(&self) -> ServerLaunch {
+        let settings = self.settings.load();
+        if self.server_connected == ServerStatus::Connected
+            || !settings.get_socket_address().ip().is_loopback()
+        {
+            ServerLaunch::Hidden
+        } else if self.server_launched {
+            ServerLaunch::Launched
+        } else if server_launch::find_server(settings.server_path().as_deref()).is_some() {
+            ServerLaunch::Available
+        } else {
+            ServerLaunch::Hidden
+        }
+    }
+    fn recording_row(&self) -> Row<'_, Message> {
+        let recording = self.recording.is_some();
+        // The format can't change halfway through.
+        let formats: Element<_> = if recording {
+            text(self.recording_format.to_string()).into()
+        } else {
+            pick_list(
+                &recording::Format::ALL[..],
+                Some(self.recording_format),
+                Message::RecordingFormatSelected,
+            )
+            .padding(5)
+            .into()
+        };
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push("Record motion:")
+            .push(formats)
+            .push(
+                button(text(if recording {
+                    "Stop recording"
+                } else {
+                    "Start recording"
+                }))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::RecordingPressed),
+            )
+            .push(text(match (&self.recording_result, recording) {
+                (_, true) => "Recording...".to_owned(),
+                (None, false) => {
+                    "Saves the rotation of all trackers to the desktop, 60 times per second."
+                        .to_owned()
+                }
+                (Some(Ok(path)), false) => format!("Saved to {}", path.display()),
+                (Some(Err(e)), false) => format!("Could not save: {e}"),
+            }))
+    }
+
+    fn backup_row(&self) -> Row<'_, Message> {
+        let mut restore =
+            button(text("Restore")).style(theme::Button::Custom(Box::new(style::PrimaryButton)));
+        if self.backup_choice.is_some() {
+            restore = restore.on_press(Message::BackupRestorePressed);
+        }
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push("Restore settings from:")
+            .push(
+                pick_list(
+                    self.backups.clone(),
+                    self.backup_choice.clone(),
+                    Message::BackupSelected,
+                )
+                .placeholder("Backup")
+                .padding(5),
+            )
+            .push(restore)
+            .push(text(match &self.restore_result {
+                None if self.backups.is_empty() => {
+                    "No backups yet, they are made when the settings change.".to_owned()
+                }
+                None => "The current settings are backed up first.".to_owned(),
+                Some(Ok(())) => {
+                    "Restored. Restart Wrangler for the ports and connections to follow.".to_owned()
+                }
+                Some(Err(e)) => format!("Could not restore: {e}"),
+            }))
+    }
+
+    fn theme_row(&self) -> Row<'_, Message> {
+        let swatches = style::ACCENTS
+            .iter()
+            .fold(Row::new().spacing(5), |row, hex| {
+                let color = style::parse_color(hex).unwrap_or(Color::WHITE);
+                row.push(
+                    button(horizontal_space(Length::Fixed(10.0)))
+                        .style(theme::Button::Custom(Box::new(style::Swatch(color))))
+                        .on_press(Message::AccentColorChanged((*hex).to_owned())),
+                )
+            });
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push("Theme:")
+            .push(
+                pick_list(
+                    &settings::ColorTheme::ALL[..],
+                    Some(self.settings.load().color_theme),
+                    Message::ColorThemeSelected,
+                )
+                .padding(5),
+            )
+            .push(horizontal_space(Length::Fixed(20.0)))
+            .push("Tracker boxes:")
+            .push(
+                pick_list(
+                    &BoxSize::ALL[..],
+                    Some(self.settings.load().box_size),
+                    Message::BoxSizeSelected,
+                )
+                .padding(5),
+            )
+            .push(horizontal_space(Length::Fixed(20.0)))
+            .push("Accent color:")
+            .push(swatches)
+            .push(
+                text_input("#663499", &self.accent_input)
+                    .on_input(Message::AccentColorChanged)
+                    .width(Length::Fixed(100.0))
+                    .padding(5),
+            )
+    }
+
+    fn alerts_row(&self) -> Row<'_, Message> {
+        let alerts = self.settings.load().alerts;
+        Row::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push("Play a sound when:")
+            .push(checkbox(
+                "A tracker disconnects",
+                alerts.tracker_disconnect,
+                move |tracker_disconnect| {
+                    Message::SettingsAlertsChanged(settings::Alerts {
+                        tracker_disconnect,
+                        ..alerts
+                    })
+                },
+            ))
+            .push(checkbox(
+                "SlimeVR Server disconnects",
+                alerts.server_disconnect,
+                move |server_disconnect| {
+                    Message::SettingsAlertsChanged(settings::Alerts {
+                        server_disconnect,
+                        ..alerts
+                    })
+                },
+            ))
+            .push(checkbox(
+                "A battery gets critical",
+                alerts.battery_critical,
+                move |battery_critical| {
+                    Message::SettingsAlertsChanged(settings::Alerts {
+                        battery_critical,
+                        ..alerts
+                    })
+                },
+            ))
+    }
+
+    /// Empty in builds without the SteamVR overlay.
+    fn overlay_checkbox(&self) -> Column<'_, Message> {
+        let column = Column::new();
+        if !cfg!(feature = "overlay") {
+            return column;
+        }
+        column.push(checkbox(
+            "Show tracker status and battery inside the headset while SteamVR runs.",
+            self.settings.load().overlay_enabled,
+            Message::SettingsOverlayToggled,
+        ))
+    }
+
+    fn opentrack_picker(&self) -> Row<'_, Message> {
+        let settings = self.settings.load();
+        let choices: Vec<_> = std::iter::once(OpentrackChoice::Off)
+            .chain(
+                self.joycon_boxes
+                    .statuses
+                    .iter()
+                    .map(|s| OpentrackChoice::Tracker {
+                        serial_number: s.serial_number.clone(),
+                        name: s.name.clone(),
+                    }),
+            )
+            .collect();
+        // Keeps showing the pick while that controller is disconnected.
+        let selected = match &settings.opentrack_tracker {
+            None => OpentrackChoice::Off,
+            Some(serial_number) => choices
+                .iter()
+                .find(|c| c.serial_number() == Some(serial_number.as_str()))
+                .cloned()
+                .unwrap_or_else(|| OpentrackChoice::Tracker {
+                    serial_number: serial_number.clone(),
+                    name: serial_number.clone(),
+                }),
+        };
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push("Send to opentrack:")
+            .push(pick_list(choices, Some(selected), Message::OpentrackSelected).padding(5))
+            .push(text(format!(
+                "Head tracking for sim games, to opentrack's \"UDP over network\" input on {}.",
+                settings.opentrack_address
+            )))
+    }
+
+    fn interface_picker(&self) -> Row<'_, Message> {
+        let bind_address = self.settings.load().bind_address;
+        let choices: Vec<_> = std::iter::once(InterfaceChoice::Automatic)
+            .chain(
+                self.interfaces
+                    .iter()
+                    .cloned()
+                    .map(InterfaceChoice::Interface),
+            )
+            .collect();
+        let find = |ip| self.interfaces.iter().find(|i| i.ip == ip);
+        // An interface that is gone keeps showing by its address.
+        let selected = match bind_address {
+            None => InterfaceChoice::Automatic,
+            Some(ip) => InterfaceChoice::Interface(find(ip).cloned().unwrap_or_else(|| {
+                interfaces::Interface {
+                    name: "Not found".into(),
+                    ip,
+                }
+            })),
+        };
+        let in_use = match bind_address.or(self.server_route) {
+            Some(ip) => match find(ip) {
+                Some(interface) => format!("Sending from {interface}."),
+                None => format!("Sending from {ip}."),
+            },
+            None => "No route to the server address.".to_owned(),
+        };
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push("Network interface:")
+            .push(pick_list(choices, Some(selected), Message::InterfaceSelected).padding(5))
+            .push(text(format!(
+                "{in_use} Pick one if a VPN or virtual adapter takes the traffic. Restart Wrangler after changing this."
+            )))
+    }
+
+    fn settings_screen(&self) -> Column<'_, Message> {
+        Column::new()
+            .spacing(20)
+            .push(address(
+                &self.settings.load().address,
+                &self.settings.load().address_history,
+            ))
+            .push(self.interface_picker())
+            .push(steam_path(
+                self.settings.load().steam_path.as_deref().unwrap_or_default(),
+            ))
+            .push(server_path(
+                self.settings.load().server_path.as_deref().unwrap_or_default(),
+            ))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        button(text("Undo blacklist fix"))
+                            .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                            .on_press(Message::BlacklistUndoPressed),
+                    )
+                    .push("Removes only the entries Wrangler added, so Steam can use those controllers again."),
+            )
+            .push(checkbox(
+                "Fix the Steam controller blacklist automatically when Steam resets it, once Steam is closed.",
+                self.settings.load().blacklist_auto_fix,
+                Message::SettingsAutoFixToggled,
+            ))
+            .push(checkbox(
+                "Pause sending to SlimeVR Server while SteamVR isn't running, to save battery.",
+                self.settings.load().steamvr_auto_pause,
+                Message::SettingsSteamVrPauseToggled,
+            ))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Pause when SlimeVR Server has been gone for:")
+                    .push(
+                        slider(
+                            0..=120,
+                            self.settings.load().server_lost_pause_minutes,
+                            Message::SettingsServerLostPauseChanged,
+                        )
+                        .step(5)
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(text(match self.settings.load().server_lost_pause_minutes {
+                        0 => "Never".to_owned(),
+                        m => format!("{m} min"),
+                    }))
+                    .push(checkbox(
+                        "Quit Wrangler instead",
+                        self.settings.load().server_lost_exit,
+                        Message::SettingsServerLostExitToggled,
+                    )),
+            )
+            .push(self.alerts_row())
+            .push(self.theme_row())
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Show tracker rotation as:")
+                    .push(
+                        pick_list(
+                            &AngleDisplay::ALL[..],
+                            Some(self.settings.load().angle_display),
+                            Message::AngleDisplaySelected,
+                        )
+                        .padding(5),
+                    )
+                    .push("Radians and quaternions help comparing with the server's values."),
+            )
+            .push(checkbox(
+                "Wait for SlimeVR Server before connecting controllers, for starting Wrangler with the computer. Restart Wrangler after changing this.",
+                self.settings.load().wait_for_server,
+                Message::SettingsWaitForServerToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Smooth output: send rotations at a steady {SMOOTH_OUTPUT_RATE} Hz. Adds about 15 ms of latency. Restart Wrangler after changing this."
+                ),
+                self.settings.load().output_rate > 0,
+                Message::SettingsSmoothOutputToggled,
+            ))
+            .push(checkbox(
+                "Show status on the HOME button light: dim when tracking, blinking when not connected to SlimeVR Server, fast blinking on low battery.",
+                self.settings.load().home_light,
+                Message::SettingsHomeLightToggled,
+            ))
+            .push(checkbox(
+                "Flip handedness: mirror the rotation of all trackers. Can be changed per controller in the config file.",
+                self.settings.load().flip_handedness,
+                Message::SettingsFlipHandednessToggled,
+            ))
+            .push(checkbox(
+                "Send yaw reset command to SlimeVR Server after B or UP button press.",
+                self.settings.load().send_reset,
+                Message::SettingsResetToggled,
+            ))
+            .push(checkbox(
+                "Save mounting location on server. Requires SlimeVR Server v0.6.1 or newer. Restart Wrangler after changing this.",
+                self.settings.load().keep_ids,
+                Message::SettingsIdsToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Enable local control API on http://127.0.0.1:{}. Restart Wrangler after changing this.",
+                    self.settings.load().api_port
+                ),
+                self.settings.load().api_enabled,
+                Message::SettingsApiToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Enable live status WebSocket on ws://127.0.0.1:{}. Restart Wrangler after changing this.",
+                    self.settings.load().websocket_port
+                ),
+                self.settings.load().websocket_enabled,
+                Message::SettingsWebsocketToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Publish tracker status and battery to the MQTT broker on {}. Restart Wrangler after changing this.",
+                    self.settings.load().mqtt_broker
+                ),
+                self.settings.load().mqtt_enabled,
+                Message::SettingsMqttToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Relay trackers from other computers: pass on what Wrangler on another computer sends to port {} to SlimeVR Server. Restart Wrangler after changing this.",
+                    self.settings.load().relay_port
+                ),
+                self.settings.load().relay_enabled,
+                Message::SettingsRelayToggled,
+            ))
+            .push(checkbox(
+                format!(
+                    "Receive Sony mocopi trackers: in the mocopi app, send to this PC's IP address on port {}. Restart Wrangler after changing this.",
+                    self.settings.load().mocopi_port
+                ),
+                self.settings.load().mocopi_enabled,
+                Message::SettingsMocopiToggled,
+            ))
+            .push(checkbox(
+                "Show pre-release (beta) updates. These may be less stable.",
+                self.settings.load().prerelease_updates,
+                Message::SettingsPrereleaseToggled,
+            ))
+            .push(checkbox(
+                "Offline: only send to SlimeVR Server, without checking for updates, connecting to MQTT or sending to opentrack. Restart Wrangler for MQTT to follow this.",
+                self.settings.load().offline,
+                Message::SettingsOfflineToggled,
+            ))
+            .push(self.overlay_checkbox())
+            .push(self.opentrack_picker())
+            .push(self.recording_row())
+            .push(self.backup_row())
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        button(text("Export diagnostics"))
+                            .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                            .on_press(Message::ExportPressed),
+                    )
+                    .push(checkbox(
+                        "Leave out the server and MAC address",
+                        self.export_redact,
+                        Message::ExportRedactToggled,
+                    ))
+                    .push(text(match &self.export_result {
+                        None => "Saves a zip to attach to GitHub issues.".to_owned(),
+                        Some(Ok(path)) => format!("Saved to {}", path.display()),
+                        Some(Err(e)) => format!("Could not save: {e}"),
+                    })),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Advanced: IMU reports buffered per controller:")
+                    .push(
+                        slider(
+                            1..=32,
+                            self.settings.load().imu_queue_size,
+                            Message::SettingsImuQueueChanged,
+                        )
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(text(self.settings.load().imu_queue_size.to_string()))
+                    .push(
+                        "Lower means less lag after a Bluetooth hiccup, higher drops fewer reports. \
+                        Restart Wrangler after changing this.",
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Advanced: Fastest allowed tracker rotation:")
+                    .push(
+                        slider(
+                            0.0..=5000.0,
+                            self.settings.load().max_angular_velocity,
+                            Message::SettingsMaxVelocityChanged,
+                        )
+                        .step(100.0)
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(text(match self.settings.load().max_angular_velocity {
+                        v if v <= 0.0 => "No limit".to_owned(),
+                        v => format!("{v:.0} °/s"),
+                    }))
+                    .push("Stops a corrupted report from snapping a tracker around."),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Advanced: Heartbeat to SlimeVR Server every:")
+                    .push(
+                        slider(
+                            500..=10000,
+                            self.settings.load().heartbeat_interval_ms,
+                            Message::SettingsHeartbeatChanged,
+                        )
+                        .step(500)
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(text(format!(
+                        "{} ms",
+                        self.settings.load().heartbeat_interval_ms
+                    ))),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push("Advanced: Reconnect after no ping from SlimeVR Server for:")
+                    .push(
+                        slider(
+                            1000..=30000,
+                            self.settings.load().server_timeout_ms,
+                            Message::SettingsServerTimeoutChanged,
+                        )
+                        .step(1000)
+                        .width(Length::Fixed(200.0)),
+                    )
+                    .push(text(format!(
+                        "{} ms",
+                        self.settings.load().server_timeout_ms
+                    ))),
+            )
+    }
+}
+
+/// Looks for a new release on GitHub, unless offline.
+fn update_check(settings: &WranglerSettings) -> Command<Message> {
+    if settings.offline {
+        return Command::none();
+    }
+    Command::perform(
+        update::check_updates(settings.prerelease_updates),
+        Message::UpdateFound,
+    )
+}
+
+/// Sends the tracker snapshot to the GUI whenever the communication thread changes it.
+fn joycon_updates(remote: joycon::Remote) -> Subscription<Message> {
+    iced::subscription::unfold(
+        "joycon-updates",
+        remote.updates(),
+        |mut updates| async move {
+            if updates.changed().await.is_err() {
+                // The communication thread is gone, nothing will change anymore.
+                return std::future::pending().await;
+            }
+            // Rendering faster than the screen refreshes is wasted work.
+            tokio::time::sleep(Duration::from_millis(16)).await;
+            let snapshot = updates.borrow_and_update().clone();
+            (Some(Message::JoyconUpdate(snapshot)), updates)
+        },
+    )
+}
 
-        let list = list.push(
-            container(text(format!(
-                "Searching for Joycon controllers{}\n\
-                    Please pair controllers in your system's \
-                    bluetooth settings if they don't show up here.",
-                ".".repeat(self.search_dots)
-            )))
-            .padding(10),
-        );
-        scrollable(list).height(Length::Fill)
-    }
-    fn settings_screen(&self) -> Column<'_, Message> {
-        Column::new()
-            .spacing(20)
-            .push(address(&self.settings.load().address))
-            .push(checkbox(
-                "Send yaw reset command to SlimeVR Server after B or UP button press.",
-                self.settings.load().send_reset,
-                Message::SettingsResetToggled,
-            ))
-            .push(checkbox(
-                "Save mounting location on server. Requires SlimeVR Server v0.6.1 or newer. Restart Wrangler after changing this.",
-                self.settings.load().keep_ids,
-                Message::SettingsIdsToggled,
-            ))
-    }
+/// Server and socket state from the event bus. Starts, and starts over after falling behind,
+/// with the state in the snapshot, as the changes before subscribing were never seen.
+fn joycon_events(remote: joycon::Remote) -> Subscription<Message> {
+    iced::subscription::unfold(
+        "joycon-events",
+        (remote, None, vec![]),
+        |(remote, events, mut pending)| async move {
+            let mut events = events.unwrap_or_else(|| {
+                let events = remote.events().subscribe();
+                let snapshot = remote.snapshot();
+                pending = vec![
+                    Event::ServerChanged(snapshot.server),
+                    Event::SocketError(snapshot.socket_error.clone()),
+                ];
+                events
+            });
+            if let Some(event) = pending.pop() {
+                return (
+                    Some(Message::JoyconEvent(event)),
+                    (remote, Some(events), pending),
+                );
+            }
+            match events.recv().await {
+                Ok(event) => (
+                    Some(Message::JoyconEvent(event)),
+                    (remote, Some(events), pending),
+                ),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                    (None, (remote, None, pending))
+                }
+                // Nothing will be published anymore.
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    std::future::pending().await
+                }
+            }
+        },
+    )
 }
 
-fn address<'a>(input_value: &str) -> Column<'a, Message> {
+fn address<'a>(input_value: &str, history: &[String]) -> Column<'a, Message> {
     let address = text_input("127.0.0.1:6969", input_value)
         .on_input(Message::AddressChange)
         .width(Length::Fixed(300.0))
         .padding(10);
+    // For switching between servers, like a desktop and a PC streaming to a standalone headset.
+    let recent: Vec<String> = history
+        .iter()
+        .filter(|a| a.as_str() != input_value)
+        .cloned()
+        .collect();
+    let recent = pick_list(recent, None, Message::AddressChange)
+        .placeholder("Recent")
+        .padding(10);
 
     let address_row = Row::new()
         .spacing(10)
         .align_items(Alignment::Center)
         .push("SlimeVR Server address:")
         .push(address)
+        .push(recent)
         .push("Restart Wrangler after changing this.");
     let mut allc = Column::new().push(address_row).spacing(10);
 
@@ -271,26 +1962,102 @@ fn address<'a>(input_value: &str) -> Column<'a, Message> {
     }
     allc
 }
-fn top_bar<'a>(update: Option<String>) -> Container<'a, Message> {
+fn steam_path<'a>(input_value: &str) -> Row<'a, Message> {
+    let path = text_input("Detect automatically", input_value)
+        .on_input(Message::SteamPathChange)
+        .on_submit(Message::SteamPathSubmitted)
+        .width(Length::Fixed(300.0))
+        .padding(10);
+
+    Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push("Steam install folder:")
+        .push(path)
+        .push("Only needed if Steam is installed in an unusual place. Press enter to check.")
+}
+fn server_path<'a>(input_value: &str) -> Row<'a, Message> {
+    let path = text_input("Detect automatically", input_value)
+        .on_input(Message::ServerPathChange)
+        .width(Length::Fixed(300.0))
+        .padding(10);
+
+    Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push("SlimeVR Server install folder:")
+        .push(path)
+        .push("Used by the \"Launch SlimeVR Server\" button.")
+}
+fn top_bar<'a>(
+    update: Option<&update::UpdateInfo>,
+    progress: Option<&update::Progress>,
+    profiles: Option<(Vec<ProfileChoice>, ProfileChoice)>,
+) -> Container<'a, Message> {
     let mut top_column = Row::new()
         .align_items(Alignment::Center)
         .push(text("SlimeVR Wrangler").size(24));
 
     if let Some(u) = update {
-        let update_btn = button(text("Update"))
+        let mut update_btn =
+            button(text("Update")).style(theme::Button::Custom(Box::new(style::PrimaryButton)));
+        let checksum = if u.checksum {
+            update_btn = update_btn.on_press(Message::UpdatePressed);
+            container(text("Checksum published, verified before installing. "))
+                .style(style::text_green as for<'r> fn(&'r _) -> _)
+        } else {
+            container(text("No checksum published, download it from GitHub. "))
+                .style(style::text_orange as for<'r> fn(&'r _) -> _)
+        };
+        let skip_btn = button(text("Skip this version"))
             .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
-            .on_press(Message::UpdatePressed);
+            .on_press(Message::UpdateSkipped);
+        let snooze_btn = button(text("Remind me later"))
+            .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+            .on_press(Message::UpdateSnoozed);
+        top_column = top_column
+            .push(horizontal_space(Length::Fixed(20.0)))
+            .push(text(format!("New update found! Version: {}. ", u.version)))
+            .push(checksum)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(update_btn)
+                    .push(snooze_btn)
+                    .push(skip_btn),
+            );
+    }
+    if let Some(progress) = progress {
+        let status = container(text(progress.to_string())).style(match progress {
+            update::Progress::Failed(_) => style::text_orange,
+            _ => style::text_green,
+        });
         top_column = top_column
             .push(horizontal_space(Length::Fixed(20.0)))
-            .push(text(format!("New update found! Version: {u}. ")))
-            .push(update_btn);
+            .push(status);
     }
 
     let settings = button(text("Settings"))
         .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
         .on_press(Message::SettingsPressed);
+    let widget = button(text("Widget"))
+        .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+        .on_press(Message::WidgetToggled(true));
+    let mounting = button(text("Mounting reset"))
+        .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+        .on_press(Message::CalibrationStarted(calibration::Routine::Mounting));
+    top_column = top_column.push(horizontal_space(Length::Fill));
+    if let Some((choices, selected)) = profiles {
+        top_column = top_column
+            .push(text("Profile: "))
+            .push(pick_list(choices, Some(selected), Message::ProfileSelected).padding(5))
+            .push(horizontal_space(Length::Fixed(20.0)));
+    }
     top_column = top_column
-        .push(horizontal_space(Length::Fill))
+        .push(mounting)
+        .push(horizontal_space(Length::Fixed(10.0)))
+        .push(widget)
+        .push(horizontal_space(Length::Fixed(10.0)))
         .push(settings);
 
     container(top_column)
@@ -299,6 +2066,141 @@ fn top_bar<'a>(update: Option<String>) -> Container<'a, Message> {
         .style(style::container_highlight as for<'r> fn(&'r _) -> _)
 }
 
+/// Countdown, progress and a cancel button for a running calibration, in place of the boxes.
+fn calibration_screen(
+    calibration: &calibration::Calibration,
+    now: Instant,
+) -> Container<'_, Message> {
+    let mut dialog = Column::new()
+        .spacing(15)
+        .align_items(Alignment::Center)
+        .push(text(calibration.title()).size(24))
+        .push(text(calibration.instructions()));
+    if let Some(left) = calibration.countdown(now) {
+        dialog = dialog.push(text(format!("Starting in {left}")).size(40));
+    }
+    dialog = dialog.push(
+        progress_bar(0.0..=1.0, calibration.progress(now))
+            .height(Length::Fixed(10.0))
+            .width(Length::Fixed(400.0)),
+    );
+    if calibration.motion_detected(now) {
+        dialog = dialog.push(
+            container(text(
+                "Movement detected, measuring again. Keep the controller still.",
+            ))
+            .style(style::text_orange as for<'r> fn(&'r _) -> _),
+        );
+    }
+    dialog = dialog.push(
+        button(text("Cancel"))
+            .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+            .on_press(Message::CalibrationCancelled),
+    );
+    container(dialog)
+        .padding(20)
+        .style(style::item_normal as for<'r> fn(&'r _) -> _)
+}
+
+fn changelog_screen(info: &update::UpdateInfo) -> Container<'_, Message> {
+    let mut notes = Column::new().spacing(5);
+    for line in info.notes.lines() {
+        let heading = line.trim_start_matches('#');
+        notes = notes.push(if heading.len() != line.len() {
+            text(heading.trim()).size(22)
+        } else {
+            text(line)
+        });
+    }
+
+    let mut dialog = Column::new()
+        .spacing(15)
+        .push(text(format!("What's new in version {}", info.version)).size(24));
+    if let Some(server) = info.required_server() {
+        dialog = dialog.push(
+            container(text(format!(
+                "This update requires SlimeVR Server {server} or newer. \
+                Update your server too."
+            )))
+            .style(style::text_yellow as for<'r> fn(&'r _) -> _),
+        );
+    }
+    let buttons = Row::new()
+        .spacing(10)
+        .push(horizontal_space(Length::Fill))
+        .push(
+            button(text("Cancel"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::UpdateCancelled),
+        )
+        .push(
+            button(text("Update now"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::UpdateConfirmed),
+        );
+    dialog = dialog
+        .push(scrollable(notes).height(Length::Fill))
+        .push(buttons);
+
+    container(dialog)
+        .width(Length::Fixed(600.0))
+        .padding(20)
+        .style(style::item_normal as for<'r> fn(&'r _) -> _)
+}
+
+fn bluetooth_bar<'a>(problem: &bluetooth::Problem) -> Container<'a, Message> {
+    let row = Row::new()
+        .align_items(Alignment::Center)
+        .push(
+            container(text(problem.message())).style(style::text_orange as for<'r> fn(&'r _) -> _),
+        )
+        .push(horizontal_space(Length::Fixed(20.0)))
+        .push(
+            button(text("Check again"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::BluetoothRetryPressed),
+        );
+    container(row)
+        .width(Length::Fill)
+        .padding(20)
+        .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
+fn toast_bar(message: &str) -> Container<'_, Message> {
+    container(container(text(message)).style(style::text_green as for<'r> fn(&'r _) -> _))
+        .width(Length::Fill)
+        .padding(20)
+        .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
+fn conflict_bar<'a>(conflict: &conflicts::Conflict) -> Container<'a, Message> {
+    container(
+        container(text(conflict.message())).style(style::text_orange as for<'r> fn(&'r _) -> _),
+    )
+    .width(Length::Fill)
+    .padding(20)
+    .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
+fn adapter_warning_bar<'a>(warnings: &[String]) -> Container<'a, Message> {
+    let row = Row::new()
+        .align_items(Alignment::Center)
+        .push(
+            container(text(warnings.join("\n")))
+                .style(style::text_yellow as for<'r> fn(&'r _) -> _),
+        )
+        .push(horizontal_space(Length::Fixed(20.0)))
+        .push(
+            button(text("Dismiss"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::BluetoothWarningDismissed),
+        );
+    container(row)
+        .width(Length::Fill)
+        .padding(20)
+        .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
 fn blacklist_bar<'a>(result: &blacklist::BlacklistResult) -> Container<'a, Message> {
     let mut row = Row::new()
         .align_items(Alignment::Center)
@@ -311,6 +2213,72 @@ fn blacklist_bar<'a>(result: &blacklist::BlacklistResult) -> Container<'a, Messa
                 .on_press(Message::BlacklistFixPressed),
         );
     }
+    if result.steam_running {
+        row = row.push(
+            button(text("Fix when Steam closes"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::BlacklistFixWhenClosed),
+        );
+    }
+    container(row)
+        .width(Length::Fill)
+        .padding(20)
+        .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
+fn switch_input_bar<'a>(result: &blacklist::BlacklistResult) -> Container<'a, Message> {
+    let mut row = Row::new()
+        .align_items(Alignment::Center)
+        .push(text(result.info.clone()))
+        .push(horizontal_space(Length::Fixed(20.0)));
+    if result.fix_button {
+        row = row.push(
+            button(text("Turn off"))
+                .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                .on_press(Message::SwitchInputFixPressed),
+        );
+    }
+    container(row)
+        .width(Length::Fill)
+        .padding(20)
+        .style(style::container_info as for<'r> fn(&'r _) -> _)
+}
+
+/// Speed, looping and seeking for going over a clip of motion again while tuning.
+fn replay_bar<'a>(replay: &joycon::Playback) -> Container<'a, Message> {
+    let speed = replay.speed();
+    let row = Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push(text("Replay:"))
+        .push(
+            slider(
+                0.0..=replay.duration(),
+                replay.position(),
+                Message::ReplaySeeked,
+            )
+            .step(0.1)
+            .width(Length::Fixed(300.0)),
+        )
+        .push(text(format!(
+            "{:.1} / {:.1} s",
+            replay.position(),
+            replay.duration()
+        )))
+        .push(horizontal_space(Length::Fixed(20.0)))
+        .push(text("Speed:"))
+        .push(
+            slider(0.1..=4.0, speed, Message::ReplaySpeedChanged)
+                .step(0.05)
+                .width(Length::Fixed(150.0)),
+        )
+        .push(text(format!("{speed:.2}x")))
+        .push(horizontal_space(Length::Fixed(20.0)))
+        .push(checkbox(
+            "Loop",
+            replay.looping(),
+            Message::ReplayLoopToggled,
+        ));
     container(row)
         .width(Length::Fill)
         .padding(20)
@@ -321,10 +2289,15 @@ fn bottom_bar<'a>(
     connected: ServerStatus,
     search_dots: &String,
     address: &String,
+    socket_error: Option<&str>,
+    launch: ServerLaunch,
+    firewall: &firewall::FirewallStatus,
+    stats: Option<String>,
 ) -> Container<'a, Message> {
-    let status = Row::new()
+    let mut status = Row::new()
+        .align_items(Alignment::Center)
         .push(text("Connection to SlimeVR Server: "))
-        .push(container(text(format!("{connected:?}"))).style(
+        .push(container(text(connected.to_string())).style(
             if connected == ServerStatus::Connected {
                 style::text_green
             } else {
@@ -336,43 +2309,149 @@ fn bottom_bar<'a>(
         } else {
             format!(". Trying to connect to {address}{search_dots}")
         }));
+    // Says more than endlessly trying to connect, sends keep being tried in the background.
+    if let Some(error) = socket_error {
+        status = Row::new()
+            .align_items(Alignment::Center)
+            .push(text("Connection to SlimeVR Server: "))
+            .push(container(text(error)).style(style::text_orange as for<'r> fn(&'r _) -> _))
+            .push(horizontal_space(Length::Fixed(10.0)))
+            .push(
+                button(text("Retry"))
+                    .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                    .on_press(Message::SocketRetryPressed),
+            );
+    }
+    if let firewall::FirewallStatus::Blocked(_) = firewall {
+        status = status
+            .push(horizontal_space(Length::Fixed(20.0)))
+            .push(
+                container(text("Windows Firewall is blocking Wrangler."))
+                    .style(style::text_orange as for<'r> fn(&'r _) -> _),
+            )
+            .push(horizontal_space(Length::Fixed(10.0)))
+            .push(
+                button(text("Allow in firewall"))
+                    .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                    .on_press(Message::FirewallFixPressed),
+            );
+    }
+    if let Some(stats) = stats {
+        status = status
+            .push(horizontal_space(Length::Fixed(20.0)))
+            .push(text(stats));
+    }
+    match launch {
+        ServerLaunch::Hidden => {}
+        ServerLaunch::Available => {
+            status = status.push(horizontal_space(Length::Fill)).push(
+                button(text("Launch SlimeVR Server"))
+                    .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+                    .on_press(Message::ServerLaunchPressed),
+            );
+        }
+        ServerLaunch::Launched => {
+            status = status
+                .push(horizontal_space(Length::Fill))
+                .push(text("Starting SlimeVR Server..."));
+        }
+    }
     container(status)
         .width(Length::Fill)
         .padding(20)
         .style(style::container_info as for<'r> fn(&'r _) -> _)
 }
 
+/// Tracker count, packet rate and lowest battery, so the whole session can be checked at once.
+fn session_stats(statuses: &[joycon::Status], packet_rate: u32) -> Option<String> {
+    let connected: Vec<_> = statuses
+        .iter()
+        .filter(|s| s.status != DeviceStatus::Disconnected)
+        .collect();
+    let lowest = connected.iter().map(|s| s.battery).min()?;
+    Some(format!(
+        "{} tracker{}, {packet_rate} packets/s, lowest battery {lowest:?}",
+        connected.len(),
+        if connected.len() == 1 { "" } else { "s" },
+    ))
+}
+
+/// As many columns as fit, with the boxes stretched to use the leftover space. Below one
+/// column, the single box shrinks with the window.
+fn grid_layout(window_width: u32, min_width: f32) -> (usize, f32) {
+    // The list padding, and room for the scrollbar.
+    let available = window_width as f32 - 2.0 * BOX_PADDING - 10.0;
+    let cell = min_width + 2.0 * BOX_PADDING;
+    let columns = ((available / cell) as usize).max(1);
+    let box_width = (available / columns as f32 - 2.0 * BOX_PADDING).max(200.0);
+    (columns, box_width)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ServerLaunch {
+    Hidden,
+    Available,
+    Launched,
+}
+
 #[derive(Debug)]
 struct JoyconBoxes {
     pub statuses: Vec<joycon::Status>,
-    svg_handler: joycon::Svg,
-    needles: Vec<Needle>,
+    svg_handler: svg::Svg,
+    histories: HashMap<String, YawHistory>,
+    /// Body parts assigned in SlimeVR Server by tracker id, if its config could be read.
+    body_parts: Option<HashMap<u8, String>>,
+    /// Serial numbers of the boxes with the raw values open.
+    raw_shown: HashSet<String>,
 }
 
 impl Default for JoyconBoxes {
     fn default() -> Self {
         Self {
             statuses: vec![],
-            svg_handler: joycon::Svg::new(),
-            needles: (0..360).map(Needle::new).collect(),
+            svg_handler: svg::Svg::new(),
+            histories: HashMap::new(),
+            body_parts: None,
+            raw_shown: HashSet::new(),
         }
     }
 }
 
 impl JoyconBoxes {
-    fn view<'a>(&'a self, settings: &WranglerSettings) -> Vec<Container<'a, Message>> {
+    fn update(&mut self, statuses: &[joycon::Status]) {
+        let now = Instant::now();
+        self.histories
+            .retain(|sn, _| statuses.iter().any(|s| &s.serial_number == sn));
+        for status in statuses {
+            self.histories
+                .entry(status.serial_number.clone())
+                .or_default()
+                .push(now, -status.rotation.2);
+        }
+        self.statuses = statuses.to_vec();
+    }
+    fn view<'a>(&'a self, settings: &WranglerSettings, width: f32) -> Vec<Container<'a, Message>> {
         self.statuses
             .iter()
             .map(|status| {
+                let raw_shown = self.raw_shown.contains(&status.serial_number);
+                let height = box_metrics(settings.box_size).1;
                 container(single_box_view(
                     status,
                     &self.svg_handler,
-                    &self.needles,
-                    settings.joycon_scale_get(&status.serial_number),
-                    settings.joycon_rotation_get(&status.serial_number),
+                    self.histories.get(&status.serial_number),
+                    settings,
+                    self.body_parts
+                        .as_ref()
+                        .map(|parts| parts.get(&status.tracker_id)),
+                    raw_shown,
                 ))
-                .height(Length::Fixed(335.0))
-                .width(Length::Fixed(300.0))
+                .height(Length::Fixed(if raw_shown {
+                    height + RAW_VALUES_HEIGHT
+                } else {
+                    height
+                }))
+                .width(Length::Fixed(width))
                 .padding(10)
                 .style(style::item_normal as for<'r> fn(&'r _) -> _)
             })
@@ -380,14 +2459,154 @@ impl JoyconBoxes {
     }
 }
 
+fn duration_text(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+/// Helps telling whether moving the Bluetooth adapter made the connection more reliable.
+fn uptime_text(status: &joycon::Status) -> String {
+    let Some(uptime) = status.uptime else {
+        return "Not connected".into();
+    };
+    match status.since_stall {
+        Some(since) => format!(
+            "Connected for {}, last stall {} ago",
+            duration_text(uptime),
+            duration_text(since)
+        ),
+        None => format!("Connected for {}, no stalls", duration_text(uptime)),
+    }
+}
+
+/// Catches a tracker that was never assigned before putting on the headset. Shows nothing when
+/// the server's config couldn't be read.
+fn body_part_text<'a>(body_part: Option<Option<&String>>) -> Row<'a, Message> {
+    let row = Row::new().push(text("Body part: ").size(14));
+    match body_part {
+        None => Row::new(),
+        Some(Some(part)) => row.push(
+            container(text(part).size(14)).style(style::text_green as for<'r> fn(&'r _) -> _),
+        ),
+        Some(None) => row.push(
+            container(text("Not assigned in SlimeVR Server").size(14))
+                .style(style::text_orange as for<'r> fn(&'r _) -> _),
+        ),
+    }
+}
+
+fn sticks_text(sticks: &joycon::Sticks) -> String {
+    [sticks.left, sticks.right]
+        .into_iter()
+        .flatten()
+        .map(|s| format!("{:+.2} {:+.2}", s.x, s.y))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// A slider per controller axis for a gyro bias that the automatic calibration
+/// misses, with the drift left after it as a preview.
+fn gyro_bias_row<'a>(status: &joycon::Status, bias: [f64; 3]) -> Column<'a, Message> {
+    let sliders = bias
+        .iter()
+        .enumerate()
+        .fold(Row::new().spacing(5), |row, (axis, &value)| {
+            let sn = status.serial_number.clone();
+            row.push(
+                slider(-1.0..=1.0, value, move |v| {
+                    Message::JoyconGyroBias(sn.clone(), axis, v)
+                })
+                .step(0.01),
+            )
+        });
+    let (x, y, z) = status.gyro_average;
+    let calibrate = button(text("Calibrate").size(14))
+        .padding([2, 8])
+        .style(theme::Button::Custom(Box::new(style::PrimaryButton)))
+        .on_press(Message::CalibrationStarted(
+            calibration::Routine::GyroBias {
+                serial_number: status.serial_number.clone(),
+                name: status.name.clone(),
+            },
+        ));
+    Column::new()
+        .spacing(5)
+        .push(sliders.push(calibrate))
+        .push(
+            text(format!(
+                "Gyro bias: {:.2}, {:.2}, {:.2} °/s. Drift while lying still: {x:.2}, {y:.2}, {z:.2} °/s",
+                bias[0], bias[1], bias[2]
+            ))
+            .size(14),
+        )
+}
+
+/// Room the raw values take in a box when open.
+const RAW_VALUES_HEIGHT: f32 = 45.0;
+
+/// The last gyro and accelerometer reading as sent, behind a toggle. An axis stuck at the same
+/// value or at 0 while moving the controller is a broken sensor.
+fn raw_values<'a>(status: &joycon::Status, shown: bool) -> Column<'a, Message> {
+    let toggle = button(
+        text(if shown {
+            "Hide raw values"
+        } else {
+            "Show raw values"
+        })
+        .size(14),
+    )
+    .on_press(Message::RawValuesToggled(status.serial_number.clone()))
+    .padding(0)
+    .style(theme::Button::Text);
+    if !shown {
+        return Column::new().push(toggle);
+    }
+    let (gx, gy, gz) = status.raw_gyro;
+    let (ax, ay, az) = status.raw_accel;
+    Column::new()
+        .spacing(5)
+        .push(toggle)
+        .push(text(format!("Gyro: {gx:8.2} {gy:8.2} {gz:8.2} °/s")).size(14))
+        .push(text(format!("Accel: {ax:6.3} {ay:6.3} {az:6.3} G")).size(14))
+}
+
+fn status_color(status: DeviceStatus) -> Color {
+    match status {
+        DeviceStatus::Disconnected
+        | DeviceStatus::NoIMU
+        | DeviceStatus::SetupFailed
+        | DeviceStatus::ButtonsOnly
+        | DeviceStatus::AccessDenied => Color::from_rgb8(0xff, 0x38, 0x4A),
+        DeviceStatus::LaggyIMU => Color::from_rgb8(0xff, 0xe3, 0x3c),
+        DeviceStatus::Healthy => Color::from_rgb8(0x3d, 0xff, 0x81),
+    }
+}
+
+fn battery_text<'a>(battery: Battery) -> Container<'a, Message> {
+    container(text(format!("{battery:?}"))).style(match battery {
+        Battery::Empty | Battery::Critical => style::text_orange,
+        Battery::Low => style::text_yellow,
+        Battery::Medium | Battery::Full => style::text_green,
+    })
+}
+
 fn single_box_view<'a>(
     status: &joycon::Status,
-    svg_handler: &joycon::Svg,
-    needles: &'a [Needle],
-    scale: f64,
-    mount_rot: i32,
+    svg_handler: &svg::Svg,
+    history: Option<&'a YawHistory>,
+    settings: &WranglerSettings,
+    body_part: Option<Option<&String>>,
+    raw_shown: bool,
 ) -> Column<'a, Message> {
     let sn = status.serial_number.clone();
+    let scale = settings.joycon_scale_get(&sn);
+    let yaw_scale = settings.joycon_yaw_scale_get(&sn);
+    let mount_rot = settings.joycon_rotation_get(&sn);
+    let data = settings.joycon_data_get(&sn);
+    let log_raw = settings.joycon_log_raw_get(&sn);
 
     let buttons = Row::new()
         .spacing(10)
@@ -412,60 +2631,127 @@ fn single_box_view<'a>(
         .width(Length::Fixed(130.0));
 
     let rot = status.rotation;
-    let values = Row::with_children(
-        [("Roll", rot.0), ("Pitch", rot.1), ("Yaw", -rot.2)]
+    let euler = [("Roll", rot.0), ("Pitch", rot.1), ("Yaw", -rot.2)];
+    let lines: Vec<String> = match settings.angle_display {
+        AngleDisplay::Degrees => euler
             .iter()
-            .map(|(name, val)| {
-                let ival = (*val as i32).rem_euclid(360) as usize;
-                let needle = needles.get(ival).unwrap_or_else(|| &needles[0]);
-
-                Column::new()
-                    .push(text(name))
-                    .push(
-                        canvas(needle)
-                            .width(Length::Fixed(25.0))
-                            .height(Length::Fixed(25.0)),
-                    )
-                    .push(text(format!("{ival}")))
-                    .spacing(10)
-                    .align_items(Alignment::Center)
-                    .width(Length::Fill)
-                    .into()
-            })
+            .map(|(name, val)| format!("{name}: {}", (*val as i32).rem_euclid(360)))
             .collect(),
-    );
+        AngleDisplay::Radians => euler
+            .iter()
+            .map(|(name, val)| format!("{name}: {:.3}", val.to_radians()))
+            .collect(),
+        AngleDisplay::Quaternion => ["w", "x", "y", "z"]
+            .iter()
+            .zip(status.quaternion)
+            .map(|(name, val)| format!("{name}: {val:.4}"))
+            .collect(),
+    };
+    let angles = Column::with_children(
+        lines
+            .into_iter()
+            .map(|line| text(line).size(14).into())
+            .collect(),
+    )
+    .spacing(2);
+    let values = Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push(
+            canvas(Gimbal::new(rot))
+                .width(Length::Fixed(60.0))
+                .height(Length::Fixed(60.0)),
+        )
+        .push(angles);
 
-    let circle = circle(
-        8.0,
-        match status.status {
-            DeviceStatus::Disconnected | DeviceStatus::NoIMU => Color::from_rgb8(0xff, 0x38, 0x4A),
-            DeviceStatus::LaggyIMU => Color::from_rgb8(0xff, 0xe3, 0x3c),
-            DeviceStatus::Healthy => Color::from_rgb8(0x3d, 0xff, 0x81),
-        },
-    );
+    // Drift shows up as a slope, easier to spot than a slowly changing number. Large boxes
+    // have room for it below the rotation, at full width.
+    let (_, _, graph_height) = box_metrics(settings.box_size);
+    let graph = history.filter(|_| graph_height > 0.0).map(|history| {
+        canvas(history)
+            .width(Length::Fill)
+            .height(Length::Fixed(graph_height))
+    });
+    let mut values = Column::new().spacing(10).push(values);
+    let mut graph_below = None;
+    match (graph, settings.box_size) {
+        (Some(graph), BoxSize::Large) => graph_below = Some(graph),
+        (Some(graph), _) => values = values.push(graph),
+        (None, _) => {}
+    }
+
+    let circle = circle(8.0, status_color(status.status));
 
     let top = Row::new()
         .spacing(5)
         .push(circle)
         .push(left)
         .push(values)
-        .height(Length::Fixed(150.0));
+        .height(Length::Fixed(130.0));
 
-    let battery_text =
-        container(text(format!("{:?}", status.battery))).style(match status.battery {
-            Battery::Empty | Battery::Critical => style::text_orange,
-            Battery::Low => style::text_yellow,
-            Battery::Medium | Battery::Full => style::text_green,
-        });
+    let battery_text = battery_text(status.battery);
 
     let status_text = container(text(format!("{}", status.status))).style(match status.status {
-        DeviceStatus::Disconnected | DeviceStatus::NoIMU => style::text_orange,
+        DeviceStatus::Disconnected
+        | DeviceStatus::NoIMU
+        | DeviceStatus::SetupFailed
+        | DeviceStatus::ButtonsOnly
+        | DeviceStatus::AccessDenied => style::text_orange,
         DeviceStatus::LaggyIMU => style::text_yellow,
         DeviceStatus::Healthy => style::text_green,
     });
 
+    let status_row = Row::new()
+        .push(text("Status: "))
+        .push(status_text)
+        .push(text(format!(
+            " ({} Hz{})",
+            status.report_rate,
+            if status.paused { ", paused" } else { "" }
+        )));
+    if settings.box_size == BoxSize::Small {
+        return Column::new()
+            .spacing(5)
+            .push(top)
+            .push(text(&status.name).size(14))
+            .push(status_row)
+            .push(Row::new().push(text("Battery level: ")).push(battery_text));
+    }
+
+    let alias = if status.name == status.serial_number {
+        ""
+    } else {
+        status.name.as_str()
+    };
+    let alias_sn = sn.clone();
+    let data_sn = sn.clone();
+    let log_sn = sn.clone();
+    let yaw_sn = sn.clone();
     let bottom = Column::new()
         .spacing(10)
+        .push(
+            text_input(&status.serial_number, alias)
+                .on_input(move |value| Message::JoyconAlias(alias_sn.clone(), value))
+                .padding(5),
+        )
+        .push(
+            Row::new()
+                .align_items(Alignment::Center)
+                .push(text("Send: "))
+                .push(
+                    pick_list(&TrackerData::ALL[..], Some(data), move |data| {
+                        Message::JoyconData(data_sn.clone(), data)
+                    })
+                    .padding(5),
+                ),
+        )
+        .push(
+            checkbox("Log raw IMU to CSV", log_raw, move |log_raw| {
+                Message::JoyconLogRaw(log_sn.clone(), log_raw)
+            })
+            .size(16.0)
+            .text_size(14.0),
+        )
         .push(
             slider(0.8..=1.2, scale, move |c| {
                 Message::JoyconScale(sn.clone(), c)
@@ -474,13 +2760,49 @@ fn single_box_view<'a>(
         )
         .push(text(format!("Rotation scale ratio: {scale:.3}")))
         .push(
-            text(
-                "Change this if the tracker in VR moves less or more than your irl Joycon. Higher value = more movement.",
-            )
+            slider(0.8..=1.2, yaw_scale, move |c| {
+                Message::JoyconYawScale(yaw_sn.clone(), c)
+            })
+            .step(0.001),
+        )
+        .push(text(format!("Yaw scale ratio: {yaw_scale:.3}")))
+        .push(gyro_bias_row(status, settings.joycon_gyro_bias_get(&status.serial_number)))
+        .push(raw_values(status, raw_shown))
+        // The scale explanation makes way for advice when something is wrong.
+        .push(
+            text(status.status.hint().unwrap_or(
+                "Change these if the tracker in VR moves less or more than your irl Joycon. Higher value = more movement. Yaw only scales turning, for straps that twist.",
+            ))
             .size(14),
         )
         .push(Row::new().push(text("Battery level: ")).push(battery_text))
-        .push(Row::new().push(text("Status: ")).push(status_text));
+        .push(text(uptime_text(status)).size(14))
+        .push(body_part_text(body_part))
+        .push(
+            status_row
+                .push(horizontal_space(Length::Fill))
+                .push(text(sticks_text(&status.sticks))),
+        );
+
+    let mut column = Column::new().spacing(10).push(top);
+    if let Some(graph) = graph_below {
+        column = column.push(graph);
+    }
+    column.push(bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Column::new().spacing(10).push(top).push(bottom)
+    #[test]
+    fn grid_layout_fills_the_width() {
+        assert_eq!(grid_layout(1030, 230.0), (4, 230.0));
+        assert_eq!(grid_layout(1100, 230.0), (4, 247.5));
+    }
+    #[test]
+    fn grid_layout_one_column() {
+        assert_eq!(grid_layout(400, 230.0), (1, 350.0));
+        assert_eq!(grid_layout(200, 230.0), (1, 200.0));
+    }
 }
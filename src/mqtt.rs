@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use slimevr_wrangler_core::events::Event;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::joycon::{Battery, DeviceStatus, Remote, Status};
+use crate::settings::{self, WranglerSettings};
+
+/// Seconds the broker waits for a packet before dropping the connection.
+const KEEP_ALIVE: u16 = 60;
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// MQTT 3.1.1 variable length, 7 bits per byte with the high bit meaning more follow.
+fn remaining_length(mut len: usize, buf: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+        if len == 0 {
+            return;
+        }
+    }
+}
+
+fn string(s: &str, buf: &mut Vec<u8>) {
+    buf.extend((s.len() as u16).to_be_bytes());
+    buf.extend(s.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = vec![header];
+    remaining_length(body.len(), &mut buf);
+    buf.extend(body);
+    buf
+}
+
+/// Only what publishing needs: connect, QoS 0 publish and keep alive.
+struct Client {
+    stream: TcpStream,
+    topic: String,
+}
+impl Client {
+    fn connect(settings: &WranglerSettings) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(&settings.mqtt_broker)?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let topic = settings.mqtt_topic.trim_end_matches('/').to_owned();
+
+        let mut flags = 0x02; // Clean session.
+        flags |= 0x04 | 0x20; // Retained will message, so subscribers see Wrangler going away.
+                              // MQTT 3.1.1 only allows a password together with a username.
+        let password = settings
+            .mqtt_password
+            .as_ref()
+            .filter(|_| settings.mqtt_username.is_some());
+        if settings.mqtt_username.is_some() {
+            flags |= 0x80;
+        }
+        if password.is_some() {
+            flags |= 0x40;
+        }
+        let mut body = vec![];
+        string("MQTT", &mut body);
+        body.push(4); // Protocol level 3.1.1.
+        body.push(flags);
+        body.extend(KEEP_ALIVE.to_be_bytes());
+        string(
+            &format!("slimevr-wrangler-{}", std::process::id()),
+            &mut body,
+        );
+        string(&format!("{topic}/online"), &mut body);
+        string("false", &mut body);
+        if let Some(username) = &settings.mqtt_username {
+            string(username, &mut body);
+        }
+        if let Some(password) = password {
+            string(password, &mut body);
+        }
+        stream.write_all(&packet(0x10, &body))?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("broker refused the connection (code {})", connack[3]),
+            ));
+        }
+        let mut client = Self { stream, topic };
+        client.publish("online", "true", true)?;
+        Ok(client)
+    }
+
+    /// Publishes under the configured topic, at QoS 0.
+    fn publish(&mut self, subtopic: &str, payload: &str, retain: bool) -> io::Result<()> {
+        let mut body = vec![];
+        string(&format!("{}/{subtopic}", self.topic), &mut body);
+        body.extend(payload.as_bytes());
+        self.stream
+            .write_all(&packet(0x30 | u8::from(retain), &body))
+    }
+
+    /// Nothing else comes from the broker without subscriptions, so the next packet read is the
+    /// PINGRESP. A broker that doesn't answer within the read timeout counts as gone.
+    fn ping(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[0xC0, 0x00])?;
+        let mut pingresp = [0; 2];
+        self.stream.read_exact(&mut pingresp)?;
+        if pingresp != [0xD0, 0x00] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "broker didn't answer the ping",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn tracker_json(status: &Status) -> String {
+    json!({
+        "name": status.name,
+        "battery": format!("{:?}", status.battery),
+        "status": status.status.to_string(),
+    })
+    .to_string()
+}
+
+fn event(name: &str, status: &Status) -> String {
+    json!({
+        "event": name,
+        "serial_number": status.serial_number,
+        "name": status.name,
+    })
+    .to_string()
+}
+
+fn low_battery(battery: Battery) -> bool {
+    matches!(battery, Battery::Empty | Battery::Critical | Battery::Low)
+}
+
+/// Publishes a tracker's state if it changed, and the events it went through since it was
+/// last seen. Tracker state is retained, so automations see it right after subscribing,
+/// events like a battery running low are not.
+fn publish_tracker(
+    client: &mut Client,
+    last: &mut HashMap<String, Status>,
+    status: &Status,
+    with_events: bool,
+) -> io::Result<()> {
+    let previous = last.get(&status.serial_number);
+    if previous.map(tracker_json) != Some(tracker_json(status)) {
+        client.publish(
+            &format!("trackers/{}", status.serial_number),
+            &tracker_json(status),
+            true,
+        )?;
+    }
+    if with_events {
+        let was_connected = previous.map_or(false, |p| p.status != DeviceStatus::Disconnected);
+        let connected = status.status != DeviceStatus::Disconnected;
+        if connected != was_connected {
+            let name = if connected {
+                "connected"
+            } else {
+                "disconnected"
+            };
+            client.publish("events", &event(name, status), false)?;
+        }
+        if low_battery(status.battery) && !previous.map_or(false, |p| low_battery(p.battery)) {
+            client.publish("events", &event("low_battery", status), false)?;
+        }
+    }
+    last.insert(status.serial_number.clone(), status.clone());
+    Ok(())
+}
+
+/// Publishes a tracker that went away as disconnected, and forgets it.
+fn remove_tracker(
+    client: &mut Client,
+    last: &mut HashMap<String, Status>,
+    serial_number: &str,
+) -> io::Result<()> {
+    let Some(previous) = last.get(serial_number) else {
+        return Ok(());
+    };
+    let status = Status {
+        status: DeviceStatus::Disconnected,
+        ..previous.clone()
+    };
+    publish_tracker(client, last, &status, true)?;
+    last.remove(serial_number);
+    Ok(())
+}
+
+/// Publishes everything in the snapshot that differs from what was published, after
+/// connecting or when events were missed.
+fn publish_snapshot(
+    client: &mut Client,
+    remote: &Remote,
+    last: &mut Option<HashMap<String, Status>>,
+) -> io::Result<()> {
+    let snapshot = remote.snapshot();
+    client.publish("server", &snapshot.server.to_string(), true)?;
+    // No events for the trackers that were there when connecting, only their state.
+    let with_events = last.is_some();
+    let last = last.get_or_insert_with(HashMap::new);
+    for status in &snapshot.trackers {
+        publish_tracker(client, last, status, with_events)?;
+    }
+    let gone: Vec<_> = last
+        .keys()
+        .filter(|serial| {
+            !snapshot
+                .trackers
+                .iter()
+                .any(|t| &t.serial_number == *serial)
+        })
+        .cloned()
+        .collect();
+    for serial_number in gone {
+        remove_tracker(client, last, &serial_number)?;
+    }
+    Ok(())
+}
+
+fn run(remote: &Remote, settings: &settings::Handler) -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()?;
+    // Subscribed before taking the snapshot, so nothing falls between the two.
+    let mut events = remote.events().subscribe();
+    let mut client = Client::connect(&settings.load())?;
+    println!(
+        "[INFO] Publishing status to MQTT broker {}",
+        settings.load().mqtt_broker
+    );
+    let mut last = None;
+    publish_snapshot(&mut client, remote, &mut last)?;
+    let mut last_ping = Instant::now();
+    loop {
+        let until_ping = PING_INTERVAL.saturating_sub(last_ping.elapsed());
+        match runtime.block_on(tokio::time::timeout(until_ping, events.recv())) {
+            Ok(Ok(Event::ServerChanged(server))) => {
+                client.publish("server", &server.to_string(), true)?;
+            }
+            Ok(Ok(Event::DeviceAdded(status) | Event::DeviceChanged(status))) => {
+                let last = last.get_or_insert_with(HashMap::new);
+                publish_tracker(&mut client, last, &status, true)?;
+            }
+            Ok(Ok(Event::DeviceRemoved { serial_number })) => {
+                if let Some(last) = &mut last {
+                    remove_tracker(&mut client, last, &serial_number)?;
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(RecvError::Lagged(_))) => publish_snapshot(&mut client, remote, &mut last)?,
+            Ok(Err(RecvError::Closed)) => return Ok(()),
+            Err(_) => {
+                last_ping = Instant::now();
+                client.ping()?;
+            }
+        }
+    }
+}
+
+/// Publishes tracker status, battery and connection events to an MQTT broker, if enabled in
+/// settings. Reconnects when the broker goes away.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    if !settings.load().mqtt_enabled {
+        return;
+    }
+    if settings.load().offline {
+        println!("[INFO] Not connecting to the MQTT broker in offline mode.");
+        return;
+    }
+    thread::spawn(move || loop {
+        if let Err(e) = run(&remote, &settings) {
+            println!(
+                "\x1b[0;31m[ERROR]\x1b[0m MQTT broker {}: {e}",
+                settings.load().mqtt_broker
+            );
+        }
+        thread::sleep(RECONNECT_DELAY);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(len: usize) -> Vec<u8> {
+        let mut buf = vec![];
+        remaining_length(len, &mut buf);
+        buf
+    }
+    #[test]
+    fn remaining_length_bytes() {
+        assert_eq!(encoded(0), [0x00]);
+        assert_eq!(encoded(127), [0x7F]);
+        assert_eq!(encoded(128), [0x80, 0x01]);
+        assert_eq!(encoded(16_383), [0xFF, 0x7F]);
+        assert_eq!(encoded(16_384), [0x80, 0x80, 0x01]);
+        assert_eq!(encoded(268_435_455), [0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+}
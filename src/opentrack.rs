@@ -0,0 +1,53 @@
+use std::{net::UdpSocket, thread};
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::joycon::Remote;
+use crate::settings;
+
+/// opentrack's "UDP over network" input: x, y, z in centimeters, then yaw, pitch and roll in
+/// degrees, all little endian f64. The controller has no position, so that stays at 0.
+fn packet((roll, pitch, yaw): (f64, f64, f64)) -> [u8; 48] {
+    let mut buf = [0; 48];
+    for (chunk, value) in buf
+        .chunks_exact_mut(8)
+        .zip([0.0, 0.0, 0.0, yaw, pitch, roll])
+    {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    buf
+}
+
+/// Sends the rotation of the tracker picked in the settings to opentrack, for head tracking.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(s) => s,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not open socket for opentrack: {e}");
+            return;
+        }
+    };
+    thread::spawn(move || {
+        let mut rotations = remote.events().subscribe_rotations();
+        loop {
+            // Sent for every IMU report, opentrack smooths better with more of them.
+            let rotation = match rotations.blocking_recv() {
+                Ok(rotation) => rotation,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            let (serial_number, address, offline) = {
+                let s = settings.load();
+                (
+                    s.opentrack_tracker.clone(),
+                    s.opentrack_address.clone(),
+                    s.offline,
+                )
+            };
+            if offline || serial_number.as_deref() != Some(&*rotation.serial_number) {
+                continue;
+            }
+            socket.send_to(&packet(rotation.rotation), &address).ok();
+        }
+    });
+}
@@ -0,0 +1,140 @@
+#![cfg_attr(not(feature = "overlay"), allow(dead_code))]
+
+use std::{thread, time::Duration};
+
+use crate::joycon::{Battery, DeviceStatus, Remote, Status};
+use crate::settings;
+
+const WIDTH: usize = 128;
+/// Height of one tracker's row, its dot is as wide.
+const ROW: usize = 24;
+
+fn status_color(status: DeviceStatus) -> [u8; 3] {
+    match status {
+        DeviceStatus::Disconnected
+        | DeviceStatus::NoIMU
+        | DeviceStatus::SetupFailed
+        | DeviceStatus::ButtonsOnly
+        | DeviceStatus::AccessDenied => [0xff, 0x38, 0x4A],
+        DeviceStatus::LaggyIMU => [0xff, 0xe3, 0x3c],
+        DeviceStatus::Healthy => [0x3d, 0xff, 0x81],
+    }
+}
+
+/// How full the battery bar is, and its color.
+fn battery_bar(battery: Battery) -> (f32, [u8; 3]) {
+    match battery {
+        Battery::Empty => (0.05, [0xff, 0x38, 0x4A]),
+        Battery::Critical => (0.15, [0xff, 0x38, 0x4A]),
+        Battery::Low => (0.35, [0xff, 0xe3, 0x3c]),
+        Battery::Medium => (0.65, [0x3d, 0xff, 0x81]),
+        Battery::Full => (1.0, [0x3d, 0xff, 0x81]),
+    }
+}
+
+fn set(pixels: &mut [u8], x: usize, y: usize, rgb: [u8; 3]) {
+    let i = (y * WIDTH + x) * 4;
+    pixels[i..i + 3].copy_from_slice(&rgb);
+    pixels[i + 3] = 0xff;
+}
+
+/// RGBA pixels and height of the panel: a row per tracker with a dot colored like its status
+/// and a battery bar. Text would need a font renderer, the colors are enough at a glance.
+fn draw(trackers: &[Status]) -> (Vec<u8>, usize) {
+    let height = ROW * trackers.len().max(1);
+    let mut pixels = [0x20, 0x20, 0x20, 0xc0].repeat(WIDTH * height);
+    for (row, status) in trackers.iter().enumerate() {
+        let top = row * ROW;
+        let color = status_color(status.status);
+        for y in 0..ROW {
+            for x in 0..ROW {
+                let (dx, dy) = (x as f32 - 11.5, y as f32 - 11.5);
+                if dx * dx + dy * dy <= 64.0 {
+                    set(&mut pixels, x, top + y, color);
+                }
+            }
+        }
+        let (level, color) = battery_bar(status.battery);
+        let filled = ROW + 8 + ((WIDTH - ROW - 16) as f32 * level) as usize;
+        for y in top + 8..top + 16 {
+            for x in ROW + 8..WIDTH - 8 {
+                let rgb = if x < filled {
+                    color
+                } else {
+                    [0x50, 0x50, 0x50]
+                };
+                set(&mut pixels, x, y, rgb);
+            }
+        }
+    }
+    (pixels, height)
+}
+
+#[cfg(feature = "overlay")]
+fn run(remote: &Remote, settings: &settings::Handler) -> Result<(), String> {
+    use ovr_overlay::{pose::Matrix3x4, TrackedDeviceIndex};
+    use slimevr_wrangler_core::events::Event;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let err = |e: ovr_overlay::errors::EVROverlayError| format!("{e:?}");
+    let context = ovr_overlay::Context::init().map_err(|e| format!("{e:?}"))?;
+    let mut manager = context.overlay_mngr();
+    let handle = manager
+        .create_overlay("slimevr-wrangler.status", "SlimeVR Wrangler")
+        .map_err(err)?;
+    manager.set_width(handle, 0.06).map_err(err)?;
+    // Low in the left of the view, close enough to read without getting in the way.
+    let transform = Matrix3x4([
+        [1.0, 0.0, 0.0, -0.12],
+        [0.0, 1.0, 0.0, -0.1],
+        [0.0, 0.0, 1.0, -0.4],
+    ]);
+    manager
+        .set_transform_tracked_device_relative(handle, TrackedDeviceIndex::HMD, &transform)
+        .map_err(err)?;
+    manager.set_visibility(handle, true).map_err(err)?;
+    println!("[INFO] Showing tracker status in SteamVR");
+
+    // Subscribed before the first draw, so no change falls in between.
+    let mut events = remote.events().subscribe();
+    let mut redraw = true;
+    while settings.load().overlay_enabled {
+        if redraw {
+            let (mut pixels, height) = draw(&remote.snapshot().trackers);
+            manager
+                .set_raw_data(handle, &mut pixels, WIDTH, height, 4)
+                .map_err(err)?;
+        }
+        redraw = match events.blocking_recv() {
+            Ok(Event::DeviceAdded(_) | Event::DeviceChanged(_) | Event::DeviceRemoved { .. }) => {
+                true
+            }
+            Ok(_) => false,
+            Err(RecvError::Lagged(_)) => true,
+            Err(RecvError::Closed) => break,
+        };
+    }
+    manager.destroy_overlay(handle).map_err(err)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "overlay"))]
+fn run(_: &Remote, _: &settings::Handler) -> Result<(), String> {
+    Err("this build of Wrangler doesn't include the SteamVR overlay".into())
+}
+
+/// Shows a small panel with every tracker's status and battery in the headset while enabled
+/// in settings and SteamVR runs. Only built with the `overlay` feature.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    thread::spawn(move || loop {
+        if settings.load().overlay_enabled && slimevr_wrangler_core::process::steamvr_running() {
+            if let Err(e) = run(&remote, &settings) {
+                println!("\x1b[0;31m[ERROR]\x1b[0m SteamVR overlay: {e}");
+                if !cfg!(feature = "overlay") {
+                    return;
+                }
+            }
+        }
+        thread::sleep(Duration::from_secs(5));
+    });
+}
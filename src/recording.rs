@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use slimevr_wrangler_core::events;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::joycon::Remote;
+
+/// Time between BVH frames, which need a fixed rate. About as often as Joy-Cons report.
+const FRAME_TIME: Duration = Duration::from_millis(15);
+/// How often to check whether the recording was stopped while no rotations come in.
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Bvh,
+}
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Csv, Format::Bvh];
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Bvh => "bvh",
+        }
+    }
+}
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Format::Csv => "CSV",
+            Format::Bvh => "BVH",
+        })
+    }
+}
+
+type Rotation = (f64, f64, f64);
+
+/// Header and one row per IMU report, written as they come in.
+struct Csv<W>(W);
+impl<W: Write> Csv<W> {
+    fn header(&mut self) -> io::Result<()> {
+        writeln!(self.0, "time,serial_number,name,roll,pitch,yaw")
+    }
+    /// Time in seconds since the start.
+    fn row(
+        &mut self,
+        time: f64,
+        serial_number: &str,
+        name: &str,
+        rotation: Rotation,
+    ) -> io::Result<()> {
+        let (roll, pitch, yaw) = rotation;
+        writeln!(
+            self.0,
+            "{time:.4},{serial_number},\"{}\",{roll:.3},{pitch:.3},{yaw:.3}",
+            name.replace('"', "\"\"")
+        )
+    }
+}
+
+/// A root that stays put, with one joint per tracker that was connected when recording started.
+/// Trackers have no position, so the joints all sit on the root. The frame count comes first,
+/// so the frames are kept until the end.
+fn write_bvh(
+    file: &mut impl Write,
+    serials: &[String],
+    frames: &[Vec<Rotation>],
+) -> io::Result<()> {
+    writeln!(file, "HIERARCHY")?;
+    writeln!(file, "ROOT Wrangler")?;
+    writeln!(file, "{{")?;
+    writeln!(file, "\tOFFSET 0.0 0.0 0.0")?;
+    writeln!(
+        file,
+        "\tCHANNELS 6 Xposition Yposition Zposition Zrotation Yrotation Xrotation"
+    )?;
+    for serial_number in serials {
+        // Joint names can't have spaces.
+        writeln!(file, "\tJOINT {}", serial_number.replace(' ', "_"))?;
+        writeln!(file, "\t{{")?;
+        writeln!(file, "\t\tOFFSET 0.0 0.0 0.0")?;
+        // Same order the angles were taken apart in: yaw, then pitch, then roll.
+        writeln!(file, "\t\tCHANNELS 3 Zrotation Yrotation Xrotation")?;
+        writeln!(file, "\t\tEnd Site")?;
+        writeln!(file, "\t\t{{")?;
+        writeln!(file, "\t\t\tOFFSET 0.0 1.0 0.0")?;
+        writeln!(file, "\t\t}}")?;
+        writeln!(file, "\t}}")?;
+    }
+    writeln!(file, "}}")?;
+
+    writeln!(file, "MOTION")?;
+    writeln!(file, "Frames: {}", frames.len())?;
+    writeln!(file, "Frame Time: {:.6}", FRAME_TIME.as_secs_f64())?;
+    for rotations in frames {
+        let mut line = "0.0 0.0 0.0 0.0 0.0 0.0".to_owned();
+        for (roll, pitch, yaw) in rotations {
+            line += &format!(" {yaw:.3} {pitch:.3} {roll:.3}");
+        }
+        writeln!(file, "{line}")?;
+    }
+    Ok(())
+}
+
+fn record(remote: &Remote, format: Format, path: &Path, stop: &AtomicBool) -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()?;
+    let mut rotations = remote.events().subscribe_rotations();
+    let mut file = BufWriter::new(File::create(path)?);
+    let mut csv = Csv(&mut file);
+    if format == Format::Csv {
+        csv.header()?;
+    }
+    let mut names: HashMap<String, String> = HashMap::new();
+    let serials: Vec<String> = remote
+        .snapshot()
+        .trackers
+        .iter()
+        .map(|t| t.serial_number.clone())
+        .collect();
+    // Latest rotation of each tracker, a tracker that disconnects stays where it was last.
+    let mut latest = vec![(0.0, 0.0, 0.0); serials.len()];
+    let mut bvh_frames = vec![];
+
+    let start = Instant::now();
+    let mut next_frame = start;
+    while !stop.load(Ordering::Relaxed) {
+        match runtime.block_on(tokio::time::timeout(STOP_CHECK, rotations.recv())) {
+            Ok(Ok(events::Rotation {
+                serial_number,
+                rotation,
+                at,
+            })) => match format {
+                Format::Csv => {
+                    if !names.contains_key(&*serial_number) {
+                        for t in &remote.snapshot().trackers {
+                            names.insert(t.serial_number.clone(), t.name.clone());
+                        }
+                    }
+                    let name = names.get(&*serial_number).map_or("", String::as_str);
+                    let time = at.saturating_duration_since(start).as_secs_f64();
+                    csv.row(time, &serial_number, name, rotation)?;
+                }
+                Format::Bvh => {
+                    if let Some(i) = serials.iter().position(|s| **s == *serial_number) {
+                        latest[i] = rotation;
+                    }
+                }
+            },
+            Ok(Err(RecvError::Lagged(_))) | Err(_) => {}
+            Ok(Err(RecvError::Closed)) => break,
+        }
+        if format == Format::Bvh {
+            // Scheduled from the start, so frames don't drift from the clock.
+            while next_frame <= Instant::now() {
+                bvh_frames.push(latest.clone());
+                next_frame += FRAME_TIME;
+            }
+        }
+    }
+    if format == Format::Bvh {
+        write_bvh(&mut file, &serials, &bvh_frames)?;
+    }
+    file.flush()
+}
+
+/// Records the rotations of all trackers to a file on the desktop until stopped.
+pub struct Recording {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<io::Result<()>>,
+    path: PathBuf,
+}
+impl Recording {
+    pub fn start(remote: Remote, format: Format) -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = crate::bundle::folder().join(format!(
+            "slimevr-wrangler-recording-{secs}.{}",
+            format.extension()
+        ));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (stop, path) = (stop.clone(), path.clone());
+            thread::spawn(move || record(&remote, format, &path, &stop))
+        };
+        Self { stop, thread, path }
+    }
+
+    /// Stops recording and finishes the file, returning where it went.
+    pub async fn finish(self) -> Result<PathBuf, String> {
+        tokio::task::spawn_blocking(move || {
+            self.stop.store(true, Ordering::Relaxed);
+            match self.thread.join() {
+                Ok(Ok(())) => Ok(self.path),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("recording stopped unexpectedly".into()),
+            }
+        })
+        .await
+        .unwrap()
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::settings;
+
+/// Senders not heard from for this long are dropped, along with their socket and thread.
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Most remote tracker hosts relayed at once.
+const MAX_SOURCES: usize = 16;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Source {
+    socket: UdpSocket,
+    last_seen: Instant,
+    alive: Arc<AtomicBool>,
+}
+impl Drop for Source {
+    fn drop(&mut self) {
+        self.alive.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Gives a remote sender its own socket towards the server, so the server sees each remote
+/// tracker host as a separate device and its replies find their way back.
+fn upstream(listener: &UdpSocket, source: SocketAddr) -> std::io::Result<Source> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let reply_socket = socket.try_clone()?;
+    reply_socket.set_read_timeout(Some(POLL_INTERVAL))?;
+    let listener = listener.try_clone()?;
+    let alive = Arc::new(AtomicBool::new(true));
+    let thread_alive = alive.clone();
+    thread::spawn(move || {
+        let mut buf = [0; 1500];
+        while thread_alive.load(Ordering::Relaxed) {
+            if let Ok(len) = reply_socket.recv(&mut buf) {
+                listener.send_to(&buf[..len], source).ok();
+            }
+        }
+    });
+    Ok(Source {
+        socket,
+        last_seen: Instant::now(),
+        alive,
+    })
+}
+
+/// Only listed machines, or any on the local network when the list is empty.
+fn allowed(ip: IpAddr, allowlist: &[IpAddr]) -> bool {
+    if !allowlist.is_empty() {
+        return allowlist.contains(&ip);
+    }
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            // Unique local (fc00::/7) and link local (fe80::/10) addresses.
+            ip.is_loopback()
+                || (ip.segments()[0] & 0xfe00) == 0xfc00
+                || (ip.segments()[0] & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Accepts the SlimeVR protocol from trackers on other machines, for example another
+/// Wrangler on a PC near the playspace, and passes it on to the server set in the settings.
+pub fn spawn(settings: settings::Handler) {
+    let (enabled, port) = {
+        let s = settings.load();
+        (s.relay_enabled, s.relay_port)
+    };
+    if !enabled {
+        return;
+    }
+    let listener = match UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port))) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not start relay on port {port}: {e}");
+            return;
+        }
+    };
+    if let Err(e) = listener.set_read_timeout(Some(POLL_INTERVAL)) {
+        println!("\x1b[0;31m[ERROR]\x1b[0m Could not start relay on port {port}: {e}");
+        return;
+    }
+    println!("[INFO] Relaying trackers received on port {port} to SlimeVR Server");
+    thread::spawn(move || {
+        let mut sources: HashMap<SocketAddr, Source> = HashMap::new();
+        let mut logged_refusals: Vec<IpAddr> = vec![];
+        let mut buf = [0; 1500];
+        loop {
+            let received = listener.recv_from(&mut buf);
+            sources.retain(|source, s| {
+                let keep = s.last_seen.elapsed() < SOURCE_TIMEOUT;
+                if !keep {
+                    println!("[INFO] Stopped relaying trackers from {source}");
+                }
+                keep
+            });
+            let Ok((len, source)) = received else {
+                continue;
+            };
+            if !sources.contains_key(&source) {
+                if !allowed(source.ip(), &settings.load().relay_allowed) {
+                    // Log each refused machine once, not every packet.
+                    if !logged_refusals.contains(&source.ip()) {
+                        println!("[INFO] Not relaying {source}, it isn't in `relay_allowed`");
+                        logged_refusals.push(source.ip());
+                    }
+                    continue;
+                }
+                if sources.len() >= MAX_SOURCES {
+                    if !logged_refusals.contains(&source.ip()) {
+                        println!(
+                            "[INFO] Not relaying {source}, already relaying {MAX_SOURCES} machines"
+                        );
+                        logged_refusals.push(source.ip());
+                    }
+                    continue;
+                }
+                match upstream(&listener, source) {
+                    Ok(s) => {
+                        println!("[INFO] Relaying trackers from {source}");
+                        sources.insert(source, s);
+                    }
+                    Err(e) => {
+                        println!("\x1b[0;31m[ERROR]\x1b[0m Could not relay {source}: {e}");
+                        continue;
+                    }
+                }
+            }
+            let Some(s) = sources.get_mut(&source) else {
+                continue;
+            };
+            s.last_seen = Instant::now();
+            // Read every time, so a changed server address applies right away.
+            let server = settings.load().get_socket_address();
+            s.socket.send_to(&buf[..len], server).ok();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn allowed_local_network_by_default() {
+        assert!(allowed("192.168.1.20".parse().unwrap(), &[]));
+        assert!(allowed("10.0.0.5".parse().unwrap(), &[]));
+        assert!(allowed("fe80::1".parse().unwrap(), &[]));
+        assert!(!allowed("8.8.8.8".parse().unwrap(), &[]));
+        assert!(!allowed("2001:db8::1".parse().unwrap(), &[]));
+    }
+    #[test]
+    fn allowed_only_listed() {
+        let list = ["192.168.1.20".parse().unwrap()];
+        assert!(allowed("192.168.1.20".parse().unwrap(), &list));
+        assert!(!allowed("192.168.1.21".parse().unwrap(), &list));
+    }
+}
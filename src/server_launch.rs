@@ -0,0 +1,56 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[cfg(target_os = "windows")]
+fn default_paths() -> Vec<PathBuf> {
+    [
+        "C:\\Program Files (x86)\\SlimeVR Server",
+        "C:\\Program Files\\SlimeVR Server",
+    ]
+    .into_iter()
+    .map(|dir| Path::new(dir).join("slimevr.exe"))
+    .collect()
+}
+#[cfg(not(target_os = "windows"))]
+fn default_paths() -> Vec<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join("slimevr"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The SlimeVR Server launcher, either the one set in the settings or a default install.
+pub fn find_server(custom_path: Option<&Path>) -> Option<PathBuf> {
+    custom_path
+        .map(|path| {
+            // Accept the install folder as well as the executable itself.
+            if path.is_dir() {
+                path.join(if cfg!(target_os = "windows") {
+                    "slimevr.exe"
+                } else {
+                    "slimevr"
+                })
+            } else {
+                path.to_path_buf()
+            }
+        })
+        .into_iter()
+        .chain(default_paths())
+        .find(|path| path.is_file())
+}
+
+/// Starts the server next to Wrangler, it keeps running after Wrangler is closed.
+pub fn launch(path: &Path) -> io::Result<()> {
+    let mut command = Command::new(path);
+    // The server looks for its files relative to the working directory.
+    if let Some(dir) = path.parent() {
+        command.current_dir(dir);
+    }
+    command.spawn().map(drop)
+}
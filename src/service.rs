@@ -0,0 +1,233 @@
+use std::{env, path::PathBuf};
+
+fn service_args(profile: Option<&str>) -> Result<(PathBuf, Vec<String>), String> {
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let mut args = vec![];
+    if let Some(profile) = profile {
+        args.push("--profile".to_owned());
+        args.push(profile.to_owned());
+    }
+    Ok((exe, args))
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::OsString, path::PathBuf, sync::mpsc, time::Duration};
+
+    use windows_service::{
+        define_windows_service,
+        service::{
+            ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl,
+            ServiceExitCode, ServiceInfo, ServiceStartType, ServiceState, ServiceStatus,
+            ServiceType,
+        },
+        service_control_handler::{self, ServiceControlHandlerResult},
+        service_dispatcher,
+        service_manager::{ServiceManager, ServiceManagerAccess},
+    };
+
+    use crate::settings;
+
+    const NAME: &str = "SlimeVRWrangler";
+
+    pub fn install(exe: PathBuf, mut args: Vec<String>) -> Result<(), String> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .map_err(|e| e.to_string())?;
+        args.push("--service".to_owned());
+        let info = ServiceInfo {
+            name: NAME.into(),
+            display_name: "SlimeVR Wrangler".into(),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe,
+            launch_arguments: args.into_iter().map(OsString::from).collect(),
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager
+            .create_service(&info, ServiceAccess::START)
+            .map_err(|e| e.to_string())?;
+        service.start::<OsString>(&[]).map_err(|e| e.to_string())
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .map_err(|e| e.to_string())?;
+        let service = manager
+            .open_service(
+                NAME,
+                ServiceAccess::STOP | ServiceAccess::QUERY_STATUS | ServiceAccess::DELETE,
+            )
+            .map_err(|e| e.to_string())?;
+        service.stop().ok();
+        service.delete().map_err(|e| e.to_string())
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let handler = move |control| match control {
+            ServiceControl::Stop => {
+                stop_tx.send(()).ok();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let Ok(status) = service_control_handler::register(NAME, handler) else {
+            return;
+        };
+        let set_state = |state, accept| {
+            status
+                .set_service_status(ServiceStatus {
+                    service_type: ServiceType::OWN_PROCESS,
+                    current_state: state,
+                    controls_accepted: accept,
+                    exit_code: ServiceExitCode::Win32(0),
+                    checkpoint: 0,
+                    wait_hint: Duration::default(),
+                    process_id: None,
+                })
+                .ok();
+        };
+        set_state(ServiceState::Running, ServiceControlAccept::STOP);
+
+        // Arguments given to the service at creation are also in env::args.
+        let args = crate::cli::Args::parse().unwrap_or_default();
+        let settings = settings::Handler::new(args.profile.as_deref(), &args.overrides);
+        std::thread::spawn(move || crate::headless::run(settings));
+
+        stop_rx.recv().ok();
+        set_state(ServiceState::Stopped, ServiceControlAccept::empty());
+    }
+
+    pub fn run() -> Result<(), String> {
+        service_dispatcher::start(NAME, ffi_service_main).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{fs, path::PathBuf, process::Command};
+
+    use directories::BaseDirs;
+
+    const UNIT: &str = "slimevr-wrangler.service";
+
+    fn unit_path() -> Result<PathBuf, String> {
+        BaseDirs::new()
+            .map(|dirs| dirs.config_dir().join("systemd").join("user").join(UNIT))
+            .ok_or_else(|| "Could not find home directory".into())
+    }
+
+    fn systemctl(args: &[&str]) -> Result<(), String> {
+        let status = Command::new("systemctl")
+            .arg("--user")
+            .args(args)
+            .status()
+            .map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("systemctl --user {} failed", args.join(" ")))
+        }
+    }
+
+    /// One word of a systemd command line. systemd expands `%` specifiers and `$` variables
+    /// even in quotes.
+    fn quote(arg: &str) -> String {
+        let escaped = arg
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$");
+        format!("\"{escaped}\"")
+    }
+
+    pub fn install(exe: PathBuf, args: Vec<String>) -> Result<(), String> {
+        let path = unit_path()?;
+        fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+        let unit = format!(
+            "[Unit]\n\
+            Description=SlimeVR Wrangler\n\
+            After=bluetooth.target\n\n\
+            [Service]\n\
+            ExecStart={} --headless {}\n\
+            Restart=on-failure\n\
+            RestartSec=5\n\n\
+            [Install]\n\
+            WantedBy=default.target\n",
+            quote(&exe.to_string_lossy()),
+            args.iter()
+                .map(|arg| quote(arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        fs::write(&path, unit).map_err(|e| e.to_string())?;
+        systemctl(&["daemon-reload"])?;
+        systemctl(&["enable", "--now", UNIT])?;
+        println!(
+            "[INFO] Installed {}. Run \"loginctl enable-linger\" to also start it without logging in.",
+            path.display()
+        );
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        systemctl(&["disable", "--now", UNIT]).ok();
+        fs::remove_file(unit_path()?).map_err(|e| e.to_string())?;
+        systemctl(&["daemon-reload"])
+    }
+
+    pub fn run() -> Result<(), String> {
+        Err("--service is only used on Windows, the systemd unit uses --headless".into())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn quote_for_systemd() {
+            assert_eq!(quote("player 2"), "\"player 2\"");
+            assert_eq!(quote("100% $HOME"), "\"100%% $$HOME\"");
+            assert_eq!(quote("a \"b\" \\c"), "\"a \\\"b\\\" \\\\c\"");
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    use std::path::PathBuf;
+
+    pub fn install(_exe: PathBuf, _args: Vec<String>) -> Result<(), String> {
+        Err("Services are not supported on this platform".into())
+    }
+    pub fn uninstall() -> Result<(), String> {
+        Err("Services are not supported on this platform".into())
+    }
+    pub fn run() -> Result<(), String> {
+        Err("Services are not supported on this platform".into())
+    }
+}
+
+/// Installs and starts a service running the headless core at boot.
+pub fn install(profile: Option<&str>) -> Result<(), String> {
+    let (exe, args) = service_args(profile)?;
+    platform::install(exe, args)
+}
+
+pub fn uninstall() -> Result<(), String> {
+    platform::uninstall()
+}
+
+/// Entry point when started by the Windows service manager.
+pub fn run() -> Result<(), String> {
+    platform::run()
+}
@@ -1,12 +1,18 @@
 #![allow(clippy::result_large_err)]
 
-use std::{fs, io, mem::take, path::PathBuf};
+use std::{
+    fs, io,
+    mem::take,
+    path::{Path, PathBuf},
+};
 
 use itertools::Itertools;
 use keyvalues_parser::Vdf;
 use regex::{Captures, Regex};
 use thiserror::Error;
 
+use crate::joycon::JoyconDesignType;
+
 fn check_valid(config: &Vdf) -> Result<(), BlacklistError> {
     config
         .value
@@ -26,27 +32,110 @@ fn get_blacklist<'a>(config: &'a Vdf<'a>) -> Option<&'a str> {
         .get_str()
 }
 
+/// Steam Input's Switch controller support. SteamVR input goes through Steam Input, so with
+/// this on SteamVR binds Joy-Cons and Pro controllers as gamepads, blacklist or not.
+const SWITCH_INPUT: &str = "SteamController_SwitchProSupport";
+
+fn get_switch_input<'a>(config: &'a Vdf<'a>) -> Option<&'a str> {
+    let mut obj = config.value.get_obj()?;
+    for key in ["Software", "Valve", "Steam"] {
+        obj = obj.get(key)?.get(0)?.get_obj()?;
+    }
+    obj.get(SWITCH_INPUT)?.get(0)?.get_str()
+}
+
 #[cfg(target_os = "windows")]
-fn get_steam_path() -> io::Result<PathBuf> {
-    let hklm = winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE);
-    hklm.open_subkey("SOFTWARE\\Wow6432Node\\Valve\\Steam")
-        .or_else(|_| hklm.open_subkey("SOFTWARE\\Valve\\Steam"))?
-        .get_value::<String, _>("InstallPath")
-        .map(PathBuf::from)
+fn get_steam_paths() -> Vec<PathBuf> {
+    use winreg::{enums::*, RegKey};
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    // SteamPath in HKCU follows the install actually in use, InstallPath in HKLM can be stale.
+    [
+        hkcu.open_subkey("SOFTWARE\\Valve\\Steam")
+            .and_then(|k| k.get_value::<String, _>("SteamPath")),
+        hklm.open_subkey("SOFTWARE\\Wow6432Node\\Valve\\Steam")
+            .and_then(|k| k.get_value::<String, _>("InstallPath")),
+        hklm.open_subkey("SOFTWARE\\Valve\\Steam")
+            .and_then(|k| k.get_value::<String, _>("InstallPath")),
+    ]
+    .into_iter()
+    .filter_map(Result::ok)
+    .map(PathBuf::from)
+    .chain([PathBuf::from("C:\\Program Files (x86)\\Steam")])
+    .collect()
+}
+#[cfg(target_os = "linux")]
+fn get_steam_paths() -> Vec<PathBuf> {
+    let Some(dirs) = directories::BaseDirs::new() else {
+        return vec![];
+    };
+    let home = dirs.home_dir();
+    // ~/.steam/steam and ~/.steam/root are symlinks to the install in use.
+    vec![
+        home.join(".steam").join("steam"),
+        home.join(".steam").join("root"),
+        dirs.data_dir().join("Steam"),
+        home.join(".local").join("share").join("Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/data/Steam"),
+    ]
+}
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn get_steam_paths() -> Vec<PathBuf> {
+    vec![]
+}
+
+/// Library folders listed by Steam. A library can be a former Steam install with its own config.
+fn get_library_paths(steam_path: &Path) -> Vec<PathBuf> {
+    let Ok(text) = fs::read_to_string(steam_path.join("steamapps").join("libraryfolders.vdf"))
+    else {
+        return vec![];
+    };
+    let Ok(vdf) = Vdf::parse(&text) else {
+        return vec![];
+    };
+    let Some(folders) = vdf.value.get_obj() else {
+        return vec![];
+    };
+    folders
+        .values()
+        .flatten()
+        .filter_map(|folder| folder.get_obj()?.get("path")?.get(0)?.get_str())
+        .map(|path| PathBuf::from(path.replace("\\\\", "\\")))
+        .collect()
 }
-#[cfg(not(target_os = "windows"))]
-fn get_steam_path() -> io::Result<PathBuf> {
-    Err(io::Error::from(io::ErrorKind::NotFound))
+
+/// All Steam config files found, the one of the Steam install in use first.
+pub fn get_steam_config_paths(custom_path: Option<&Path>) -> Vec<PathBuf> {
+    let installs = custom_path
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(get_steam_paths());
+    let mut paths: Vec<PathBuf> = vec![];
+    for install in installs {
+        let libraries = get_library_paths(&install);
+        for candidate in std::iter::once(install).chain(libraries) {
+            let config = candidate.join("config").join("config.vdf");
+            if !config.is_file() {
+                continue;
+            }
+            let config = config.canonicalize().unwrap_or(config);
+            if !paths.contains(&config) {
+                paths.push(config);
+            }
+        }
+    }
+    paths
 }
-fn get_steam_config_path() -> io::Result<PathBuf> {
-    let mut path = get_steam_path()?;
-    path.push("config");
-    path.push("config.vdf");
-    Ok(path)
+
+/// Steam writes its config on exit, overwriting any changes made while it was running.
+pub fn steam_running() -> bool {
+    slimevr_wrangler_core::process::running("steam")
 }
 
-fn read_config() -> io::Result<String> {
-    fs::read_to_string(get_steam_config_path()?)
+fn read_config(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path)
 }
 
 fn set_blacklist(
@@ -94,29 +183,79 @@ fn verify(config_text: &str, new_list: &str) -> Result<(), BlacklistError> {
     Err(BlacklistError::Update)
 }
 
-fn inner_save(new_text: &str) -> Result<(), BlacklistError> {
-    let path = get_steam_config_path()?;
+fn inner_save(path: &Path, new_text: &str) -> Result<(), BlacklistError> {
     fs::write(path, new_text)?;
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Whether Steam Input's Switch controller support is turned on in this config.
+pub fn switch_input_enabled(path: &Path) -> Result<bool, BlacklistError> {
+    let config_text = read_config(path)?;
+    let config = Vdf::parse(&config_text)?;
+    check_valid(&config)?;
+    // Missing means the user never touched the setting, and Steam leaves it off.
+    Ok(get_switch_input(&config).map_or(false, |v| v != "0"))
+}
+
+pub fn disable_switch_input(path: &Path) -> Result<(), BlacklistError> {
+    let config_text = read_config(path)?;
+    let config = Vdf::parse(&config_text)?;
+    check_valid(&config)?;
+
+    let re = Regex::new(&format!(r#"((?i)"{SWITCH_INPUT}"\s*)"[^"]*""#)).unwrap();
+    if re.find_iter(&config_text).count() != 1 {
+        return Err(BlacklistError::Regex);
+    }
+    let new_text = re.replace(&config_text, |caps: &Captures| {
+        format!(r#"{}"0""#, &caps[1])
+    });
+    if get_switch_input(&Vdf::parse(&new_text)?) != Some("0") {
+        return Err(BlacklistError::Update);
+    }
+    inner_save(path, &new_text)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Device {
     Joycon,
     SwitchPro,
 }
 
 impl Device {
+    pub const ALL: [Device; 2] = [Device::Joycon, Device::SwitchPro];
+
     pub fn ids(self) -> Vec<String> {
         match self {
             Device::Joycon => vec![
-                "0x057e/0x2006".into(),
-                "0x057e/0x2007".into(),
-                "0x057e/0x2008".into(),
+                "0x057e/0x2006".into(), // Left
+                "0x057e/0x2007".into(), // Right
+                "0x057e/0x2008".into(), // Both in a grip
+                "0x057e/0x200e".into(), // Charging grip
             ],
             Device::SwitchPro => vec!["0x057e/0x2009".into()],
         }
     }
+    pub fn from_design(design: JoyconDesignType) -> Self {
+        match design {
+            JoyconDesignType::Left | JoyconDesignType::Right => Device::Joycon,
+            JoyconDesignType::Pro => Device::SwitchPro,
+        }
+    }
+    /// The device types to blacklist for the controllers the user has connected before.
+    /// Falls back to all supported types before any controller has been seen.
+    pub fn for_designs(designs: impl IntoIterator<Item = JoyconDesignType>) -> Vec<Self> {
+        let devices: Vec<_> = designs
+            .into_iter()
+            .map(Self::from_design)
+            .unique()
+            .collect();
+        if devices.is_empty() {
+            Self::ALL.to_vec()
+        } else {
+            devices
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -125,25 +264,36 @@ pub struct Blacklist {
 }
 
 impl Blacklist {
-    pub fn add_all(&mut self) {
-        self.add(Device::Joycon);
-        self.add(Device::SwitchPro);
+    /// Returns the ids that weren't in the list yet.
+    pub fn add_all(&mut self, devices: &[Device]) -> Vec<String> {
+        devices
+            .iter()
+            .flat_map(|device| self.add(*device))
+            .collect()
     }
     pub fn has(&self, device: Device) -> bool {
         device.ids().iter().all(|d| self.devices.contains(d))
     }
-    pub fn add(&mut self, device: Device) {
+    /// Returns the ids that weren't in the list yet.
+    pub fn add(&mut self, device: Device) -> Vec<String> {
+        let added: Vec<_> = device
+            .ids()
+            .into_iter()
+            .filter(|id| !self.devices.contains(id))
+            .collect();
         self.devices = take(&mut self.devices)
             .into_iter()
-            .chain(device.ids().into_iter())
+            .chain(added.iter().cloned())
             .unique()
             .collect();
+        added
+    }
+    /// Removes these ids, and leaves the entries the user or other programs added.
+    pub fn remove_ids(&mut self, ids: &[String]) {
+        self.devices.retain(|d| !ids.contains(d));
     }
-    /*pub fn remove(&mut self, device: Device) {
-        self.devices.retain(|d| !device.ids().contains(d))
-    }*/
-    pub fn read() -> Result<Self, BlacklistError> {
-        let config_text = read_config()?;
+    pub fn read(path: &Path) -> Result<Self, BlacklistError> {
+        let config_text = read_config(path)?;
         let config = Vdf::parse(&config_text)?;
         check_valid(&config)?;
 
@@ -157,15 +307,15 @@ impl Blacklist {
             .unwrap_or_default();
         Ok(Self { devices })
     }
-    pub fn save(&self) -> Result<(), BlacklistError> {
-        let config_text = read_config()?;
+    pub fn save(&self, path: &Path) -> Result<(), BlacklistError> {
+        let config_text = read_config(path)?;
         let config = Vdf::parse(&config_text)?;
         check_valid(&config)?;
 
         let new_list = self.devices.join(",");
         let new_text = set_blacklist(&config_text, &config, &new_list)?;
         verify(&new_text, &new_list)?;
-        inner_save(&new_text)?;
+        inner_save(path, &new_text)?;
         Ok(())
     }
 }
@@ -1,11 +1,24 @@
-use std::{thread, time::Duration};
+use std::{
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
 
-use super::{Blacklist, BlacklistError, Device};
+use super::{
+    disable_switch_input, get_steam_config_paths, steam_running, switch_input_enabled, Blacklist,
+    BlacklistError, Device,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct BlacklistResult {
     pub info: String,
     pub fix_button: bool,
+    pub steam_running: bool,
+    /// The config was changed as asked.
+    pub updated: bool,
+    /// Ids the change added to the blacklist, undoing the fix removes only these.
+    pub added: Vec<String>,
 }
 impl BlacklistResult {
     pub fn visible(&self) -> bool {
@@ -15,44 +28,73 @@ impl BlacklistResult {
         Self {
             info: info.into(),
             fix_button: true,
+            steam_running: false,
+            updated: false,
+            added: vec![],
         }
     }
     pub fn info<S: Into<String>>(info: S) -> Self {
         Self {
             info: info.into(),
             fix_button: false,
+            steam_running: false,
+            updated: false,
+            added: vec![],
+        }
+    }
+    pub fn steam_running() -> Self {
+        Self {
+            info: "Steam is running and would undo changes to its config. Close Steam first."
+                .into(),
+            fix_button: false,
+            steam_running: true,
+            updated: false,
+            added: vec![],
         }
     }
 }
 
-fn inner_check() -> BlacklistResult {
-    let list = match Blacklist::read() {
-        Ok(l) => l,
-        Err(e) => {
-            match e {
-                BlacklistError::IO(_) | BlacklistError::Parse(_) => {
-                    println!("[INFO] Steam config - Could not open steam config file to check for controller blacklist.");
-                }
-                BlacklistError::Invalid => {
-                    println!("[WARNING] Steam config - File invalid.");
+fn inner_check(custom_path: Option<PathBuf>, devices: &[Device]) -> BlacklistResult {
+    let paths = get_steam_config_paths(custom_path.as_deref());
+    if paths.is_empty() {
+        println!("[INFO] Steam config - Could not find steam config file to check for controller blacklist.");
+        return BlacklistResult::default();
+    }
+    let mut fully_set = usize::MAX;
+    for path in &paths {
+        let list = match Blacklist::read(path) {
+            Ok(l) => l,
+            Err(e) => {
+                match e {
+                    BlacklistError::IO(_) | BlacklistError::Parse(_) => {
+                        println!("[INFO] Steam config - Could not open steam config file {} to check for controller blacklist.", path.display());
+                    }
+                    BlacklistError::Invalid => {
+                        println!("[WARNING] Steam config - File {} invalid.", path.display());
+                    }
+                    _ => {}
                 }
-                _ => {}
+                continue;
             }
-            return BlacklistResult::default();
-        }
-    };
-    let all = [Device::Joycon, Device::SwitchPro];
-    match all.iter().filter(|d| list.has(**d)).count() {
+        };
+        let count = devices.iter().filter(|d| list.has(**d)).count();
+        println!(
+            "[INFO] Steam config - {}: {count} of {} controller types blacklisted.",
+            path.display(),
+            devices.len()
+        );
+        fully_set = fully_set.min(count);
+    }
+    match fully_set {
         0 => {
-            println!("[INFO] Steam config - Blacklist does not contain either Pro controllers or all types of Joycon's.");
+            println!("[INFO] Steam config - Blacklist does not contain any of the controller types in use.");
             BlacklistResult::fix("Your steam config does not contain a controller blacklist. This will interfere with this app.")
         }
-        1 => {
-            println!(
-                "[INFO] Steam config - Blacklist not fully populated (Joycon's + Pro controller)."
-            );
+        x if x < devices.len() => {
+            println!("[INFO] Steam config - Blacklist not fully populated ({devices:?}).");
             BlacklistResult::fix("Your steam config blacklist does not contain all types of controllers supported by this app.")
         }
+        usize::MAX => BlacklistResult::default(),
         _ => {
             println!("[INFO] Steam config - Controller blacklist correctly set.");
             BlacklistResult::default()
@@ -60,46 +102,161 @@ fn inner_check() -> BlacklistResult {
     }
 }
 
-pub async fn check_blacklist() -> BlacklistResult {
-    tokio::task::spawn_blocking(inner_check).await.unwrap()
+pub async fn check_blacklist(
+    custom_path: Option<PathBuf>,
+    devices: Vec<Device>,
+) -> BlacklistResult {
+    tokio::task::spawn_blocking(move || inner_check(custom_path, &devices))
+        .await
+        .unwrap()
 }
-fn inner_update() -> BlacklistResult {
-    let mut list = match Blacklist::read() {
-        Ok(l) => l,
-        Err(_) => {
-            return BlacklistResult::info("Couldn't update steam controller blacklist.");
-        }
+/// Applies `change` to the blacklist in every Steam config, it returns the ids it added.
+fn inner_update(
+    custom_path: Option<PathBuf>,
+    change: &dyn Fn(&mut Blacklist) -> Vec<String>,
+) -> BlacklistResult {
+    let paths = get_steam_config_paths(custom_path.as_deref());
+    if paths.is_empty() {
+        return BlacklistResult::info("Couldn't update steam controller blacklist.");
+    }
+    if steam_running() {
+        println!("[INFO] Steam config - Steam is running, not changing the config.");
+        return BlacklistResult::steam_running();
+    }
+    let mut result = BlacklistResult {
+        updated: true,
+        ..BlacklistResult::info("Steam controller blacklist updated. Please restart computer (or at least Steam and this app).")
     };
-    list.add_all();
-    match list.save() {
-        Ok(_) => {
-            BlacklistResult::info("Steam controller blacklist updated. Please restart computer (or at least Steam and this app).")
-        },
-        Err(e) => {
+    for path in paths {
+        let mut list = match Blacklist::read(&path) {
+            Ok(l) => l,
+            Err(_) => {
+                return BlacklistResult::info("Couldn't update steam controller blacklist.");
+            }
+        };
+        let added = change(&mut list);
+        if let Err(e) = list.save(&path) {
             match e {
                 BlacklistError::Parse(_) | BlacklistError::Invalid => {
                     println!("[INFO] Steam config - Could not open or parse config file to check for controller blacklist.");
-                },
+                }
                 BlacklistError::Regex => {
                     println!("[ERROR] Steam config - Could not parse blacklist with regex.");
-                },
+                }
                 BlacklistError::Update => {
                     println!("[ERROR] Steam config - Could not save config file.");
-                },
+                }
                 BlacklistError::IO(e) => {
                     println!("[ERROR] Could not read/write config file. Full Error:\n{e:?}");
-                },
+                }
+            }
+            // What was added to the other configs still needs undoing.
+            result = BlacklistResult {
+                added: std::mem::take(&mut result.added),
+                ..BlacklistResult::info(
+                    "Couldn't update steam controller blacklist. More info in console.",
+                )
+            };
+            continue;
+        }
+        for id in added {
+            if !result.added.contains(&id) {
+                result.added.push(id);
             }
-            BlacklistResult::info("Couldn't update steam controller blacklist. More info in console.")
-        },
+        }
     }
+    result
+}
+
+pub async fn update_blacklist(
+    custom_path: Option<PathBuf>,
+    devices: Vec<Device>,
+) -> BlacklistResult {
+    tokio::task::spawn_blocking(move || {
+        thread::sleep(Duration::from_millis(500)); // Add delay so fixing message can be seen
+        inner_update(custom_path, &|list| list.add_all(&devices))
+    })
+    .await
+    .unwrap()
 }
 
-pub async fn update_blacklist() -> BlacklistResult {
-    tokio::task::spawn_blocking(|| {
+/// Removes the ids the fix added from the blacklist, so Steam Input can use the controllers
+/// again. Entries that were there before are left alone.
+pub async fn revert_blacklist(custom_path: Option<PathBuf>, added: Vec<String>) -> BlacklistResult {
+    tokio::task::spawn_blocking(move || {
         thread::sleep(Duration::from_millis(500)); // Add delay so fixing message can be seen
-        inner_update()
+        let result = inner_update(custom_path, &|list| {
+            list.remove_ids(&added);
+            vec![]
+        });
+        if result.steam_running {
+            // Only fixing can wait for Steam to close.
+            return BlacklistResult::info(result.info);
+        }
+        result
     })
     .await
     .unwrap()
 }
+
+fn inner_check_switch_input(custom_path: Option<PathBuf>) -> BlacklistResult {
+    let enabled = get_steam_config_paths(custom_path.as_deref())
+        .iter()
+        .any(|path| switch_input_enabled(path).unwrap_or(false));
+    if enabled {
+        println!("[INFO] Steam config - Switch controller support in Steam Input is on.");
+        BlacklistResult::fix("Steam Input's Switch controller support is on. SteamVR can take Joy-Cons away from this app through it, even with the blacklist set.")
+    } else {
+        BlacklistResult::default()
+    }
+}
+
+/// Checks for the Steam Input setting that lets SteamVR bind Joy-Cons, which the blacklist
+/// doesn't always stop.
+pub async fn check_switch_input(custom_path: Option<PathBuf>) -> BlacklistResult {
+    tokio::task::spawn_blocking(move || inner_check_switch_input(custom_path))
+        .await
+        .unwrap()
+}
+
+fn inner_disable_switch_input(custom_path: Option<PathBuf>) -> BlacklistResult {
+    if steam_running() {
+        println!("[INFO] Steam config - Steam is running, not changing the config.");
+        return BlacklistResult::info(BlacklistResult::steam_running().info);
+    }
+    for path in get_steam_config_paths(custom_path.as_deref()) {
+        if !switch_input_enabled(&path).unwrap_or(false) {
+            continue;
+        }
+        if let Err(e) = disable_switch_input(&path) {
+            println!(
+                "[ERROR] Steam config - Could not turn off Switch controller support in {}: {e}",
+                path.display()
+            );
+            return BlacklistResult::info(
+                "Couldn't turn off Switch controller support. Turn off \"Switch Configuration Support\" in Steam's controller settings instead.",
+            );
+        }
+    }
+    BlacklistResult::info("Switch controller support turned off. Please restart Steam and SteamVR.")
+}
+
+pub async fn turn_off_switch_input(custom_path: Option<PathBuf>) -> BlacklistResult {
+    tokio::task::spawn_blocking(move || inner_disable_switch_input(custom_path))
+        .await
+        .unwrap()
+}
+
+fn inner_config_modified(custom_path: Option<PathBuf>) -> Option<SystemTime> {
+    get_steam_config_paths(custom_path.as_deref())
+        .iter()
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+/// Last time any of the Steam config files was written, used to notice Steam rewriting them.
+pub async fn config_modified(custom_path: Option<PathBuf>) -> Option<SystemTime> {
+    tokio::task::spawn_blocking(move || inner_config_modified(custom_path))
+        .await
+        .unwrap()
+}
@@ -1,5 +1,9 @@
 mod backend;
 use backend::*;
+pub use backend::{
+    disable_switch_input, get_steam_config_paths, steam_running, switch_input_enabled, Blacklist,
+    BlacklistError, Device,
+};
 
 mod blacklist;
 pub use blacklist::*;
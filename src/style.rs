@@ -1,16 +1,69 @@
 use iced::{
+    theme::Palette,
     widget::{button, container},
     Color, Theme,
 };
 
-pub fn container_highlight(_theme: &Theme) -> container::Appearance {
-    container_base(Color::from_rgb8(0x66, 0x34, 0x99))
+use crate::settings::ColorTheme;
+
+/// Offered next to typing a color in the settings, the first is the default.
+pub const ACCENTS: [&str; 6] = [
+    "#663499", "#3465a4", "#1f8a70", "#c0392b", "#d35400", "#7f8c8d",
+];
+
+/// Parses a `#rrggbb` color.
+pub fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::from_rgb8(
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ))
+}
+
+/// The dark theme, or pure black for OLED screens where black pixels are off, with the accent
+/// color on buttons and highlights.
+pub fn theme(color_theme: ColorTheme, accent: &str) -> Theme {
+    Theme::custom(Palette {
+        background: match color_theme {
+            ColorTheme::Dark => Color::from_rgb8(0x26, 0x28, 0x29),
+            ColorTheme::Amoled => Color::BLACK,
+        },
+        primary: parse_color(accent).unwrap_or_else(|| Color::from_rgb8(0x66, 0x34, 0x99)),
+        ..Palette::DARK
+    })
 }
-pub fn container_info(_theme: &Theme) -> container::Appearance {
-    container_base(Color::from_rgb8(0x42, 0x44, 0x45))
+
+fn amoled(theme: &Theme) -> bool {
+    theme.palette().background == Color::BLACK
 }
-pub fn container_darker(_theme: &Theme) -> container::Appearance {
-    container_base(Color::from_rgb8(0x26, 0x28, 0x29))
+
+/// Darker for hovering, like the default purple.
+fn darken(color: Color) -> Color {
+    Color {
+        r: color.r * 0.8,
+        g: color.g * 0.8,
+        b: color.b * 0.8,
+        ..color
+    }
+}
+
+pub fn container_highlight(theme: &Theme) -> container::Appearance {
+    container_base(theme.palette().primary)
+}
+pub fn container_info(theme: &Theme) -> container::Appearance {
+    container_base(if amoled(theme) {
+        Color::from_rgb8(0x1a, 0x1a, 0x1a)
+    } else {
+        Color::from_rgb8(0x42, 0x44, 0x45)
+    })
+}
+pub fn container_darker(theme: &Theme) -> container::Appearance {
+    container_base(theme.palette().background)
 }
 fn container_base(bg: Color) -> container::Appearance {
     container::Appearance {
@@ -20,8 +73,12 @@ fn container_base(bg: Color) -> container::Appearance {
     }
 }
 
-pub fn item_normal(_theme: &Theme) -> container::Appearance {
-    item_base(Color::from_rgb8(0x36, 0x39, 0x3F))
+pub fn item_normal(theme: &Theme) -> container::Appearance {
+    item_base(if amoled(theme) {
+        Color::from_rgb8(0x12, 0x12, 0x12)
+    } else {
+        Color::from_rgb8(0x36, 0x39, 0x3F)
+    })
 }
 pub fn _item_special(_theme: &Theme) -> container::Appearance {
     item_base(Color::from_rgb8(0x3c, 0x38, 0x4A))
@@ -55,9 +112,9 @@ pub struct PrimaryButton;
 
 impl button::StyleSheet for PrimaryButton {
     type Style = Theme;
-    fn active(&self, _style: &Self::Style) -> button::Appearance {
+    fn active(&self, style: &Self::Style) -> button::Appearance {
         button::Appearance {
-            background: Color::from_rgb8(0x66, 0x34, 0x99).into(),
+            background: style.palette().primary.into(),
             border_radius: 3.0,
             text_color: Color::WHITE,
             ..button::Appearance::default()
@@ -66,7 +123,7 @@ impl button::StyleSheet for PrimaryButton {
 
     fn hovered(&self, style: &Self::Style) -> button::Appearance {
         button::Appearance {
-            background: Color::from_rgb8(0x50, 0x28, 0x82).into(),
+            background: darken(style.palette().primary).into(),
             text_color: Color::WHITE,
             ..self.active(style)
         }
@@ -95,3 +152,25 @@ impl button::StyleSheet for SettingsButton {
         }
     }
 }
+
+/// A button filled with a color to pick.
+pub struct Swatch(pub Color);
+
+impl button::StyleSheet for Swatch {
+    type Style = Theme;
+    fn active(&self, _style: &Self::Style) -> button::Appearance {
+        button::Appearance {
+            background: self.0.into(),
+            border_radius: 3.0,
+            ..button::Appearance::default()
+        }
+    }
+
+    fn hovered(&self, style: &Self::Style) -> button::Appearance {
+        button::Appearance {
+            border_width: 2.0,
+            border_color: Color::WHITE,
+            ..self.active(style)
+        }
+    }
+}
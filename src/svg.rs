@@ -7,22 +7,11 @@ use std::{
     },
 };
 
-static LEFT: &str = include_str!("../../assets/joycon-left.svg");
-static RIGHT: &str = include_str!("../../assets/joycon-right.svg");
-static PRO: &str = include_str!("../../assets/pro-controller.svg");
+use crate::joycon::{JoyconDesign, JoyconDesignType};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum JoyconDesignType {
-    Left,
-    Right,
-    Pro,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct JoyconDesign {
-    pub color: String,
-    pub design_type: JoyconDesignType,
-}
+static LEFT: &str = include_str!("../assets/joycon-left.svg");
+static RIGHT: &str = include_str!("../assets/joycon-right.svg");
+static PRO: &str = include_str!("../assets/pro-controller.svg");
 
 fn generate(design: &JoyconDesign, rotation: i32) -> Handle {
     let svg_code = match design.design_type {
@@ -0,0 +1,179 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+
+use crate::joycon::{self, Battery, DeviceStatus, ServerStatus, Status};
+use crate::settings;
+use slimevr_wrangler_core::events::Event as WranglerEvent;
+
+fn status_color(status: DeviceStatus) -> Color {
+    match status {
+        DeviceStatus::Disconnected
+        | DeviceStatus::NoIMU
+        | DeviceStatus::SetupFailed
+        | DeviceStatus::ButtonsOnly
+        | DeviceStatus::AccessDenied => Color::Red,
+        DeviceStatus::LaggyIMU => Color::Yellow,
+        DeviceStatus::Healthy => Color::Green,
+    }
+}
+
+fn battery_color(battery: Battery) -> Color {
+    match battery {
+        Battery::Empty | Battery::Critical => Color::Red,
+        Battery::Low => Color::Yellow,
+        Battery::Medium | Battery::Full => Color::Green,
+    }
+}
+
+fn draw(
+    out: &mut impl Write,
+    address: &str,
+    server: ServerStatus,
+    paused: bool,
+    waiting_for_server: bool,
+    statuses: &[Status],
+) -> io::Result<()> {
+    queue!(
+        out,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(ClearType::All),
+        Print("SlimeVR Wrangler"),
+        cursor::MoveToNextLine(2),
+        Print(format!("Server {address}: ")),
+        SetForegroundColor(if server == ServerStatus::Connected {
+            Color::Green
+        } else {
+            Color::Yellow
+        }),
+        Print(server.to_string()),
+        ResetColor,
+    )?;
+    if paused {
+        queue!(
+            out,
+            SetForegroundColor(Color::Yellow),
+            Print("  [PAUSED]"),
+            ResetColor
+        )?;
+    }
+    queue!(
+        out,
+        cursor::MoveToNextLine(2),
+        Print(format!(
+            "{:<24}{:<8}{:<16}{:<12}{:>6}{:>6}",
+            "Name", "Type", "Status", "Battery", "Rate", "Yaw"
+        )),
+        cursor::MoveToNextLine(1),
+    )?;
+    for status in statuses {
+        queue!(
+            out,
+            Print(format!(
+                "{:<24}{:<8}",
+                status.name,
+                format!("{:?}", status.design.design_type)
+            )),
+            SetForegroundColor(status_color(status.status)),
+            Print(format!("{:<16}", status.status.to_string())),
+            SetForegroundColor(battery_color(status.battery)),
+            Print(format!("{:<12}", format!("{:?}", status.battery))),
+            ResetColor,
+            Print(format!(
+                "{:>6}{:>6}",
+                status.report_rate,
+                (-status.rotation.2 as i32).rem_euclid(360)
+            )),
+            cursor::MoveToNextLine(1),
+        )?;
+    }
+    if statuses.is_empty() {
+        queue!(
+            out,
+            Print(if waiting_for_server {
+                "Waiting for SlimeVR Server before connecting controllers..."
+            } else {
+                "Searching for Joycon controllers..."
+            }),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    queue!(
+        out,
+        cursor::MoveToNextLine(1),
+        Print("[r] Yaw reset   [p] Pause/resume   [q] Quit"),
+    )?;
+    out.flush()
+}
+
+fn event_loop(out: &mut impl Write, settings: settings::Handler) -> io::Result<()> {
+    let wrapper = joycon::Wrapper::new(settings.clone());
+    let mut events = wrapper.events().subscribe();
+    crate::start_services(&wrapper, &settings);
+    let address = settings.load().get_socket_address().to_string();
+
+    let mut server = ServerStatus::Disconnected;
+    let mut statuses = vec![];
+    loop {
+        while let Ok(event) = events.try_recv() {
+            if event == WranglerEvent::Quit {
+                return Ok(());
+            }
+        }
+        if let Some(new) = wrapper.poll_server() {
+            server = new;
+        }
+        if let Some(mut new) = wrapper.poll_status() {
+            new.sort_by(|a, b| a.serial_number.cmp(&b.serial_number));
+            statuses = new;
+        }
+        let remote = wrapper.remote();
+        draw(
+            out,
+            &address,
+            server,
+            remote.paused(),
+            remote.waiting_for_server(),
+            &statuses,
+        )?;
+
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(())
+                    }
+                    KeyCode::Char('r') => wrapper.yaw_reset(),
+                    KeyCode::Char('p') => wrapper.set_paused(!wrapper.paused()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+pub fn run(settings: settings::Handler) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = event_loop(&mut out, settings);
+
+    queue!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    out.flush()?;
+    terminal::disable_raw_mode()?;
+    result
+}
@@ -1,28 +1,72 @@
+use std::{
+    env,
+    fmt::Display,
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::mpsc,
+    thread,
+};
+
+use regex::Regex;
 use self_update::{
-    backends::github, cargo_crate_version, errors::Error, update::ReleaseUpdate, version,
+    backends::github, errors::Error, update::Release, version, ArchiveKind, Extract,
 };
+use sha2::{Digest, Sha256};
 
-fn update_config() -> Result<Box<dyn ReleaseUpdate>, Error> {
-    github::Update::configure()
+/// Finds the newest release, skipping pre-releases (versions like `1.0.0-rc.1`) unless asked for.
+fn latest_release(prerelease: bool) -> Result<Release, Error> {
+    let releases = github::ReleaseList::configure()
         .repo_owner("carl-anders")
         .repo_name("slimevr-wrangler")
-        .bin_name("slimevr-wrangler")
-        .show_download_progress(true)
-        .show_output(true)
-        .current_version(cargo_crate_version!())
-        .no_confirm(true)
-        .build()
-}
-pub async fn check_updates() -> Option<String> {
-    tokio::task::spawn_blocking(|| {
-        if let Ok(conf) = update_config() {
-            if let Ok(release) = conf.get_latest_release() {
-                match version::bump_is_greater(env!("CARGO_PKG_VERSION"), &release.version) {
-                    Ok(new_version) if new_version => {
-                        return Some(release.version);
-                    }
-                    _ => {}
+        .build()?
+        .fetch()?;
+    releases
+        .into_iter()
+        .filter(|r| prerelease || !r.version.contains('-'))
+        .reduce(|newest, r| {
+            if version::bump_is_greater(&newest.version, &r.version).unwrap_or(false) {
+                r
+            } else {
+                newest
+            }
+        })
+        .ok_or_else(|| Error::Release("No releases found".into()))
+}
+#[derive(Debug, Clone)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub notes: String,
+    /// Whether the release publishes a checksum for this platform's download. Without one the
+    /// update can't be verified, and isn't installed.
+    pub checksum: bool,
+    /// The release shown to the user, installed as is when they confirm.
+    release: Release,
+}
+impl UpdateInfo {
+    /// Looks for a line like "Requires SlimeVR Server v0.6.1" in the release notes.
+    pub fn required_server(&self) -> Option<String> {
+        let re = Regex::new(r"(?i)requires\s+(?:the\s+)?slimevr\s+server\s+(v?\d+\.\d+(?:\.\d+)?)")
+            .unwrap();
+        re.captures(&self.notes).map(|caps| caps[1].to_owned())
+    }
+}
+
+pub async fn check_updates(prerelease: bool) -> Option<UpdateInfo> {
+    tokio::task::spawn_blocking(move || {
+        if let Ok(release) = latest_release(prerelease) {
+            match version::bump_is_greater(env!("CARGO_PKG_VERSION"), &release.version) {
+                Ok(new_version) if new_version => {
+                    return Some(UpdateInfo {
+                        checksum: find_asset(&release)
+                            .map_or(false, |a| checksum_asset(&release, &a.name).is_some()),
+                        version: release.version.clone(),
+                        notes: release.body.clone().unwrap_or_default(),
+                        release,
+                    });
                 }
+                _ => {}
             }
         }
         None
@@ -30,15 +74,170 @@ pub async fn check_updates() -> Option<String> {
     .await
     .unwrap()
 }
-pub fn update() {
-    if let Ok(conf) = update_config() {
-        match conf.update() {
-            Ok(_) => {
-                panic!("Update complete.");
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    Downloading { done: u64, total: Option<u64> },
+    Verifying,
+    Installing,
+    Failed(String),
+}
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Progress::Downloading {
+                done,
+                total: Some(total),
+            } if *total > 0 => write!(f, "Downloading update: {}%", done * 100 / total),
+            Progress::Downloading { done, .. } => {
+                write!(f, "Downloading update: {} kB", done / 1024)
             }
-            Err(e) => {
+            Progress::Verifying => f.write_str("Verifying update..."),
+            Progress::Installing => f.write_str("Checksum verified, installing update..."),
+            Progress::Failed(e) => write!(f, "Update failed: {e}"),
+        }
+    }
+}
+
+const BIN_NAME: &str = if cfg!(windows) {
+    "slimevr-wrangler.exe"
+} else {
+    "slimevr-wrangler"
+};
+
+/// The download for this platform, the `.sha256` published next to it has the target in its
+/// name too.
+fn find_asset(release: &Release) -> Option<self_update::update::ReleaseAsset> {
+    let target = self_update::get_target();
+    release
+        .assets
+        .iter()
+        .find(|a| a.name.contains(target) && !a.name.ends_with(".sha256"))
+        .or_else(|| release.assets.iter().find(|a| a.name == BIN_NAME))
+        .cloned()
+}
+
+fn http_get(url: &str) -> Result<reqwest::blocking::Response, String> {
+    reqwest::blocking::Client::new()
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/octet-stream")
+        .header(reqwest::header::USER_AGENT, "slimevr-wrangler")
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .map_err(|e| e.to_string())
+}
+
+fn download(url: &str, path: &Path, tx: &mpsc::Sender<Progress>) -> Result<(), String> {
+    let mut response = http_get(url)?;
+    let total = response.content_length();
+    let mut file = File::create(path).map_err(|e| e.to_string())?;
+    let mut buf = [0; 64 * 1024];
+    let mut done = 0;
+    loop {
+        let len = response.read(&mut buf).map_err(|e| e.to_string())?;
+        if len == 0 {
+            return Ok(());
+        }
+        file.write_all(&buf[..len]).map_err(|e| e.to_string())?;
+        done += len as u64;
+        tx.send(Progress::Downloading { done, total }).ok();
+    }
+}
+
+/// The `<asset>.sha256` file published with the release.
+fn checksum_asset(
+    release: &Release,
+    asset_name: &str,
+) -> Option<self_update::update::ReleaseAsset> {
+    let checksum_name = format!("{asset_name}.sha256");
+    release
+        .assets
+        .iter()
+        .find(|a| a.name == checksum_name)
+        .cloned()
+}
+
+/// Checks the downloaded file against the checksum published with the release.
+fn verify(release: &Release, asset_name: &str, path: &Path) -> Result<(), String> {
+    let checksum_asset =
+        checksum_asset(release, asset_name).ok_or("release has no published checksum")?;
+    let expected = http_get(&checksum_asset.download_url)?
+        .text()
+        .map_err(|e| e.to_string())?;
+    let expected = expected
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let actual = format!("{:x}", Sha256::digest(data));
+    if actual == expected {
+        Ok(())
+    } else {
+        Err("checksum does not match".into())
+    }
+}
+
+fn install(asset_name: &str, path: &Path, dir: &Path) -> Result<(), String> {
+    let binary: PathBuf = if asset_name.ends_with(".zip") {
+        Extract::from_source(path)
+            .archive(ArchiveKind::Zip)
+            .extract_file(dir, BIN_NAME)
+            .map_err(|e| e.to_string())?;
+        dir.join(BIN_NAME)
+    } else {
+        path.to_owned()
+    };
+    self_replace::self_replace(binary).map_err(|e| e.to_string())
+}
+
+fn relaunch() -> Result<(), String> {
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    process::Command::new(exe)
+        .args(env::args_os().skip(1))
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    process::exit(0);
+}
+
+fn inner_update(tx: &mpsc::Sender<Progress>, release: &Release) -> Result<(), String> {
+    let asset = find_asset(release).ok_or("no download found for this platform")?;
+
+    let dir = env::temp_dir().join("slimevr-wrangler-update");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(&asset.name);
+
+    tx.send(Progress::Downloading {
+        done: 0,
+        total: None,
+    })
+    .ok();
+    download(&asset.download_url, &path, tx)?;
+    tx.send(Progress::Verifying).ok();
+    verify(release, &asset.name, &path)?;
+    tx.send(Progress::Installing).ok();
+    install(&asset.name, &path, &dir)?;
+    fs::remove_dir_all(&dir).ok();
+    relaunch()
+}
+
+/// Downloads, verifies and installs the found release in the background, then restarts.
+pub struct Updater {
+    progress_rx: mpsc::Receiver<Progress>,
+}
+impl Updater {
+    pub fn start(info: UpdateInfo) -> Self {
+        let (tx, progress_rx) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(e) = inner_update(&tx, &info.release) {
                 println!("Update not successful.\n{e}");
+                tx.send(Progress::Failed(e)).ok();
             }
-        }
+        });
+        Self { progress_rx }
+    }
+    pub fn poll(&self) -> Option<Progress> {
+        self.progress_rx.try_iter().last()
     }
 }
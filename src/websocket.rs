@@ -0,0 +1,95 @@
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+};
+
+use tokio::sync::broadcast::error::RecvError;
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+
+use crate::api::is_local_origin;
+use crate::joycon::Remote;
+use crate::settings;
+
+/// Any web page could otherwise read the trackers' serial numbers and rotations, like with the
+/// control API.
+fn check_origin(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    let origin = request
+        .headers()
+        .get("Origin")
+        .map(|origin| origin.to_str().unwrap_or_default());
+    match origin {
+        Some(origin) if !is_local_origin(origin) => {
+            let mut error =
+                ErrorResponse::new(Some("Requests from web pages are not allowed".into()));
+            *error.status_mut() = StatusCode::FORBIDDEN;
+            Err(error)
+        }
+        _ => Ok(response),
+    }
+}
+
+fn serve(stream: TcpStream, remote: &Remote) {
+    // Subscribed before taking the snapshot, so nothing falls between the two.
+    let mut events = remote.events().subscribe();
+    let Ok(mut ws) = tungstenite::accept_hdr(stream, check_origin) else {
+        return;
+    };
+    let Ok(json) = serde_json::to_string(&*remote.snapshot()) else {
+        return;
+    };
+    if ws.write_message(Message::Text(json)).is_err() {
+        return;
+    }
+    loop {
+        let event = match events.blocking_recv() {
+            Ok(event) => event,
+            // Missed events are in the snapshot, start over from it.
+            Err(RecvError::Lagged(_)) => {
+                let Ok(json) = serde_json::to_string(&*remote.snapshot()) else {
+                    return;
+                };
+                if ws.write_message(Message::Text(json)).is_err() {
+                    return;
+                }
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let Ok(json) = serde_json::to_string(&event) else {
+            continue;
+        };
+        if ws.write_message(Message::Text(json)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Pushes the tracker snapshot and then every change to each connected WebSocket client, if
+/// enabled in settings.
+pub fn spawn(remote: Remote, settings: settings::Handler) {
+    let (enabled, port) = {
+        let s = settings.load();
+        (s.websocket_enabled, s.websocket_port)
+    };
+    if !enabled {
+        return;
+    }
+    let listener = match TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port))) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("\x1b[0;31m[ERROR]\x1b[0m Could not start WebSocket on port {port}: {e}");
+            return;
+        }
+    };
+    println!("[INFO] Status WebSocket listening on ws://127.0.0.1:{port}");
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            let remote = remote.clone();
+            thread::spawn(move || serve(stream, &remote));
+        }
+    });
+}